@@ -14,7 +14,6 @@ use std::time::Instant;
 use gl::types::*;
 
 use gui::elements::*;
-use gui::widget_parser::StoredCallback;
 use gui::*;
 use mecs::*;
 use tools::*;
@@ -47,7 +46,7 @@ extern "C" {
 struct Data {
     goat: i32,
     shown: String,
-    show_red: bool,
+    tab: usize,
     x: f32,
 }
 
@@ -65,43 +64,40 @@ impl GuiBuilder for Data {
                 text: self.shown.clone(),
                 font: "arial".to_owned(),
                 align: Align::from(HAlign::Left, VAlign::Center),
-                color: if self.show_red { Vec4::RED } else { Vec4::BLUE },
+                color: if self.tab == 0 { Vec4::RED } else { Vec4::BLUE },
                 ..Default::default()
             };
 
-            -GridLayout {
-                col_widths: vec![
-                    GuiDimension::Relative(1.0),
-                    GuiDimension::Units(130.0),
-                    GuiDimension::Relative(1.0),
-                    GuiDimension::Units(130.0),
-                    GuiDimension::Relative(1.0),
-                ],
-                row_heights: vec![GuiDimension::Default; 5],
+            -TabView {
+                tabs: vec![Tab::new("Buttons"), Tab::new("Slider")],
+                active: self.tab,
+                callback: self.make_callback2(|data, tabs: &TabView| data.tab = tabs.active),
                 ..Default::default()
             } << {
-                let text = format!("{}", self.x);
-                self.mybutton(text, false);
-                for i in 1..20 {
-                    let text = format!("{}", i + self.goat);
-                    self.mybutton(text, i == 13);
-                }
-                -Button {
-                    text: if self.show_red {
-                        "ON".to_owned()
-                    } else {
-                        "OFF".to_owned()
-                    },
-                    callback: self.make_callback1(|data| {
-                        data.show_red = !data.show_red;
-                    }),
+                -GridLayout {
+                    col_widths: vec![
+                        GuiDimension::Relative(1.0),
+                        GuiDimension::Units(130.0),
+                        GuiDimension::Relative(1.0),
+                        GuiDimension::Units(130.0),
+                        GuiDimension::Relative(1.0),
+                    ],
+                    row_heights: vec![GuiDimension::Default; 4],
                     ..Default::default()
+                } << {
+                    let text = format!("{}", self.x);
+                    self.mybutton(text, false);
+                    for i in 1..20 {
+                        let text = format!("{}", i + self.goat);
+                        self.mybutton(text, i == 13);
+                    }
                 };
-                -LinearBar {
-                    value: self.x,
-                    callback: self.make_callback2(|data, bar: &LinearBar| data.x = bar.value),
-                    ..Default::default()
-                }
+                -Padding::absolute(20.0)
+                    << -LinearBar {
+                        value: self.x,
+                        callback: self.make_callback2(|data, bar: &LinearBar| data.x = bar.value),
+                        ..Default::default()
+                    };
             };
         };
     }
@@ -142,8 +138,9 @@ fn main() {
         x: 0.1,
         goat: 0,
         shown: "hy ".to_owned(),
-        show_red: true,
+        tab: 0,
     });
+    w.set_render_mode(RenderMode::OnDemand);
     w.run();
 }
 
@@ -6,26 +6,82 @@ pub use self::align::VAlign;
 pub use self::callback::CallbackExecutor;
 pub use self::callback::GuiCallback;
 pub use self::context::GuiContext;
+pub use self::drag::DragPayload;
+pub use self::drag::DragPreview;
+pub use self::draw::CornerRadii;
 pub use self::draw::DrawBuilder;
+pub use self::draw::DrawFill;
+pub use self::draw::RetainedRenderSequence;
 pub use self::elements::gui_primitives;
+pub use self::elements::Anchor;
 pub use self::elements::Button;
 pub use self::elements::ButtonBckg;
+pub use self::elements::ColorPicker;
+pub use self::elements::ContextMenu;
 pub use self::elements::FixedPanel;
 pub use self::elements::FontSize;
 pub use self::elements::GridLayout;
+pub use self::elements::HueShape;
 pub use self::elements::Image;
 pub use self::elements::LinearBar;
 pub use self::elements::Lines;
+pub use self::elements::MenuBar;
+pub use self::elements::MenuItem;
+pub use self::elements::MenuItemKind;
 pub use self::elements::Overlay;
 pub use self::elements::Padding;
 pub use self::elements::PanelDirection;
+pub use self::elements::Plot;
+pub use self::elements::PlotKind;
+pub use self::elements::PlotSeries;
+pub use self::elements::RowHeight;
+pub use self::elements::SelectionMode;
+pub use self::elements::ShortcutArea;
 pub use self::elements::SkipCell;
+pub use self::elements::SortOrder;
 pub use self::elements::SplineEditor;
+pub use self::elements::SplitDirection;
+pub use self::elements::SplitPanel;
+pub use self::elements::SplitSide;
 pub use self::elements::Square;
+pub use self::elements::Stack;
+pub use self::elements::StackItem;
+pub use self::elements::Tab;
+pub use self::elements::TabView;
+pub use self::elements::Table;
+pub use self::elements::TableColumn;
+pub use self::elements::TableValue;
 pub use self::elements::Text;
+pub use self::elements::TreeNode;
+pub use self::elements::TreeView;
 pub use self::elements::VertLayout;
+pub use self::elements::VirtualList;
+pub use self::elements::VirtualListView;
+pub use self::event::EventKind;
+pub use self::event::EventPhase;
+pub use self::event::WidgetEvent;
 pub use self::gui_builder::GuiBuilder;
 pub use self::gui_builder::WidgetAdder;
+pub use self::history::GuiHistory;
+pub use self::history::History;
+pub use self::inspector::EventTrace;
+pub use self::inspector::InspectedWidget;
+pub use self::inspector::Inspector;
+pub use self::localization::Catalog;
+pub use self::localization::Localization;
+pub use self::localization::LocalizationError;
+pub use self::localization::PluralCategory;
+pub use self::path::FillRule;
+pub use self::path::LineCap;
+pub use self::path::LineJoin;
+pub use self::path::Path;
+pub use self::path::StrokeStyle;
+#[cfg(feature = "serializable")]
+pub use self::persistence::GuiPersistence;
+pub use self::scale::GuiScale;
+pub use self::scale::SetGuiScale;
+pub use self::shortcut::Shortcut;
+pub use self::widget::DimensionContext;
 pub use self::widget::EventResponse;
 pub use self::widget::GuiDimension;
 pub use self::widget::Widget;
@@ -36,15 +92,27 @@ pub use self::widget_layout_builder::WidgetLayoutBuilder;
 pub use self::widget_list::WidgetBuilderCache;
 pub use self::widget_list::WidgetList;
 pub use self::widget_parser::WidgetParser;
+pub use self::world_gui::WorldGui;
 
 #[macro_use]
 pub mod gui_builder;
+#[macro_use]
+pub mod localization;
 pub mod align;
 pub mod callback;
 pub mod context;
+pub mod drag;
 pub mod draw;
 pub mod elements;
+pub mod event;
+pub mod history;
+pub mod inspector;
+pub mod path;
+pub mod persistence;
+pub mod scale;
+pub mod shortcut;
 pub mod widget;
 pub mod widget_layout_builder;
 pub mod widget_list;
 pub mod widget_parser;
+pub mod world_gui;
@@ -1,39 +1,59 @@
-use gui::{GuiBuilder, Widget, WidgetParser};
+use gui::{DragPayload, DragPreview, EventResponse, GuiBuilder};
 use mecs::StaticWorld;
 use std::any::Any;
-use std::marker::PhantomData;
+use std::cell::Cell;
 use std::rc::Rc;
 
-#[derive(Default, Clone)]
-pub struct GuiCallback<T>
-where
-    T: Widget,
-{
-    pub(super) callback_id: Rc<Option<usize>>,
-    phantom: PhantomData<T>,
+type CallbackFn<T> = dyn Fn(&mut dyn Any, &T, &mut StaticWorld) -> EventResponse;
+
+/// A closure run with the gui data when a widget reports `T`
+///
+/// The callback lives inside the widget that got it, so it belongs to the context owning that
+/// widget and is dropped with it. Data of another gui type never reaches it.
+pub struct GuiCallback<T> {
+    callback: Option<Rc<CallbackFn<T>>>,
 }
 
-impl<T> GuiCallback<T>
-where
-    T: Widget,
-{
-    pub fn new(id: usize) -> GuiCallback<T> {
+impl<T> GuiCallback<T> {
+    pub fn new<D, F>(f: F) -> GuiCallback<T>
+    where
+        D: GuiBuilder + 'static,
+        F: 'static + Fn(&mut D, &T, &mut StaticWorld) -> EventResponse,
+    {
         GuiCallback {
-            callback_id: Rc::new(Some(id)),
-            phantom: Default::default(),
+            callback: Some(Rc::new(
+                move |data: &mut dyn Any, value: &T, world: &mut StaticWorld| match data
+                    .downcast_mut::<D>()
+                {
+                    Some(data) => f(data, value, world),
+                    None => EventResponse::Pass,
+                },
+            )),
+        }
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.callback.is_some()
+    }
+
+    fn call(&self, data: &mut dyn Any, value: &T, world: &mut StaticWorld) -> EventResponse {
+        match &self.callback {
+            Some(f) => f(data, value, world),
+            None => EventResponse::Pass,
         }
     }
 }
 
-impl<T> Drop for GuiCallback<T>
-where
-    T: Widget,
-{
-    fn drop(&mut self) {
-        if Rc::strong_count(&self.callback_id) == 1 {
-            if let Some(id) = *self.callback_id {
-                WidgetParser::remove_callback(id);
-            }
+impl<T> Default for GuiCallback<T> {
+    fn default() -> Self {
+        GuiCallback { callback: None }
+    }
+}
+
+impl<T> Clone for GuiCallback<T> {
+    fn clone(&self) -> Self {
+        GuiCallback {
+            callback: self.callback.clone(),
         }
     }
 }
@@ -41,25 +61,36 @@ where
 pub struct CallbackExecutor<'a> {
     gui_builder: &'a mut dyn Any,
     static_world: &'a mut StaticWorld,
+    response: &'a Cell<EventResponse>,
+    pub(super) started_drag: Option<(DragPayload, Option<DragPreview>)>,
 }
 
 impl<'a> CallbackExecutor<'a> {
-    pub fn execute<S>(&mut self, cb: &GuiCallback<S>, instance: &S)
-    where
-        S: Widget + 'static,
-    {
-        WidgetParser::execute_callback(cb, self.gui_builder, instance, self.static_world);
+    /// Runs `cb` with `value`, the context applies the strongest response once the event is handled
+    pub fn execute<S>(&mut self, cb: &GuiCallback<S>, value: &S) -> EventResponse {
+        let response = cb.call(self.gui_builder, value, self.static_world);
+        self.response.set(self.response.get().max(response));
+        response
+    }
+    pub fn world(&self) -> &StaticWorld {
+        self.static_world
+    }
+    /// Starts dragging `payload` once the current event is handled, the preview follows the cursor
+    pub fn start_drag(&mut self, payload: DragPayload, preview: Option<DragPreview>) {
+        self.started_drag = Some((payload, preview));
     }
 }
 
-impl<'a, D> From<(&'a mut D, &'a mut StaticWorld)> for CallbackExecutor<'a>
+impl<'a, D> From<(&'a mut D, &'a mut StaticWorld, &'a Cell<EventResponse>)> for CallbackExecutor<'a>
 where
     D: GuiBuilder + 'static,
 {
-    fn from(pair: (&'a mut D, &'a mut StaticWorld)) -> Self {
+    fn from(parts: (&'a mut D, &'a mut StaticWorld, &'a Cell<EventResponse>)) -> Self {
         CallbackExecutor {
-            gui_builder: pair.0,
-            static_world: pair.1,
+            gui_builder: parts.0,
+            static_world: parts.1,
+            response: parts.2,
+            started_drag: None,
         }
     }
 }
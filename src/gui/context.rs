@@ -1,33 +1,69 @@
+#[cfg(feature = "serializable")]
+extern crate serde;
+
+use std::cell::Cell;
+use std::collections::HashMap;
 use std::fs::OpenOptions;
 use std::path::Path;
 
 use graphics::*;
-use gui::{CallbackExecutor, GuiBuilder, WidgetParser};
+use gui::{CallbackExecutor, GuiBuilder, GuiScale, Localization, SetGuiScale, WidgetParser};
+use gui::{EventKind, EventPhase, GuiHistory, History, Shortcut, ShortcutArea, WidgetEvent};
+use gui::{EventTrace, InspectedWidget, Inspector};
 use mecs::*;
 use tools::*;
 
+#[cfg(feature = "serializable")]
+use self::serde::de::DeserializeOwned;
+#[cfg(feature = "serializable")]
+use self::serde::Serialize;
+#[cfg(feature = "serializable")]
+use super::persistence::AutoSave;
+#[cfg(feature = "serializable")]
+use gui::GuiPersistence;
+#[cfg(feature = "serializable")]
+use tools::serde_tools::SerdeError;
+
+use super::drag::*;
 use super::draw::*;
+use super::inspector::INSPECTOR_TIMINGS;
 use super::widget::*;
 use super::widget_layout_builder::*;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 pub struct GuiContext<D>
 where
     D: GuiBuilder + 'static,
 {
     draw_res: DrawResources,
+    scale: GuiScale,
+    window_scale: f32,
     widgets: Vec<Box<dyn Widget + 'static>>,
     parents: Vec<Option<usize>>,
     widget_graph: Vec<Vec<usize>>,
     widget_depth: Vec<usize>,
     positions: Vec<WidgetPosition>,
+    shown: Vec<bool>,
     active_widget: Option<usize>,
+    focused_widget: Option<usize>,
     cursor_hierarchy: Option<usize>,
     cursor_grabbed: bool,
     cursor_pos: Vec2px,
-    render_seq: Option<RenderSequence>,
+    drag: Option<DragState>,
+    gestures: GestureRecognizer,
+    /// Finger driving the pointer, none once a second finger started a gesture
+    primary_touch: Option<u64>,
+    render_seq: Option<RetainedRenderSequence>,
     render_dirty: bool,
     build_dirty: bool,
+    /// The last rendered frame is outdated
+    frame_dirty: bool,
+    locale_generation: usize,
+    callback_response: Cell<EventResponse>,
+    history: Option<History<D>>,
+    inspector: Inspector,
+    #[cfg(feature = "serializable")]
+    autosave: Option<AutoSave<D>>,
     profiler: Profiler,
     gui_builder: D,
     gui_builder_new: D,
@@ -38,22 +74,53 @@ where
     D: GuiBuilder + 'static,
 {
     fn receive(&mut self, msg: &Box<dyn Message>, world: &mut StaticWorld) {
+        if let Some(SetGuiScale(scale)) = msg.downcast_ref() {
+            self.set_scale(*scale, world);
+        }
+        if let Some(command) = msg.downcast_ref::<GuiHistory>() {
+            self.history_command(command);
+        }
         self.gui_builder_new.receive(msg, world);
+        self.sync_history();
     }
 
     fn update(&mut self, delta_time: Duration, world: &mut StaticWorld) {
+        for gesture in self.gestures.update(Instant::now()) {
+            self.fire_gesture(gesture, world);
+        }
         self.gui_builder_new.update(delta_time, world);
+        self.sync_history();
+        #[cfg(feature = "serializable")]
+        {
+            if let Some(autosave) = self.autosave.as_mut() {
+                autosave.update(&self.gui_builder_new);
+            }
+        }
     }
 
     fn render(&mut self, world: &mut StaticWorld) {
+        // the inspector follows the cursor and shows the latest times
+        if self.is_animating() || self.inspector.is_enabled() {
+            self.render_dirty = true;
+        }
         self.actualize_data(world); // FIXME: actualize after events only
 
         crate::tools::gltraits::check_glerr_debug();
-        let rseq = self.render_seq.as_mut().unwrap();
+        let rseq = self.render_seq.as_ref().unwrap();
 
         self.profiler.begin_gpu("Draw");
-        rseq.execute(&mut self.draw_res);
+        rseq.execute(&self.draw_res);
         self.profiler.end_gpu();
+        self.frame_dirty = false;
+    }
+    fn needs_redraw(&self, _world: &StaticWorld) -> bool {
+        self.frame_dirty
+            || self.render_dirty
+            || self.build_dirty
+            || self.gui_builder != self.gui_builder_new
+            || self.locale_generation != Localization::generation()
+            || self.is_animating()
+            || self.inspector.is_enabled()
     }
     fn window_event(&mut self, event: &GlutinWindowEvent, world: &mut StaticWorld) -> bool {
         let handled = match event {
@@ -61,11 +128,18 @@ where
                 self.resized(size.into(), world);
                 false
             }
+            GlutinWindowEvent::ScaleFactorChanged {
+                scale_factor,
+                new_inner_size,
+            } => {
+                self.scale_factor_changed(*scale_factor as f32, (**new_inner_size).into(), world);
+                false
+            }
             GlutinWindowEvent::KeyboardInput { input, .. } => match input.virtual_keycode {
                 None => false,
                 Some(key) => {
                     if input.state == glutin::event::ElementState::Pressed {
-                        self.key_pressed(key)
+                        self.key_pressed(key, world)
                     } else {
                         self.key_released(key)
                     }
@@ -83,6 +157,18 @@ where
                 self.cursor_moved(Vec2px::from_pixels(position.into(), scl), world)
             }
             GlutinWindowEvent::CursorLeft { .. } => self.cursor_left(world),
+            GlutinWindowEvent::Touch(touch) => self.touch(touch, world),
+            GlutinWindowEvent::ReceivedCharacter(ch) => self.char_received(*ch, world),
+            GlutinWindowEvent::HoveredFile(path) => self.file_hovered(path, world),
+            GlutinWindowEvent::HoveredFileCancelled => self.file_hover_cancelled(world),
+            GlutinWindowEvent::DroppedFile(path) => self.file_dropped(path, world),
+            GlutinWindowEvent::MouseWheel { delta, .. } => {
+                let delta = match delta {
+                    GlutinScrollDelta::LineDelta(x, y) => Vec2::new(*x, *y),
+                    GlutinScrollDelta::PixelDelta(p) => Vec2::new(p.x as f32, p.y as f32) / 20.0,
+                };
+                self.scrolled(delta, world)
+            }
             _ => false,
         };
 
@@ -90,6 +176,12 @@ where
     }
     fn detach(&mut self, world: &mut StaticWorld) {
         self.gui_builder_new.persist(world);
+        #[cfg(feature = "serializable")]
+        {
+            if let Some(autosave) = self.autosave.as_mut() {
+                autosave.save(&self.gui_builder_new);
+            }
+        }
     }
 }
 
@@ -111,13 +203,27 @@ where
             widget_graph: vec![],
             widget_depth: vec![],
             positions: vec![],
+            shown: vec![],
             cursor_hierarchy: None,
             active_widget: None,
+            focused_widget: None,
             cursor_grabbed: false,
             cursor_pos: Vec2px::new(-1.0, -1.0),
+            drag: None,
+            gestures: GestureRecognizer::new(),
+            primary_touch: None,
             render_seq: None,
             render_dirty: true,
             build_dirty: true,
+            frame_dirty: true,
+            scale: GuiScale::Window,
+            window_scale: target.gui_scale,
+            locale_generation: Localization::generation(),
+            callback_response: Cell::new(EventResponse::Pass),
+            history: None,
+            inspector: Inspector::new(),
+            #[cfg(feature = "serializable")]
+            autosave: None,
             draw_res: DrawResources::new(target).unwrap(),
             profiler: Profiler::new(profile),
             gui_builder_new: gui_builder.clone(),
@@ -139,56 +245,193 @@ where
     }
     fn rebuild_render_seq(&mut self) {
         self.profiler.begin("Rebuild_Render");
+        let inspection = if self.inspector.is_enabled() {
+            let timings: Vec<_> = INSPECTOR_TIMINGS
+                .iter()
+                .map(|&(label, name)| (label, self.profiler.last(name)))
+                .collect();
+            Some((
+                self.inspected_widgets(),
+                self.widget_at(self.cursor_pos),
+                timings,
+            ))
+        } else {
+            None
+        };
+        let window_size = self.draw_res.window_info.logical_size();
         let mut builder = DrawBuilder::new(&mut self.draw_res);
         let n = self.widgets.len();
+        // parents come before their children, so the rects they cut descendants to are known
+        let mut child_bounds: Vec<Option<Rect>> = vec![None; n];
         for i in 0..n {
+            if !self.shown[i] {
+                continue;
+            }
+            let pos = self.positions[i].pos.as_vec2();
+            let bounds = self.parents[i].and_then(|p| child_bounds[p]);
+            child_bounds[i] = if self.widgets[i].clips_children() {
+                let rect = Rect::from_pos_size(pos, self.widgets[i].size().as_vec2());
+                Some(bounds.map_or(rect, |b| b.intersection(rect)))
+            } else {
+                bounds
+            };
             builder.offset = self.positions[i].to_pixels(1.0);
+            builder.owner = Some(i);
+            builder.set_bounds(bounds.map(|b| b.offset(-pos)));
+            builder.set_clip(None);
             self.widgets[i].on_draw_build(&mut builder);
-            // builder.add_clr_rect(Rect::from_pos_size(Vec2::origin(), self.widgets[i].size().to_pixels(1.0)), Vec4::new(1.0,0.0,0.0,0.5));
         }
-        self.render_seq = Some(builder.into_render_sequence());
+        builder.set_bounds(None);
+        builder.set_clip(None);
+        if let Some(preview) = self.drag.as_ref().and_then(|d| d.preview.as_ref()) {
+            let pos = self.cursor_pos + Vec2px::new(12.0, 12.0);
+            builder.offset = WidgetPosition::new(pos, 0.9).to_pixels(1.0);
+            builder.owner = None;
+            preview.build(&mut builder);
+        }
+        if let Some((widgets, hovered, timings)) = inspection {
+            self.inspector
+                .build(&mut builder, &widgets, hovered, &timings, window_size);
+        }
+        self.render_seq = Some(builder.into_retained(self.render_seq.take()));
         self.render_dirty = false;
+        self.frame_dirty = true;
         self.profiler.end();
     }
 
     pub fn rebuild_gui(&mut self, world: &mut StaticWorld) {
         crate::tools::gltraits::check_glerr_debug();
         self.profiler.begin("Rebuild_Gui");
-        let widget_list = WidgetParser::produce_list(&self.gui_builder);
+        let mut widget_list = WidgetParser::produce_list(&self.gui_builder);
         // println!("cache_details is {:?}",widget_list.cache_details);
         // println!("cache_loc is {:?}",widget_list.cache_loc);
 
+        // widgets of the same kind at the same place in the hierarchy keep their state and focus
+        let previous: HashMap<Vec<usize>, usize> = widget_paths(&self.parents, &self.widget_graph)
+            .into_iter()
+            .enumerate()
+            .map(|(id, path)| (path, id))
+            .collect();
+        let mut focused_widget = None;
+        let mut active_widget = None;
+        let mut grabbed_hierarchy = None;
+        let mut drag_source = None;
+        let paths = widget_paths(&widget_list.parents, &widget_list.widget_graph);
+        for (id, path) in paths.iter().enumerate() {
+            if let Some(&old) = previous.get(path) {
+                let old_widget = self.widgets[old].as_ref();
+                if old_widget.as_any().type_id() == widget_list.widgets[id].as_any().type_id() {
+                    widget_list.widgets[id].adopt_state(old_widget);
+                    if self.focused_widget == Some(old) {
+                        focused_widget = Some(id);
+                    }
+                    if self.active_widget == Some(old) {
+                        active_widget = Some(id);
+                    }
+                    if self.cursor_hierarchy == Some(old) {
+                        grabbed_hierarchy = Some(id);
+                    }
+                    if self.drag.as_ref().and_then(|d| d.source) == Some(old) {
+                        drag_source = Some(id);
+                    }
+                }
+            }
+        }
+
+        self.profiler.end();
+
+        self.profiler.begin("Layout");
         let mut layout_builder = WidgetLayoutBuilder::new(
             widget_list.widgets,
             widget_list.postorder,
             widget_list.widget_graph,
         );
         layout_builder.build(self.draw_res.window_info.logical_size());
+        self.profiler.end();
 
         self.widgets = layout_builder.widgets;
-        self.cursor_hierarchy = None;
-        self.active_widget = None;
+        // a drag keeps going on the rebuilt widget that replaced the grabbed one
+        if self.cursor_grabbed && active_widget.is_some() {
+            self.cursor_hierarchy = grabbed_hierarchy;
+            self.active_widget = active_widget;
+        } else {
+            self.cursor_grabbed = false;
+            self.cursor_hierarchy = None;
+            self.active_widget = None;
+        }
+        self.focused_widget = focused_widget;
+        if let Some(drag) = self.drag.as_mut() {
+            drag.source = drag_source;
+            drag.target = None;
+            drag.rejected.clear();
+        }
 
         self.build_dirty = false;
         self.widget_graph = layout_builder.widget_graph;
         self.parents = widget_list.parents;
         self.widget_depth = widget_list.widget_depth;
         self.positions = layout_builder.positions;
-        self.profiler.end();
+        self.shown = layout_builder.shown;
+        // data changed here is applied like that of any callback, by the next rebuild
+        let mut cb_exec: CallbackExecutor = (
+            &mut self.gui_builder_new,
+            &mut *world,
+            &self.callback_response,
+        )
+            .into();
+        for (widget, &shown) in self.widgets.iter_mut().zip(self.shown.iter()) {
+            if shown {
+                widget.on_layout(&mut cb_exec);
+            }
+        }
+        // what only the layout tells is no step of the history
+        self.sync_history();
         self.rebuild_render_seq();
 
-        self.rebuild_cursor_inside(world);
+        if !self.cursor_grabbed {
+            self.rebuild_cursor_inside(world);
+        }
     }
     pub fn resized(&mut self, s: Vec2, world: &mut StaticWorld) {
         self.draw_res.window_info.size = s;
         self.update_projection_matrix();
         self.rebuild_gui(world);
     }
+    /// The window moved to a monitor with a different scale factor
+    pub fn scale_factor_changed(&mut self, window_scale: f32, s: Vec2, world: &mut StaticWorld) {
+        self.window_scale = window_scale;
+        self.draw_res.window_info.size = s;
+        self.apply_scale(world);
+    }
+    /// Override the scale of the window, e.g. for accessibility zoom
+    pub fn set_scale(&mut self, scale: GuiScale, world: &mut StaticWorld) {
+        self.scale = scale;
+        self.apply_scale(world);
+    }
+    pub fn scale(&self) -> GuiScale {
+        self.scale
+    }
+    /// Pixels per unit currently in use
+    pub fn gui_scale(&self) -> f32 {
+        self.draw_res.window_info.gui_scale
+    }
+    fn apply_scale(&mut self, world: &mut StaticWorld) {
+        self.draw_res.window_info.gui_scale = self.scale.resolve(self.window_scale);
+        self.update_projection_matrix();
+        self.rebuild_gui(world);
+    }
+    fn is_animating(&self) -> bool {
+        self.widgets
+            .iter()
+            .zip(self.shown.iter())
+            .any(|(widget, &shown)| shown && widget.is_animating())
+    }
     pub fn widget_count(&self) -> usize {
         self.widgets.len()
     }
     fn handle_event_response(&mut self, response: EventResponse) {
-        match response {
+        // callbacks may ask for more than the widget that ran them
+        match response.max(self.callback_response.replace(EventResponse::Pass)) {
             EventResponse::HandledRedraw => {
                 self.render_dirty = true;
             }
@@ -199,21 +442,113 @@ where
             EventResponse::Handled => {}
             EventResponse::Pass => {}
         }
+        // a drag is recorded once the pointer is released
+        if !self.cursor_grabbed {
+            if let Some(history) = self.history.as_mut() {
+                history.commit(&self.gui_builder_new);
+            }
+        }
+    }
+    /// Records changes callbacks make to the data, Ctrl+Z and Ctrl+Y step through them
+    pub fn enable_history(&mut self, limit: usize) {
+        if self.history.is_none() {
+            self.history = Some(History::new(&self.gui_builder_new, limit));
+        }
+    }
+    pub fn disable_history(&mut self) {
+        self.history = None;
+    }
+    pub fn history(&self) -> Option<&History<D>> {
+        self.history.as_ref()
+    }
+    pub fn history_mut(&mut self) -> Option<&mut History<D>> {
+        self.history.as_mut()
+    }
+    pub fn undo(&mut self) -> bool {
+        let state = match self.history.as_mut() {
+            Some(history) if !self.cursor_grabbed => history.undo(&self.gui_builder_new),
+            _ => None,
+        };
+        self.restore_state(state)
+    }
+    pub fn redo(&mut self) -> bool {
+        let state = match self.history.as_mut() {
+            Some(history) if !self.cursor_grabbed => history.redo(&self.gui_builder_new),
+            _ => None,
+        };
+        self.restore_state(state)
+    }
+    fn restore_state(&mut self, state: Option<D>) -> bool {
+        match state {
+            Some(state) => {
+                self.gui_builder_new = state;
+                self.render_dirty = true;
+                true
+            }
+            None => false,
+        }
+    }
+    fn history_command(&mut self, command: &GuiHistory) {
+        match command {
+            GuiHistory::Undo => {
+                self.undo();
+            }
+            GuiHistory::Redo => {
+                self.redo();
+            }
+            GuiHistory::Label(label) => {
+                if let Some(history) = self.history.as_mut() {
+                    history.label_last(label);
+                }
+            }
+            GuiHistory::Clear => {
+                if let Some(history) = self.history.as_mut() {
+                    history.clear();
+                }
+            }
+        }
+    }
+    /// Changes from messages and updates are not undone
+    fn sync_history(&mut self) {
+        if !self.cursor_grabbed {
+            if let Some(history) = self.history.as_mut() {
+                history.sync(&self.gui_builder_new);
+            }
+        }
+    }
+    fn fire_history_key(&mut self, key: GlutinKey, world: &StaticWorld) -> bool {
+        if self.history.is_none() {
+            return false;
+        }
+        if Shortcut::ctrl(GlutinKey::Z).matches(key, world) {
+            self.undo()
+        } else if Shortcut::ctrl(GlutinKey::Y).matches(key, world)
+            || Shortcut::ctrl(GlutinKey::Z).shift().matches(key, world)
+        {
+            self.redo()
+        } else {
+            false
+        }
     }
     pub fn button_released(&mut self, button: GlutinButton, world: &mut StaticWorld) -> bool {
         if button != GlutinButton::Left {
             return false;
         }
+        if self.drag.is_some() {
+            self.end_drag(true, world);
+        }
         self.rebuild_cursor_inside(world);
 
         if self.cursor_grabbed {
             self.cursor_grabbed = false;
 
             if let Some(id) = self.active_widget {
-                let response =
-                    self.widgets[id].on_release(&mut (&mut self.gui_builder_new, world).into());
-                self.handle_event_response(response);
-                response != EventResponse::Pass
+                let dispatched =
+                    self.dispatch(EventKind::Release(button), id, false, world, |w, _, e| {
+                        w.on_release(e)
+                    });
+                self.handle_event_response(dispatched.response);
+                dispatched.response != EventResponse::Pass
             } else {
                 false
             }
@@ -223,68 +558,384 @@ where
     }
     pub fn button_pressed(&mut self, button: GlutinButton, world: &mut StaticWorld) -> bool {
         if button != GlutinButton::Left {
-            return false;
+            return self.other_button_pressed(button, world);
+        }
+        let id = match self.cursor_hierarchy {
+            Some(id) => id,
+            None => {
+                self.set_focus(None, world);
+                return false;
+            }
+        };
+        let dispatched = self.dispatch(EventKind::Press(button), id, true, world, |w, p, e| {
+            w.on_press(p, e)
+        });
+        if let Some((payload, preview)) = dispatched.started_drag {
+            let source = dispatched.handler.unwrap_or(id);
+            self.drag = Some(DragState::new(payload, preview, Some(source)));
+        }
+        if let Some(handler) = dispatched.handler {
+            self.active_widget = Some(handler);
+            self.cursor_grabbed = true;
+            if dispatched.grabbed {
+                self.cursor_hierarchy = Some(handler);
+            }
+        }
+        self.handle_event_response(dispatched.response);
+        self.set_focus(dispatched.handler, world);
+
+        dispatched.response != EventResponse::Pass
+    }
+    fn other_button_pressed(&mut self, button: GlutinButton, world: &mut StaticWorld) -> bool {
+        let id = match self.cursor_hierarchy {
+            Some(id) => id,
+            None => return false,
+        };
+        // only the left button grabs the pointer, there is no release for the others
+        let dispatched = self.dispatch(EventKind::Press(button), id, true, world, |w, p, e| {
+            w.on_button_press(button, p, e)
+        });
+        self.handle_event_response(dispatched.response);
+        if let Some(handler) = dispatched.handler {
+            self.set_focus(Some(handler), world);
+        }
+        dispatched.response != EventResponse::Pass
+    }
+    fn set_focus(&mut self, id: Option<usize>, world: &mut StaticWorld) {
+        if self.focused_widget == id {
+            return;
+        }
+        if let Some(old) = self.focused_widget {
+            let response = self.widgets[old].on_focus_lost(
+                &mut (&mut self.gui_builder_new, world, &self.callback_response).into(),
+            );
+            self.handle_event_response(response);
         }
+        self.focused_widget = id;
+    }
+    fn fire_focus_event<F>(
+        &mut self,
+        kind: EventKind,
+        world: &mut StaticWorld,
+        default: F,
+    ) -> Option<Dispatched>
+    where
+        F: FnMut(&mut dyn Widget, Vec2px, &mut CallbackExecutor) -> EventResponse,
+    {
+        let id = self.focused_widget?;
+        let dispatched = self.dispatch(kind, id, true, world, default);
+        self.handle_event_response(dispatched.response);
+        Some(dispatched)
+    }
+
+    pub fn scrolled(&mut self, delta: Vec2, world: &mut StaticWorld) -> bool {
         match self.cursor_hierarchy {
-            Some(mut id) => {
-                let wpos = self.positions[id].pos;
-                let mut cb_exec: CallbackExecutor = (&mut self.gui_builder_new, world).into();
-                let mut result = self.widgets[id].on_press(self.cursor_pos - wpos, &mut cb_exec);
-                while result == EventResponse::Pass {
-                    if let Some(parent) = self.parents[id] {
-                        let wpos = self.positions[parent].pos;
-                        result =
-                            self.widgets[parent].on_press(self.cursor_pos - wpos, &mut cb_exec);
-                        id = parent;
-                    } else {
-                        break;
-                    }
-                }
-                if result != EventResponse::Pass {
-                    self.active_widget = Some(id);
-                    self.cursor_grabbed = true;
-                }
-                self.handle_event_response(result);
+            Some(id) => {
+                let dispatched =
+                    self.dispatch(EventKind::Scroll(delta), id, true, world, |w, p, e| {
+                        w.on_scroll(p, delta, e)
+                    });
+                self.handle_event_response(dispatched.response);
 
-                result != EventResponse::Pass
+                dispatched.response != EventResponse::Pass
             }
             None => false,
         }
     }
 
-    pub fn key_pressed(&mut self, _key: GlutinKey) -> bool {
+    pub fn key_pressed(&mut self, key: GlutinKey, world: &mut StaticWorld) -> bool {
+        if let Some(shortcut) = self.inspector.shortcut {
+            if shortcut.matches(key, world) {
+                let enabled = !self.inspector.is_enabled();
+                self.set_inspector(enabled);
+                return true;
+            }
+        }
+        if key == GlutinKey::Escape && self.drag.is_some() {
+            self.end_drag(false, world);
+            return true;
+        }
+        let dispatched = self.fire_focus_event(EventKind::KeyPress(key), &mut *world, |w, _, e| {
+            w.on_key_press(key, e)
+        });
+        match dispatched {
+            Some(ref d) if d.response != EventResponse::Pass => true,
+            Some(ref d) if d.default_prevented => false,
+            _ => {
+                self.fire_focus_shortcut(key, world)
+                    || self.fire_accelerator(key, world)
+                    || self.fire_history_key(key, world)
+            }
+        }
+    }
+    /// Shortcuts of the `ShortcutArea`s around the focused widget, innermost first
+    fn fire_focus_shortcut(&mut self, key: GlutinKey, world: &mut StaticWorld) -> bool {
+        let mut candidate = self.focused_widget;
+        while let Some(id) = candidate {
+            if let Some(area) = self.widgets[id].downcast_ref::<ShortcutArea>() {
+                let scope = ShortcutScope::Focus(area.scope.clone());
+                if world.trigger_shortcut(key, &scope) {
+                    return true;
+                }
+            }
+            candidate = self.parents[id];
+        }
+        false
+    }
+    fn fire_accelerator(&mut self, key: GlutinKey, world: &mut StaticWorld) -> bool {
+        let mut cb_exec: CallbackExecutor =
+            (&mut self.gui_builder_new, world, &self.callback_response).into();
+        for id in 0..self.widgets.len() {
+            if !self.shown[id] {
+                continue;
+            }
+            let result = self.widgets[id].on_accelerator(key, &mut cb_exec);
+            if result != EventResponse::Pass {
+                self.handle_event_response(result);
+                return true;
+            }
+        }
         false
     }
 
+    pub fn char_received(&mut self, ch: char, world: &mut StaticWorld) -> bool {
+        let dispatched =
+            self.fire_focus_event(EventKind::Char(ch), world, |w, _, e| w.on_char(ch, e));
+        match dispatched {
+            Some(d) => d.response != EventResponse::Pass,
+            None => false,
+        }
+    }
+
     pub fn key_released(&mut self, _key: GlutinKey) -> bool {
         false
     }
+    /// A finger touching down alone acts as the left button, every finger feeds the gesture
+    /// recognizer
+    pub fn touch(&mut self, touch: &GlutinTouch, world: &mut StaticWorld) -> bool {
+        let pos = Vec2px::from_pixels((&touch.location).into(), self.gui_scale());
+        let primary = self.primary_touch == Some(touch.id);
+        let mut handled = match touch.phase {
+            GlutinTouchPhase::Started if self.gestures.touch_count() == 0 => {
+                self.primary_touch = Some(touch.id);
+                self.cursor_moved(pos, world);
+                self.button_pressed(GlutinButton::Left, world)
+            }
+            GlutinTouchPhase::Started => {
+                // another finger turns the touch into a gesture until every finger lifted
+                if self.primary_touch.take().is_some() {
+                    self.pointer_cancelled(world);
+                }
+                false
+            }
+            GlutinTouchPhase::Moved if primary => self.cursor_moved(pos, world),
+            GlutinTouchPhase::Ended if primary => {
+                self.primary_touch = None;
+                self.cursor_moved(pos, world);
+                let handled = self.button_released(GlutinButton::Left, world);
+                // fingers don't hover
+                self.cursor_left(world);
+                handled
+            }
+            GlutinTouchPhase::Cancelled if primary => {
+                self.primary_touch = None;
+                self.pointer_cancelled(world);
+                self.cursor_left(world);
+                true
+            }
+            _ => false,
+        };
+        for gesture in self.gestures.touch_event(touch, Instant::now()) {
+            handled |= self.fire_gesture(gesture, world);
+        }
+        handled
+    }
+    /// The press ends without a release, e.g. the system took over the touch
+    fn pointer_cancelled(&mut self, world: &mut StaticWorld) {
+        self.end_drag(false, world);
+        if !self.cursor_grabbed {
+            return;
+        }
+        self.cursor_grabbed = false;
+        if let Some(id) = self.active_widget {
+            let response = self.widgets[id].on_pointer_lost(
+                &mut (&mut self.gui_builder_new, world, &self.callback_response).into(),
+            );
+            self.handle_event_response(response);
+        }
+    }
+    /// Sends `gesture` to the widget under it, bubbling up until handled
+    pub fn fire_gesture(&mut self, gesture: Gesture, world: &mut StaticWorld) -> bool {
+        let scale = self.gui_scale();
+        let center = Vec2px::from_pixels(gesture.pos(), scale);
+        let target = match self.widget_at(center) {
+            Some(id) => id,
+            None => return false,
+        };
+        let gesture = gesture.transformed(Vec2::zero(), 1.0 / scale);
+        let kind = EventKind::Gesture(gesture);
+        let dispatched = self.dispatch_at(kind, target, center, true, world, |w, p, e| {
+            w.on_gesture(&gesture.transformed((p - center).as_vec2(), 1.0), e)
+        });
+        self.handle_event_response(dispatched.response);
+        dispatched.response != EventResponse::Pass
+    }
     pub fn cursor_left(&mut self, world: &mut StaticWorld) -> bool {
         self.cursor_moved(Vec2px::new(-1.0, -1.0), world)
     }
     fn point_in_widget(&self, id: usize, p: Vec2px) -> bool {
+        if !self.shown[id] {
+            return false;
+        }
         let pos = self.positions[id].pos;
         let siz = self.widgets[id].size();
         Rect::from_pos_size(pos.as_vec2(), siz.as_vec2()).contains(p.as_vec2())
+            || self.point_in_popup(id, p)
+    }
+    fn point_in_popup(&self, id: usize, p: Vec2px) -> bool {
+        match self.widgets[id].popup_area() {
+            Some(area) if self.shown[id] => area.contains((p - self.positions[id].pos).as_vec2()),
+            _ => false,
+        }
     }
     fn fire_enter_event(&mut self, id: usize, world: &mut StaticWorld) {
-        let response =
-            self.widgets[id].on_cursor_enter(&mut (&mut self.gui_builder_new, world).into());
+        let response = self.widgets[id].on_cursor_enter(
+            &mut (&mut self.gui_builder_new, world, &self.callback_response).into(),
+        );
         self.handle_event_response(response);
     }
     fn fire_leave_event(&mut self, id: usize, world: &mut StaticWorld) {
-        let response =
-            self.widgets[id].on_cursor_leave(&mut (&mut self.gui_builder_new, world).into());
-        self.handle_event_response(response);
-    }
-    fn fire_move_event(&mut self, id: usize, pos: Vec2px, world: &mut StaticWorld) -> bool {
-        let widget_pos = self.positions[id].pos;
-        let response = self.widgets[id].on_cursor_move(
-            pos - widget_pos,
-            &mut (&mut self.gui_builder_new, world).into(),
+        let response = self.widgets[id].on_cursor_leave(
+            &mut (&mut self.gui_builder_new, world, &self.callback_response).into(),
         );
         self.handle_event_response(response);
-        response != EventResponse::Pass
+    }
+    fn fire_move_event(&mut self, id: usize, world: &mut StaticWorld) -> bool {
+        let dispatched = self.dispatch(EventKind::CursorMove, id, false, &mut *world, |w, p, e| {
+            w.on_cursor_move(p, e)
+        });
+        if let Some((payload, preview)) = dispatched.started_drag {
+            if self.drag.is_none() {
+                let source = dispatched.handler.unwrap_or(id);
+                self.drag = Some(DragState::new(payload, preview, Some(source)));
+            }
+        }
+        if dispatched.grabbed && self.cursor_grabbed {
+            if let Some(handler) = dispatched.handler {
+                self.take_pointer(handler, world);
+            }
+        }
+        self.handle_event_response(dispatched.response);
+        dispatched.response != EventResponse::Pass
+    }
+    /// Hands the pointer of a press to an ancestor of the widget holding it
+    fn take_pointer(&mut self, id: usize, world: &mut StaticWorld) {
+        if let Some(old) = self.active_widget.filter(|&old| old != id) {
+            let response = self.widgets[old].on_pointer_lost(
+                &mut (&mut self.gui_builder_new, world, &self.callback_response).into(),
+            );
+            self.handle_event_response(response);
+        }
+        self.active_widget = Some(id);
+        self.cursor_hierarchy = Some(id);
+    }
+    /// Sends an event from the root down to `target` and back up, see `WidgetEvent`
+    ///
+    /// `default` is the typed handler getting the local cursor, it runs at the target and, if
+    /// `bubble` is set, at the ancestors until one of them handles the event.
+    fn dispatch<F>(
+        &mut self,
+        kind: EventKind,
+        target: usize,
+        bubble: bool,
+        world: &mut StaticWorld,
+        default: F,
+    ) -> Dispatched
+    where
+        F: FnMut(&mut dyn Widget, Vec2px, &mut CallbackExecutor) -> EventResponse,
+    {
+        let pos = self.cursor_pos;
+        self.dispatch_at(kind, target, pos, bubble, world, default)
+    }
+    /// Like `dispatch` with local positions relative to `pos` instead of the cursor
+    fn dispatch_at<F>(
+        &mut self,
+        kind: EventKind,
+        target: usize,
+        pos: Vec2px,
+        bubble: bool,
+        world: &mut StaticWorld,
+        mut default: F,
+    ) -> Dispatched
+    where
+        F: FnMut(&mut dyn Widget, Vec2px, &mut CallbackExecutor) -> EventResponse,
+    {
+        let mut path = vec![target];
+        while let Some(parent) = self.parents[path[path.len() - 1]] {
+            path.push(parent);
+        }
+        let capture = path[1..].iter().rev().map(|&id| (id, EventPhase::Capture));
+        let target_and_bubble = path.iter().enumerate().map(|(i, &id)| match i {
+            0 => (id, EventPhase::Target),
+            _ => (id, EventPhase::Bubble),
+        });
+
+        let mut cb_exec: CallbackExecutor =
+            (&mut self.gui_builder_new, world, &self.callback_response).into();
+        let mut event = WidgetEvent::new(kind, target);
+        let mut response = EventResponse::Pass;
+        let mut handler = None;
+        for (id, phase) in capture.chain(target_and_bubble) {
+            event.current = id;
+            event.phase = phase;
+            event.local_cursor_pos = pos - self.positions[id].pos;
+            response = response.max(self.widgets[id].on_event(&mut event, &mut cb_exec));
+
+            let run_default = match phase {
+                EventPhase::Capture => false,
+                EventPhase::Target => true,
+                EventPhase::Bubble => bubble,
+            };
+            if run_default && handler.is_none() && !event.is_default_prevented() {
+                let result = default(
+                    self.widgets[id].as_mut(),
+                    event.local_cursor_pos,
+                    &mut cb_exec,
+                );
+                if result != EventResponse::Pass {
+                    response = response.max(result);
+                    handler = Some(id);
+                }
+            }
+            if event.is_propagation_stopped() {
+                break;
+            }
+        }
+
+        let grabbed = event.grabbed_by();
+        if grabbed.is_some() {
+            response = response.max(EventResponse::Handled);
+            handler = grabbed;
+        }
+        if self.inspector.is_enabled() {
+            self.inspector.record(EventTrace {
+                kind,
+                target,
+                target_type: self.widgets[target].type_name(),
+                handler: match handler {
+                    Some(id) => Some((id, self.widgets[id].type_name())),
+                    None => None,
+                },
+                response,
+            });
+        }
+        Dispatched {
+            response,
+            handler,
+            grabbed: grabbed.is_some(),
+            default_prevented: event.is_default_prevented(),
+            started_drag: cb_exec.started_drag.take(),
+        }
     }
     pub fn cursor_moved(&mut self, p: Vec2px, world: &mut StaticWorld) -> bool {
         self.cursor_pos = p;
@@ -292,11 +943,147 @@ where
             self.rebuild_cursor_inside(world);
         }
 
-        if let Some(i) = self.cursor_hierarchy {
-            self.fire_move_event(i, p, world)
+        let handled = if let Some(i) = self.cursor_hierarchy {
+            self.fire_move_event(i, &mut *world)
         } else {
             false
+        };
+        if self.drag.is_some() {
+            self.update_drag_target(world);
+            return true;
         }
+        handled
+    }
+    /// Deepest shown widget under `p`, open popups come first
+    fn widget_at(&self, p: Vec2px) -> Option<usize> {
+        let n = self.widget_count();
+        if let Some(popup) = (0..n).rev().find(|&i| self.point_in_popup(i, p)) {
+            return Some(popup);
+        }
+        let mut found = None;
+        let mut candidates: Vec<usize> = (0..n).filter(|&i| self.parents[i].is_none()).collect();
+        while let Some(id) = self.topmost_at(&candidates, p) {
+            found = Some(id);
+            candidates = self.widget_graph[id].clone();
+        }
+        found
+    }
+    /// Of the `candidates` under `p` the one drawn on top, the later one if equally deep
+    fn topmost_at(&self, candidates: &[usize], p: Vec2px) -> Option<usize> {
+        let mut topmost: Option<usize> = None;
+        for &id in candidates {
+            if !self.point_in_widget(id, p) {
+                continue;
+            }
+            match topmost {
+                Some(top) if self.positions[top].depth > self.positions[id].depth => {}
+                _ => topmost = Some(id),
+            }
+        }
+        topmost
+    }
+    /// Finds the innermost widget under the cursor accepting the dragged payload
+    fn update_drag_target(&mut self, world: &mut StaticWorld) {
+        let hit = self.widget_at(self.cursor_pos);
+        let drag = match self.drag.as_mut() {
+            Some(drag) => drag,
+            None => return,
+        };
+        let mut cb_exec: CallbackExecutor =
+            (&mut self.gui_builder_new, world, &self.callback_response).into();
+        let mut responses = vec![EventResponse::HandledRedraw];
+
+        let mut candidate = hit;
+        let mut target = None;
+        while let Some(id) = candidate {
+            if drag.target == Some(id) {
+                target = Some(id);
+                break;
+            }
+            if !drag.rejected.contains(&id) {
+                if self.widgets[id].on_drag_enter(&drag.payload, &mut cb_exec) {
+                    target = Some(id);
+                    break;
+                }
+                drag.rejected.push(id);
+            }
+            candidate = self.parents[id];
+        }
+        if target != drag.target {
+            if let Some(old) = drag.target {
+                responses.push(self.widgets[old].on_drag_leave(&mut cb_exec));
+            }
+            drag.target = target;
+        }
+        if let Some(id) = target {
+            let local = self.cursor_pos - self.positions[id].pos;
+            responses.push(self.widgets[id].on_drag_over(local, &drag.payload, &mut cb_exec));
+        }
+        for response in responses {
+            self.handle_event_response(response);
+        }
+    }
+    /// Drops the payload on the current target if `drop` is set, then tells the source
+    fn end_drag(&mut self, drop: bool, world: &mut StaticWorld) {
+        let drag = match self.drag.take() {
+            Some(drag) => drag,
+            None => return,
+        };
+        let mut cb_exec: CallbackExecutor =
+            (&mut self.gui_builder_new, world, &self.callback_response).into();
+        let mut responses = vec![EventResponse::HandledRedraw];
+
+        let mut dropped = false;
+        if let Some(id) = drag.target {
+            if drop {
+                let local = self.cursor_pos - self.positions[id].pos;
+                let response = self.widgets[id].on_drop(local, &drag.payload, &mut cb_exec);
+                dropped = response != EventResponse::Pass;
+                responses.push(response);
+            }
+            responses.push(self.widgets[id].on_drag_leave(&mut cb_exec));
+        }
+        if let Some(id) = drag.source {
+            responses.push(self.widgets[id].on_drag_end(dropped, &mut cb_exec));
+        }
+        for response in responses {
+            self.handle_event_response(response);
+        }
+    }
+    fn file_hovered(&mut self, path: &Path, world: &mut StaticWorld) -> bool {
+        // several hovered files arrive one by one, the first one decides who accepts
+        if self.drag.is_none() {
+            self.drag = Some(DragState::new(
+                DragPayload::new(path.to_path_buf()),
+                None,
+                None,
+            ));
+            self.update_drag_target(world);
+        }
+        true
+    }
+    fn file_hover_cancelled(&mut self, world: &mut StaticWorld) -> bool {
+        if self.drag.as_ref().map(|d| d.source.is_none()) == Some(true) {
+            self.end_drag(false, world);
+        }
+        true
+    }
+    fn file_dropped(&mut self, path: &Path, world: &mut StaticWorld) -> bool {
+        match self.drag.as_mut() {
+            Some(drag) if drag.source.is_none() => {
+                drag.payload = DragPayload::new(path.to_path_buf())
+            }
+            _ => {
+                self.drag = Some(DragState::new(
+                    DragPayload::new(path.to_path_buf()),
+                    None,
+                    None,
+                ))
+            }
+        }
+        self.update_drag_target(&mut *world);
+        self.end_drag(true, world);
+        true
     }
     fn pop_cursor_hierarchy(&mut self) {
         if let Some(i) = self.cursor_hierarchy {
@@ -304,16 +1091,51 @@ where
         }
     }
     fn complete_cursor_inside(&mut self, i: usize, world: &mut StaticWorld) {
-        for &id in self.widget_graph[i].iter().rev() {
-            if self.point_in_widget(id, self.cursor_pos) {
-                self.cursor_hierarchy = Some(id);
-                self.fire_enter_event(id, world);
-                self.complete_cursor_inside(id, world);
+        if self.point_in_popup(i, self.cursor_pos) {
+            return;
+        }
+        if let Some(id) = self.topmost_at(&self.widget_graph[i], self.cursor_pos) {
+            self.cursor_hierarchy = Some(id);
+            self.fire_enter_event(id, world);
+            self.complete_cursor_inside(id, world);
+        }
+    }
+    /// Points the cursor hierarchy at the topmost popup under the cursor, if there is one
+    fn popup_cursor_inside(&mut self, world: &mut StaticWorld) -> bool {
+        let popup = (0..self.widget_count())
+            .rev()
+            .find(|&i| self.point_in_popup(i, self.cursor_pos));
+        let popup = match popup {
+            Some(popup) => popup,
+            None => return false,
+        };
+
+        let mut chain = vec![popup];
+        while let Some(parent) = self.parents[*chain.last().unwrap()] {
+            chain.push(parent);
+        }
+        chain.reverse();
+        while let Some(id) = self.cursor_hierarchy {
+            if chain.contains(&id) {
                 break;
             }
+            self.fire_leave_event(id, world);
+            self.pop_cursor_hierarchy();
+        }
+        let entered = match self.cursor_hierarchy {
+            Some(id) => chain.iter().position(|&c| c == id).unwrap() + 1,
+            None => 0,
+        };
+        for &id in &chain[entered..] {
+            self.cursor_hierarchy = Some(id);
+            self.fire_enter_event(id, world);
         }
+        true
     }
     fn rebuild_cursor_inside(&mut self, world: &mut StaticWorld) {
+        if self.popup_cursor_inside(world) {
+            return;
+        }
         let mut reduced = self.cursor_hierarchy.is_none();
         while !reduced {
             reduced = true;
@@ -326,12 +1148,12 @@ where
             }
         }
         if self.cursor_hierarchy.is_none() {
-            for i in (0..self.widget_count()).rev() {
-                if self.parents[i].is_none() && self.point_in_widget(i, self.cursor_pos) {
-                    self.cursor_hierarchy = Some(i);
-                    self.fire_enter_event(i, world);
-                    break;
-                }
+            let roots: Vec<usize> = (0..self.widget_count())
+                .filter(|&i| self.parents[i].is_none())
+                .collect();
+            if let Some(i) = self.topmost_at(&roots, self.cursor_pos) {
+                self.cursor_hierarchy = Some(i);
+                self.fire_enter_event(i, world);
             }
         }
         if let Some(i) = self.cursor_hierarchy {
@@ -342,12 +1164,57 @@ where
     pub fn set_profile(&mut self, enabled: bool) {
         self.profiler.set_enabled(enabled);
     }
+    /// Shows the inspector overlay, its shortcut toggles it too
+    pub fn set_inspector(&mut self, enabled: bool) {
+        let profiling = self.inspector.set_enabled(enabled, self.profiler.enabled());
+        self.profiler.set_enabled(profiling);
+        self.render_dirty = true;
+    }
+    pub fn inspector(&self) -> &Inspector {
+        &self.inspector
+    }
+    pub fn inspector_mut(&mut self) -> &mut Inspector {
+        &mut self.inspector
+    }
+    /// The widget tree as shown by the inspector, parents before their children
+    pub fn inspected_widgets(&self) -> Vec<InspectedWidget> {
+        let mut inspected = Vec::with_capacity(self.widgets.len());
+        let mut stack: Vec<(usize, usize)> = (0..self.widgets.len())
+            .rev()
+            .filter(|&id| self.parents[id].is_none())
+            .map(|id| (id, 0))
+            .collect();
+        while let Some((id, depth)) = stack.pop() {
+            inspected.push(InspectedWidget {
+                id,
+                type_name: self.widgets[id].type_name(),
+                depth,
+                pos: self.positions[id],
+                size: self.widgets[id].size(),
+                shown: self.shown[id],
+            });
+            let children = self.widget_graph[id].iter().rev();
+            stack.extend(children.map(|&child| (child, depth + 1)));
+        }
+        inspected
+    }
 
     fn actualize_data(&mut self, world: &mut StaticWorld) {
-        if (self.gui_builder != self.gui_builder_new || self.build_dirty) && !self.cursor_grabbed {
+        // responses of callbacks run outside of the widget events
+        self.handle_event_response(EventResponse::Pass);
+        // texts are looked up while building, a locale switch needs new widgets
+        let locale_generation = Localization::generation();
+        if locale_generation != self.locale_generation {
+            self.locale_generation = locale_generation;
+            self.build_dirty = true;
+        }
+        // data changes wait for the end of a drag, widgets asking for a rebuild don't
+        if (self.gui_builder != self.gui_builder_new && !self.cursor_grabbed) || self.build_dirty {
             self.gui_builder = self.gui_builder_new.clone();
             self.rebuild_gui(world);
-            self.rebuild_cursor_inside(world);
+            if !self.cursor_grabbed {
+                self.rebuild_cursor_inside(world);
+            }
         }
         if self.render_dirty {
             self.rebuild_render_seq();
@@ -355,11 +1222,95 @@ where
     }
 }
 
+#[cfg(feature = "serializable")]
+impl<D> GuiContext<D>
+where
+    D: GuiBuilder + Serialize + DeserializeOwned + 'static,
+{
+    /// Context whose data is restored from `persistence.path` and saved back there on detach
+    /// and periodically
+    ///
+    /// `gui_builder` supplies the fields missing from the file, or everything without a file.
+    /// The `restore` hook runs after loading.
+    pub fn new_persistent(
+        target: WindowInfo,
+        profile: bool,
+        gui_builder: D,
+        world: &mut StaticWorld,
+        persistence: GuiPersistence,
+    ) -> GuiContext<D> {
+        let stored = match persistence.load(&gui_builder) {
+            Ok(stored) => Some(stored),
+            Err(SerdeError::IoError(ref e)) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                eprintln!(
+                    "Could not restore gui data from {}: {:?}",
+                    persistence.path.display(),
+                    e
+                );
+                None
+            }
+        };
+        let mut gui_context = GuiContext::new(
+            target,
+            profile,
+            stored.clone().unwrap_or(gui_builder),
+            world,
+        );
+        gui_context.autosave = Some(AutoSave::new(persistence, stored));
+        gui_context
+    }
+
+    /// Where the data is stored, for contexts made by `new_persistent`
+    pub fn persistence(&self) -> Option<&GuiPersistence> {
+        self.autosave.as_ref().map(|a| a.persistence())
+    }
+    /// Writes the data right away if it changed since the last save
+    pub fn save_now(&mut self) {
+        if let Some(autosave) = self.autosave.as_mut() {
+            autosave.save(&self.gui_builder_new);
+        }
+    }
+}
+
+/// Outcome of sending an event through the hierarchy
+struct Dispatched {
+    response: EventResponse,
+    /// Widget whose typed handler took the event, or that grabbed the pointer
+    handler: Option<usize>,
+    grabbed: bool,
+    default_prevented: bool,
+    started_drag: Option<(DragPayload, Option<DragPreview>)>,
+}
+
+/// Child indices leading from the roots to each widget
+fn widget_paths(parents: &[Option<usize>], widget_graph: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let mut paths: Vec<Vec<usize>> = Vec::with_capacity(parents.len());
+    let mut roots = 0;
+    for (id, parent) in parents.iter().enumerate() {
+        let path = match *parent {
+            Some(parent) => {
+                let mut path = paths[parent].clone();
+                path.push(widget_graph[parent].iter().position(|&c| c == id).unwrap());
+                path
+            }
+            None => {
+                roots += 1;
+                vec![roots - 1]
+            }
+        };
+        paths.push(path);
+    }
+    paths
+}
+
 impl<D> Drop for GuiContext<D>
 where
     D: GuiBuilder,
 {
     fn drop(&mut self) {
+        // the inspector profiles while open, only dump what was asked for
+        self.set_inspector(false);
         if self.profiler.enabled() {
             let path = Path::new("performance.txt");
             let display = path.display();
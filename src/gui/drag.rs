@@ -0,0 +1,90 @@
+use std::any::Any;
+use std::rc::Rc;
+
+use gui::{Align, CornerRadii, DrawBuilder, DrawFill, HAlign, VAlign};
+use tools::*;
+
+/// Value carried by a drag, widgets under the cursor inspect its type to accept or reject it
+#[derive(Clone)]
+pub struct DragPayload {
+    value: Rc<dyn Any>,
+}
+
+impl DragPayload {
+    pub fn new<T: Any>(value: T) -> DragPayload {
+        DragPayload {
+            value: Rc::new(value),
+        }
+    }
+    pub fn get<T: Any>(&self) -> Option<&T> {
+        self.value.downcast_ref()
+    }
+    pub fn is<T: Any>(&self) -> bool {
+        self.value.is::<T>()
+    }
+}
+
+/// Label following the cursor while something is dragged
+#[derive(Clone, Debug)]
+pub struct DragPreview {
+    pub text: String,
+    pub size: Vec2px,
+    pub font: String,
+    pub font_size: f32,
+    pub text_color: Vec4,
+    pub background: Vec4,
+}
+
+impl DragPreview {
+    pub fn text(text: &str) -> DragPreview {
+        DragPreview {
+            text: text.to_owned(),
+            size: Vec2px::new(120.0, 24.0),
+            font: "sans-serif".to_owned(),
+            font_size: 13.0,
+            text_color: Vec4::WHITE,
+            background: Vec4::grey(0.25).with_w(0.85),
+        }
+    }
+
+    pub(super) fn build(&self, builder: &mut DrawBuilder) {
+        builder.add_round_rect(
+            Rect::from_pos_size(Vec2::origin(), self.size.as_vec2()),
+            CornerRadii::all(4.0),
+            DrawFill::Solid(self.background),
+        );
+        builder.add_text(
+            &self.text,
+            &self.font,
+            self.size,
+            self.text_color,
+            Align::from(HAlign::Center, VAlign::Center),
+            self.font_size,
+        );
+    }
+}
+
+/// Drag in progress, `source` is None for files dragged in from outside the window
+pub(super) struct DragState {
+    pub payload: DragPayload,
+    pub preview: Option<DragPreview>,
+    pub source: Option<usize>,
+    pub target: Option<usize>,
+    pub rejected: Vec<usize>,
+}
+
+impl DragState {
+    pub fn new(
+        payload: DragPayload,
+        preview: Option<DragPreview>,
+        source: Option<usize>,
+    ) -> DragState {
+        DragState {
+            payload,
+            preview,
+            source,
+            target: None,
+            rejected: vec![],
+        }
+    }
+}
@@ -1,6 +1,10 @@
+use std::f32::consts::PI;
+use std::ops::Range;
+
 use tools::*;
 
 use graphics::{DrawResources, DrawShaderSelector, RenderCommand, RenderSequence};
+use gui::path::{self, FillRule, Path, StrokeStyle};
 use gui::Align;
 
 #[derive(Debug)]
@@ -10,6 +14,237 @@ pub enum DrawColor {
     Default,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DrawFill {
+    Solid(Vec4),
+    LinearGradient {
+        from: Vec2px,
+        from_clr: Vec4,
+        to: Vec2px,
+        to_clr: Vec4,
+    },
+    RadialGradient {
+        center: Vec2px,
+        radius: f32,
+        inner_clr: Vec4,
+        outer_clr: Vec4,
+    },
+}
+
+impl From<Vec4> for DrawFill {
+    fn from(clr: Vec4) -> DrawFill {
+        DrawFill::Solid(clr)
+    }
+}
+
+impl DrawFill {
+    pub fn linear(from: Vec2px, from_clr: Vec4, to: Vec2px, to_clr: Vec4) -> DrawFill {
+        DrawFill::LinearGradient {
+            from,
+            from_clr,
+            to,
+            to_clr,
+        }
+    }
+    pub fn radial(center: Vec2px, radius: f32, inner_clr: Vec4, outer_clr: Vec4) -> DrawFill {
+        DrawFill::RadialGradient {
+            center,
+            radius,
+            inner_clr,
+            outer_clr,
+        }
+    }
+    pub(super) fn color_at(&self, p: Vec2, gui_scale: f32) -> Vec4 {
+        let mix = |a: Vec4, b: Vec4, t: f32| a * (1.0 - t) + b * t;
+        match *self {
+            DrawFill::Solid(c) => c,
+            DrawFill::LinearGradient {
+                from,
+                from_clr,
+                to,
+                to_clr,
+            } => {
+                let a = from.to_pixels(gui_scale);
+                let d = to.to_pixels(gui_scale) - a;
+                let len2 = d.length_squared();
+                let t = if len2 > 0.0 {
+                    (p - a).dot(d) / len2
+                } else {
+                    0.0
+                };
+                mix(from_clr, to_clr, t.clamp(0.0, 1.0))
+            }
+            DrawFill::RadialGradient {
+                center,
+                radius,
+                inner_clr,
+                outer_clr,
+            } => {
+                let r = radius * gui_scale;
+                let t = if r > 0.0 {
+                    (p - center.to_pixels(gui_scale)).length() / r
+                } else {
+                    1.0
+                };
+                mix(inner_clr, outer_clr, t.clamp(0.0, 1.0))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct CornerRadii {
+    pub top_left: f32,
+    pub top_right: f32,
+    pub bottom_right: f32,
+    pub bottom_left: f32,
+}
+
+impl CornerRadii {
+    pub fn new(top_left: f32, top_right: f32, bottom_right: f32, bottom_left: f32) -> CornerRadii {
+        CornerRadii {
+            top_left,
+            top_right,
+            bottom_right,
+            bottom_left,
+        }
+    }
+    pub fn all(radius: f32) -> CornerRadii {
+        CornerRadii::new(radius, radius, radius, radius)
+    }
+    pub fn top(radius: f32) -> CornerRadii {
+        CornerRadii::new(radius, radius, 0.0, 0.0)
+    }
+    pub fn bottom(radius: f32) -> CornerRadii {
+        CornerRadii::new(0.0, 0.0, radius, radius)
+    }
+    fn as_array(self) -> [f32; 4] {
+        [
+            f32::max(self.top_left, 0.0),
+            f32::max(self.top_right, 0.0),
+            f32::max(self.bottom_right, 0.0),
+            f32::max(self.bottom_left, 0.0),
+        ]
+    }
+    fn scaled(self, s: f32) -> CornerRadii {
+        let r = self.as_array();
+        CornerRadii::new(r[0] * s, r[1] * s, r[2] * s, r[3] * s)
+    }
+    fn shrunk(self, amount: f32) -> CornerRadii {
+        let r = self.as_array();
+        CornerRadii::new(
+            f32::max(r[0] - amount, 0.0),
+            f32::max(r[1] - amount, 0.0),
+            f32::max(r[2] - amount, 0.0),
+            f32::max(r[3] - amount, 0.0),
+        )
+    }
+    /// Scales the radii down uniformly so that neighbouring corners never overlap
+    fn fit(self, size: Vec2) -> CornerRadii {
+        let r = self.as_array();
+        let mut factor: f32 = 1.0;
+        for &(sum, side) in [
+            (r[0] + r[1], size.x),
+            (r[3] + r[2], size.x),
+            (r[0] + r[3], size.y),
+            (r[1] + r[2], size.y),
+        ]
+        .iter()
+        {
+            if sum > side && sum > 0.0 {
+                factor = factor.min(f32::max(side, 0.0) / sum);
+            }
+        }
+        self.scaled(factor)
+    }
+    fn segments(self) -> [usize; 4] {
+        let r = self.as_array();
+        let seg = |r: f32| usize::max(f32::ceil(r * PI / 4.0) as usize, 4);
+        [seg(r[0]), seg(r[1]), seg(r[2]), seg(r[3])]
+    }
+}
+
+pub(super) const AA_FRINGE: f32 = 1.0;
+
+/// Points of a rounded rectangle's outline with their outward normals, clockwise from the left edge
+fn round_rect_outline(rct: Rect, radii: CornerRadii, segments: [usize; 4]) -> Vec<(Vec2, Vec2)> {
+    let r = radii.as_array();
+    let centers = [
+        Vec2::new(rct.left + r[0], rct.top + r[0]),
+        Vec2::new(rct.right - r[1], rct.top + r[1]),
+        Vec2::new(rct.right - r[2], rct.bottom - r[2]),
+        Vec2::new(rct.left + r[3], rct.bottom - r[3]),
+    ];
+    let mut outline = Vec::with_capacity(segments.iter().sum::<usize>() + 4);
+    for c in 0..4 {
+        let a0 = PI + c as f32 * PI / 2.0;
+        for i in 0..=segments[c] {
+            let normal = Vec2::pol(1.0, a0 + i as f32 / segments[c] as f32 * PI / 2.0);
+            outline.push((centers[c] + normal * r[c], normal));
+        }
+    }
+    outline
+}
+
+#[derive(Default)]
+pub(super) struct ShadedTriangles {
+    pts: Vec<Vec2>,
+    clrs: Vec<Vec4>,
+}
+
+impl ShadedTriangles {
+    pub(super) fn triangle(&mut self, a: (Vec2, Vec4), b: (Vec2, Vec4), c: (Vec2, Vec4)) {
+        self.pts.extend_from_slice(&[a.0, b.0, c.0]);
+        self.clrs.extend_from_slice(&[a.1, b.1, c.1]);
+    }
+    pub(super) fn quad(
+        &mut self,
+        a: (Vec2, Vec4),
+        b: (Vec2, Vec4),
+        c: (Vec2, Vec4),
+        d: (Vec2, Vec4),
+    ) {
+        self.triangle(a, b, c);
+        self.triangle(a, c, d);
+    }
+    /// Fades the edge a-b out towards the given normal
+    pub(super) fn fringe(&mut self, a: Vec2, b: Vec2, normal: Vec2, clr: Vec4) {
+        self.quad(
+            (a, clr),
+            (b, clr),
+            (b + normal * AA_FRINGE, clr.with_w(0.0)),
+            (a + normal * AA_FRINGE, clr.with_w(0.0)),
+        );
+    }
+    /// A band of the given half width around the segment p0-p1, with anti-aliased sides
+    pub(super) fn stroke_piece(
+        &mut self,
+        p0: (Vec2, Vec2),
+        p1: (Vec2, Vec2),
+        half_width: f32,
+        clr: Vec4,
+    ) {
+        let clear = clr.with_w(0.0);
+        let (a, na) = p0;
+        let (b, nb) = p1;
+        let (ai, ao) = (a - na * half_width, a + na * half_width);
+        let (bi, bo) = (b - nb * half_width, b + nb * half_width);
+        self.quad((ai, clr), (bi, clr), (bo, clr), (ao, clr));
+        self.quad(
+            (ao, clr),
+            (bo, clr),
+            (bo + nb * AA_FRINGE, clear),
+            (ao + na * AA_FRINGE, clear),
+        );
+        self.quad(
+            (ai - na * AA_FRINGE, clear),
+            (bi - nb * AA_FRINGE, clear),
+            (bi, clr),
+            (ai, clr),
+        );
+    }
+}
+
 #[derive(Debug)]
 struct DrawObject {
     pts: Vec<Vec3>,
@@ -19,11 +254,156 @@ struct DrawObject {
     transparent: bool,
     depth: f32,
     mode: DrawMode,
+    owner: Option<usize>,
+}
+
+/// Corner of a clipped primitive, carrying the attributes interpolated along its edges
+#[derive(Copy, Clone)]
+struct ClipVertex {
+    p: Vec3,
+    clr: Vec4,
+    tpt: Vec2,
+}
+
+impl ClipVertex {
+    fn lerp(self, other: ClipVertex, t: f32) -> ClipVertex {
+        ClipVertex {
+            p: self.p + (other.p - self.p) * t,
+            clr: self.clr + (other.clr - self.clr) * t,
+            tpt: self.tpt + (other.tpt - self.tpt) * t,
+        }
+    }
+}
+
+/// Distances of `p` from the left, right, top and bottom side of `clip`, positive inside
+fn clip_distances(p: Vec3, clip: Rect) -> [f32; 4] {
+    [
+        p.x - clip.left,
+        clip.right - p.x,
+        p.y - clip.top,
+        clip.bottom - p.y,
+    ]
+}
+
+fn clip_polygon(mut poly: Vec<ClipVertex>, clip: Rect) -> Vec<ClipVertex> {
+    for side in 0..4 {
+        let dist = |v: ClipVertex| clip_distances(v.p, clip)[side];
+        let n = poly.len();
+        let mut kept = Vec::with_capacity(n + 1);
+        for i in 0..n {
+            let (a, b) = (poly[i], poly[(i + 1) % n]);
+            let (da, db) = (dist(a), dist(b));
+            if da >= 0.0 {
+                kept.push(a);
+            }
+            if (da >= 0.0) != (db >= 0.0) {
+                kept.push(a.lerp(b, da / (da - db)));
+            }
+        }
+        poly = kept;
+    }
+    poly
+}
+
+fn clip_segment(a: ClipVertex, b: ClipVertex, clip: Rect) -> Option<(ClipVertex, ClipVertex)> {
+    let (da, db) = (clip_distances(a.p, clip), clip_distances(b.p, clip));
+    let (mut t0, mut t1) = (0.0f32, 1.0f32);
+    for side in 0..4 {
+        let (da, db) = (da[side], db[side]);
+        if da < 0.0 && db < 0.0 {
+            return None;
+        } else if da < 0.0 {
+            t0 = f32::max(t0, da / (da - db));
+        } else if db < 0.0 {
+            t1 = f32::min(t1, da / (da - db));
+        }
+    }
+    if t0 <= t1 {
+        Some((a.lerp(b, t0), a.lerp(b, t1)))
+    } else {
+        None
+    }
+}
+
+impl DrawObject {
+    /// Cuts the object to `clip`, triangles become `Triangles` and lines `Lines`
+    fn clipped(self, clip: Rect) -> DrawObject {
+        let inside = |p: &Vec3| clip_distances(*p, clip).iter().all(|&d| d >= 0.0);
+        if self.pts.iter().all(inside) {
+            return self;
+        }
+
+        let vertex = |i: usize| ClipVertex {
+            p: self.pts[i],
+            clr: match &self.clr {
+                DrawColor::Array(clrs) => clrs[i],
+                _ => Vec4::default(),
+            },
+            tpt: self.tpt.as_ref().map_or(Vec2::zero(), |tpt| tpt[i]),
+        };
+        let n = self.pts.len();
+        let mut out: Vec<ClipVertex> = vec![];
+        let triangles = |poly: Vec<ClipVertex>, out: &mut Vec<ClipVertex>| {
+            let poly = clip_polygon(poly, clip);
+            for i in 2..poly.len() {
+                out.extend_from_slice(&[poly[0], poly[i - 1], poly[i]]);
+            }
+        };
+        let mode = match self.mode {
+            DrawMode::Triangles => {
+                for i in (0..n / 3 * 3).step_by(3) {
+                    triangles(vec![vertex(i), vertex(i + 1), vertex(i + 2)], &mut out);
+                }
+                DrawMode::Triangles
+            }
+            DrawMode::TriangleFan => {
+                for i in 2..n {
+                    triangles(vec![vertex(0), vertex(i - 1), vertex(i)], &mut out);
+                }
+                DrawMode::Triangles
+            }
+            DrawMode::Lines | DrawMode::LineStrip | DrawMode::LineLoop => {
+                let segments: Vec<(usize, usize)> = match self.mode {
+                    DrawMode::Lines => (0..n / 2).map(|i| (2 * i, 2 * i + 1)).collect(),
+                    DrawMode::LineStrip => (1..n).map(|i| (i - 1, i)).collect(),
+                    _ => (0..n).map(|i| (i, (i + 1) % n)).collect(),
+                };
+                for (a, b) in segments {
+                    if let Some((a, b)) = clip_segment(vertex(a), vertex(b), clip) {
+                        out.extend_from_slice(&[a, b]);
+                    }
+                }
+                DrawMode::Lines
+            }
+            DrawMode::Points => {
+                out = (0..n).filter(|&i| inside(&self.pts[i])).map(vertex).collect();
+                DrawMode::Points
+            }
+            _ => return self,
+        };
+
+        DrawObject {
+            pts: out.iter().map(|v| v.p).collect(),
+            clr: match self.clr {
+                DrawColor::Array(_) => DrawColor::Array(out.iter().map(|v| v.clr).collect()),
+                clr => clr,
+            },
+            tpt: self.tpt.map(|_| out.iter().map(|v| v.tpt).collect()),
+            mode,
+            ..self
+        }
+    }
 }
 
 pub struct DrawBuilder<'a> {
     objects: Vec<DrawObject>,
     pub offset: Vec3,
+    /// Widget the following objects belong to, lets redraws upload only what changed
+    pub owner: Option<usize>,
+    /// Rects the objects are cut to with the offsets they were given at, the bounds of all a
+    /// widget draws and the clip it sets itself
+    bounds: Option<(Rect, Vec2)>,
+    clip: Option<(Rect, Vec2)>,
     draw_resources: &'a mut DrawResources,
 }
 
@@ -42,15 +422,43 @@ impl<'a> DrawBuilder<'a> {
         DrawBuilder {
             objects: Vec::new(),
             offset: Vec3::zero(),
+            owner: None,
+            bounds: None,
+            clip: None,
             draw_resources,
         }
     }
+    /// Cuts the following objects to `clip`, given relative to the current offset
+    pub fn set_clip(&mut self, clip: Option<Rect>) {
+        self.clip = clip.map(|clip| (clip, self.offset.xy()));
+    }
+    /// Like `set_clip`, but also holds within the clips set afterwards
+    pub fn set_bounds(&mut self, bounds: Option<Rect>) {
+        self.bounds = bounds.map(|bounds| (bounds, self.offset.xy()));
+    }
+    /// Pushes an object whose points are widget coordinates plus the offset, the gui scale is
+    /// only applied when the vertices are uploaded
+    fn push(&mut self, object: DrawObject) {
+        let placed = |(clip, o): (Rect, Vec2)| Rect::from_pos_size(clip.pos() + o, clip.size());
+        let clip = match (self.bounds.map(placed), self.clip.map(placed)) {
+            (Some(bounds), Some(clip)) => Some(bounds.intersection(clip)),
+            (bounds, clip) => bounds.or(clip),
+        };
+        let object = match clip {
+            Some(clip) => object.clipped(clip),
+            None => object,
+        };
+        if !object.pts.is_empty() {
+            self.objects.push(object);
+        }
+    }
     pub fn add_line_strip(&mut self, points: Vec<Vec2px>, clr: Vec4) {
-        self.objects.push(DrawObject {
+        self.push(DrawObject {
+            owner: self.owner,
             pts: offset(
                 points
                     .iter()
-                    .map(|p| Vec3::from_vec2(p.to_pixels(self.gui_scale()), 0.0))
+                    .map(|p| Vec3::from_vec2(p.to_pixels(1.0), 0.0))
                     .collect(),
                 self.offset,
             ),
@@ -68,10 +476,11 @@ impl<'a> DrawBuilder<'a> {
         if !antialias {
             let pts = pts
                 .into_iter()
-                .map(|p| Vec3::from_vec2(p.to_pixels(self.gui_scale()), 0.0))
+                .map(|p| Vec3::from_vec2(p.to_pixels(1.0), 0.0))
                 .collect::<Vec<Vec3>>();
 
-            self.objects.push(DrawObject {
+            self.push(DrawObject {
+                owner: self.owner,
                 pts: offset(pts, self.offset),
                 clr: DrawColor::Const(clr),
                 tpt: None,
@@ -83,9 +492,11 @@ impl<'a> DrawBuilder<'a> {
             return;
         }
 
+        // the fringe is measured in device pixels, points get scaled back down when pushed
+        let scl = self.gui_scale();
         let pts = pts
             .into_iter()
-            .map(|p| p.to_pixels(self.gui_scale()))
+            .map(|p| p.to_pixels(scl))
             .collect::<Vec<Vec2>>();
 
         let norm: Vec<Vec2> = (0..n)
@@ -98,16 +509,14 @@ impl<'a> DrawBuilder<'a> {
             .collect();
 
         let id_to_p = |&i| {
-            Vec3::from_vec2(
-                if i < n {
-                    let normal: Vec2 = norm[i];
+            let p = if i < n {
+                let normal: Vec2 = norm[i];
 
-                    pts[i] - normal * (normal.unsign().minxy() * 1.1 + 0.8)
-                } else {
-                    pts[i - n]
-                },
-                0.0,
-            )
+                pts[i] - normal * (normal.unsign().minxy() * 1.1 + 0.8)
+            } else {
+                pts[i - n]
+            };
+            Vec3::from_vec2(p / scl, 0.0)
         };
 
         let id_to_c = |&i| {
@@ -136,7 +545,8 @@ impl<'a> DrawBuilder<'a> {
         let ids = ids_outline.chain(ids_fill).collect::<Vec<usize>>();
         let ids = ids.iter();
 
-        self.objects.push(DrawObject {
+        self.push(DrawObject {
+            owner: self.owner,
             pts: offset(ids.clone().map(id_to_p).collect(), self.offset),
             clr: DrawColor::Array(ids.map(id_to_c).collect()),
             tpt: None,
@@ -159,8 +569,9 @@ impl<'a> DrawBuilder<'a> {
             return;
         }
 
-        self.objects.push(DrawObject {
-            pts: offset((rct * self.gui_scale()).triangulate_3d(), self.offset),
+        self.push(DrawObject {
+            owner: self.owner,
+            pts: offset(rct.triangulate_3d(), self.offset),
             clr: DrawColor::Const(clr),
             tpt: None,
             tex: None,
@@ -169,6 +580,216 @@ impl<'a> DrawBuilder<'a> {
             mode: DrawMode::Triangles,
         })
     }
+    /// Pushes triangles tessellated in device pixels, so curves and fringes follow the resolution
+    fn add_shaded_triangles(&mut self, tris: ShadedTriangles) {
+        if tris.pts.is_empty() {
+            return;
+        }
+
+        let scl = self.gui_scale();
+        let o = self.offset;
+        self.push(DrawObject {
+            owner: self.owner,
+            pts: tris
+                .pts
+                .into_iter()
+                .map(|p| Vec3::from_vec2(p / scl, 0.0) + o)
+                .collect(),
+            clr: DrawColor::Array(tris.clrs),
+            tpt: None,
+            tex: None,
+            transparent: true,
+            depth: self.offset.z,
+            mode: DrawMode::Triangles,
+        })
+    }
+    pub fn stroke_path(&mut self, path: &Path, style: &StrokeStyle, clr: Vec4) {
+        let tris = path::stroke_triangles(path, style, clr, self.gui_scale());
+        self.add_shaded_triangles(tris);
+    }
+    pub fn fill_path(&mut self, path: &Path, rule: FillRule, fill: DrawFill) {
+        let tris = path::fill_triangles(path, rule, &fill, self.gui_scale());
+        self.add_shaded_triangles(tris);
+    }
+    /// A band of the given width along the points, its color interpolated between the points
+    pub fn add_gradient_strip(&mut self, pts: &[(Vec2px, Vec4)], width: f32, closed: bool) {
+        let n = pts.len();
+        if n < 2 {
+            return;
+        }
+
+        let scl = self.gui_scale();
+        let p: Vec<Vec2> = pts.iter().map(|(p, _)| p.to_pixels(scl)).collect();
+        let normals: Vec<Vec2> = (0..n)
+            .map(|i| {
+                let prev = if i > 0 || closed { (i + n - 1) % n } else { i };
+                let next = if i + 1 < n || closed { (i + 1) % n } else { i };
+                (p[next] - p[prev]).perp().sgn()
+            })
+            .collect();
+
+        let mut tris = ShadedTriangles::default();
+        let segments = if closed { n } else { n - 1 };
+        for i in 0..segments {
+            let j = (i + 1) % n;
+            let (ca, cb) = (pts[i].1, pts[j].1);
+            let (a, b) = (p[i], p[j]);
+            let (na, nb) = (normals[i], normals[j]);
+            let (ha, hb) = (na * width * scl / 2.0, nb * width * scl / 2.0);
+            tris.quad((a - ha, ca), (b - hb, cb), (b + hb, cb), (a + ha, ca));
+            for &side in [1.0, -1.0].iter() {
+                let (ea, eb) = (a + ha * side, b + hb * side);
+                tris.quad(
+                    (ea, ca),
+                    (eb, cb),
+                    (eb + nb * side * AA_FRINGE, cb.with_w(0.0)),
+                    (ea + na * side * AA_FRINGE, ca.with_w(0.0)),
+                );
+            }
+        }
+        self.add_shaded_triangles(tris);
+    }
+    pub fn add_round_rect(&mut self, rct: Rect, radii: CornerRadii, fill: DrawFill) {
+        let scl = self.gui_scale();
+        let rct = rct * scl;
+        let radii = radii.scaled(scl).fit(rct.size());
+        let outline = round_rect_outline(rct, radii, radii.segments());
+        let n = outline.len();
+
+        let mut tris = ShadedTriangles::default();
+        let mid = rct.mid();
+        let mid_clr = fill.color_at(mid, scl);
+        for i in 0..n {
+            let (a, na) = outline[i];
+            let (b, nb) = outline[(i + 1) % n];
+            let (ca, cb) = (fill.color_at(a, scl), fill.color_at(b, scl));
+
+            tris.triangle((mid, mid_clr), (a, ca), (b, cb));
+            tris.quad(
+                (a, ca),
+                (b, cb),
+                (b + nb * AA_FRINGE, cb.with_w(0.0)),
+                (a + na * AA_FRINGE, ca.with_w(0.0)),
+            );
+        }
+        self.add_shaded_triangles(tris);
+    }
+    pub fn add_round_rect_border(
+        &mut self,
+        rct: Rect,
+        radii: CornerRadii,
+        width: f32,
+        fill: DrawFill,
+    ) {
+        let scl = self.gui_scale();
+        let rct = rct * scl;
+        let radii = radii.scaled(scl).fit(rct.size());
+        let width = f32::min(width * scl, rct.min_wh() / 2.0);
+        if width <= 0.0 {
+            return;
+        }
+
+        let inner_rct = Rect {
+            left: rct.left + width,
+            top: rct.top + width,
+            right: rct.right - width,
+            bottom: rct.bottom - width,
+        };
+        let segments = radii.segments();
+        let outer = round_rect_outline(rct, radii, segments);
+        let inner = round_rect_outline(inner_rct, radii.shrunk(width), segments);
+        let n = outer.len();
+
+        let mut tris = ShadedTriangles::default();
+        for i in 0..n {
+            let j = (i + 1) % n;
+            let ((oa, na), (ob, nb)) = (outer[i], outer[j]);
+            let (ia, ib) = (inner[i].0, inner[j].0);
+            let (coa, cob) = (fill.color_at(oa, scl), fill.color_at(ob, scl));
+            let (cia, cib) = (fill.color_at(ia, scl), fill.color_at(ib, scl));
+
+            tris.quad((ia, cia), (ib, cib), (ob, cob), (oa, coa));
+            tris.quad(
+                (oa, coa),
+                (ob, cob),
+                (ob + nb * AA_FRINGE, cob.with_w(0.0)),
+                (oa + na * AA_FRINGE, coa.with_w(0.0)),
+            );
+            tris.quad(
+                (ia - na * AA_FRINGE, cia.with_w(0.0)),
+                (ib - nb * AA_FRINGE, cib.with_w(0.0)),
+                (ib, cib),
+                (ia, cia),
+            );
+        }
+        self.add_shaded_triangles(tris);
+    }
+    pub fn add_round_rect_dashed(
+        &mut self,
+        rct: Rect,
+        radii: CornerRadii,
+        width: f32,
+        dash: f32,
+        gap: f32,
+        clr: Vec4,
+    ) {
+        if dash <= 0.0 || gap <= 0.0 {
+            self.add_round_rect_border(rct, radii, width, DrawFill::Solid(clr));
+            return;
+        }
+
+        let scl = self.gui_scale();
+        let rct = rct * scl;
+        let radii = radii.scaled(scl).fit(rct.size());
+        let half_width = f32::min(width * scl, rct.min_wh() / 2.0) / 2.0;
+        let (dash, gap) = (dash * scl, gap * scl);
+        if half_width <= 0.0 {
+            return;
+        }
+
+        let center_rct = Rect {
+            left: rct.left + half_width,
+            top: rct.top + half_width,
+            right: rct.right - half_width,
+            bottom: rct.bottom - half_width,
+        };
+        let line = round_rect_outline(center_rct, radii.shrunk(half_width), radii.segments());
+        let n = line.len();
+        let period = dash + gap;
+
+        let mut tris = ShadedTriangles::default();
+        let mut dist = 0.0;
+        for i in 0..n {
+            let (a, na) = line[i];
+            let (b, nb) = line[(i + 1) % n];
+            let len = (b - a).length();
+            if len <= 0.0 {
+                continue;
+            }
+
+            let at = |t: f32| {
+                let s = t / len;
+                (a + (b - a) * s, (na * (1.0 - s) + nb * s).sgn())
+            };
+
+            let mut t0 = 0.0;
+            while t0 < len {
+                let phase = (dist + t0) % period;
+                let (on, remaining) = if phase < dash {
+                    (true, dash - phase)
+                } else {
+                    (false, period - phase)
+                };
+                let t1 = f32::min(len, t0 + remaining);
+                if on {
+                    tris.stroke_piece(at(t0), at(t1), half_width, clr);
+                }
+                t0 = t1;
+            }
+            dist += len;
+        }
+        self.add_shaded_triangles(tris);
+    }
     pub fn add_tex(&mut self, pos_mid: Vec2px, tex_name: &str, clr: Vec4, scale: f32) {
         let s = self
             .draw_resources
@@ -189,11 +810,13 @@ impl<'a> DrawBuilder<'a> {
 
         // println!("Adding tex \"{}\" at {:?} with offset {:?}", tex_name, rct.pos(), self.offset);
 
-        self.objects.push(DrawObject {
-            pts: offset((place_rct * self.gui_scale()).triangulate_3d(), self.offset),
+        let tex = self.draw_resources.texture_id(tex_name);
+        self.push(DrawObject {
+            owner: self.owner,
+            pts: offset(place_rct.triangulate_3d(), self.offset),
             clr: DrawColor::Const(clr),
             tpt: Some(cutout_rect.triangulate()),
-            tex: self.draw_resources.texture_id(tex_name),
+            tex,
             transparent: true,
             depth: self.offset.z,
             mode: DrawMode::Triangles,
@@ -211,18 +834,20 @@ impl<'a> DrawBuilder<'a> {
             return;
         }
 
-        let mut pts = offset((place_rct * self.gui_scale()).triangulate_3d(), self.offset);
+        let mut pts = offset(place_rct.triangulate_3d(), self.offset);
         let mid = (pts[0] + pts[1] + pts[2] + pts[5]) / 4.0;
         let r = Mat4::rotate_z(rot);
         for p in pts.iter_mut() {
             *p = (r * Vec4::from_vec3(*p - mid, 0.0)).xyz() + mid;
         }
 
-        self.objects.push(DrawObject {
+        let tex = self.draw_resources.texture_id(tex_name);
+        self.push(DrawObject {
+            owner: self.owner,
             pts,
             clr: DrawColor::Const(clr),
             tpt: Some(cutout_rect.triangulate()),
-            tex: self.draw_resources.texture_id(tex_name),
+            tex,
             transparent: true,
             depth: self.offset.z,
             mode: DrawMode::Triangles,
@@ -237,20 +862,22 @@ impl<'a> DrawBuilder<'a> {
         align: Align,
         font_size: f32,
     ) {
+        // glyphs are rasterized at device resolution, points get scaled back up when uploaded
         let gui_scale = self.gui_scale();
         let font = self.draw_resources.font_family(&font).unwrap();
         let (bb_rects, uv_rects) = font.layout_paragraph(
             &text,
-            f32::round(font_size),
-            f32::round(font_size),
+            f32::round(font_size * gui_scale),
+            f32::round(font_size * gui_scale),
             align,
             size.to_pixels(gui_scale),
         );
         let o = self.offset;
-        self.objects.push(DrawObject {
+        let glyphs = DrawObject {
+            owner: self.owner,
             pts: bb_rects
                 .iter()
-                .map(|r| offset(r.triangulate_3d(), o))
+                .map(|r| offset((*r * (1.0 / gui_scale)).triangulate_3d(), o))
                 .flatten()
                 .collect(),
             clr: DrawColor::Const(clr),
@@ -259,18 +886,20 @@ impl<'a> DrawBuilder<'a> {
             transparent: true,
             depth: self.offset.z,
             mode: DrawMode::Triangles,
-        })
+        };
+        self.push(glyphs)
     }
 
-    fn append_render_seq(&self, beg: usize, end: usize, render_seq: &mut RenderSequence) {
-        let pts: Vec<Vec3> = self.objects[beg..end]
+    fn batch_vertices(&self, beg: usize, end: usize) -> BatchVertices {
+        let objects = &self.objects[beg..end];
+        let pts: Vec<Vec3> = objects
             .iter()
             .map(|o| o.pts.clone())
             .flatten()
             .map(|p| p * self.draw_resources.window_info.gui_scale)
             .collect();
 
-        let clr: Vec<Vec4> = self.objects[beg..end]
+        let clr: Vec<Vec4> = objects
             .iter()
             .map(|o| match &o.clr {
                 DrawColor::Array(v) => v.clone(),
@@ -280,44 +909,41 @@ impl<'a> DrawBuilder<'a> {
             .flatten()
             .collect();
 
-        let pbuf = Buffer::from_vec(&pts);
-        let cbuf = Buffer::from_vec(&clr);
-        let mut vao = VertexArray::new();
-        vao.attrib_buffer(0, &pbuf);
-        vao.attrib_buffer(1, &cbuf);
-        render_seq.add_buffer(pbuf.into_base_type());
-        render_seq.add_buffer(cbuf.into_base_type());
+        let tpt = match (&objects[0].tex, &objects[0].tpt) {
+            (Some(_), Some(_)) => Some(
+                objects
+                    .iter()
+                    .map(|o| o.tpt.clone().unwrap())
+                    .flatten()
+                    .collect(),
+            ),
+            _ => None,
+        };
 
-        let mut uniforms = vec![];
+        // consecutive objects of the same widget share a span
+        let mut spans: Vec<(Option<usize>, Range<usize>)> = vec![];
+        let mut vertex = 0;
+        for o in objects {
+            let range = vertex..vertex + o.pts.len();
+            vertex = range.end;
+            match spans.last_mut() {
+                Some((owner, last)) if *owner == o.owner => last.end = range.end,
+                _ => spans.push((o.owner, range)),
+            }
+        }
 
-        let shader;
-        if let (Some(tex), Some(_)) = (&self.objects[beg].tex, &self.objects[beg].tpt) {
-            let tpt: Vec<Vec2> = self.objects[beg..end]
-                .iter()
-                .map(|o| o.tpt.clone().unwrap())
-                .flatten()
-                .collect();
-            let tbuf = Buffer::from_vec(&tpt);
-            vao.attrib_buffer(2, &tbuf);
-            shader = DrawShaderSelector::Textured;
-            render_seq.add_buffer(tbuf.into_base_type());
-
-            uniforms.push(Uniform::Texture2D("tex".to_owned(), *tex));
-        } else {
-            shader = DrawShaderSelector::Colored;
-        }
-        render_seq.add_command(RenderCommand {
-            vao,
-            mode: self.objects[beg].mode,
-            shader,
-            uniforms,
-            transparent: self.objects[beg].transparent,
-            instances: 1,
-            wireframe: false,
-        });
-    }
-
-    pub fn into_render_sequence(mut self) -> RenderSequence {
+        BatchVertices {
+            mode: objects[0].mode,
+            tex: objects[0].tex,
+            transparent: objects[0].transparent,
+            pts,
+            clr,
+            tpt,
+            spans,
+        }
+    }
+
+    fn into_batches(mut self) -> Vec<BatchVertices> {
         let cmp_dobj = |o1: &DrawObject, o2: &DrawObject| {
             if o1.depth != o2.depth && (o1.transparent || o2.transparent) {
                 o1.depth.partial_cmp(&o2.depth).unwrap()
@@ -330,7 +956,7 @@ impl<'a> DrawBuilder<'a> {
         };
         self.objects.sort_by(cmp_dobj);
         let n = self.objects.len();
-        let mut r = RenderSequence::new();
+        let mut batches = vec![];
         let mut i = 0;
         while i < n {
             let mut j = i + 1;
@@ -339,9 +965,186 @@ impl<'a> DrawBuilder<'a> {
                 j += 1;
             }
 
-            self.append_render_seq(i, j, &mut r);
+            batches.push(self.batch_vertices(i, j));
             i = j;
         }
+        batches
+    }
+
+    pub fn into_render_sequence(self) -> RenderSequence {
+        let mut r = RenderSequence::new();
+        for batch in self.into_batches() {
+            let GpuBatch {
+                pbuf,
+                cbuf,
+                tbuf,
+                command,
+                ..
+            } = GpuBatch::upload(batch);
+            r.add_buffer(pbuf.into_base_type());
+            r.add_buffer(cbuf.into_base_type());
+            if let Some(tbuf) = tbuf {
+                r.add_buffer(tbuf.into_base_type());
+            }
+            r.add_command(command);
+        }
         r
     }
+
+    /// Render sequence reusing the buffers of `previous`, only vertices that changed are uploaded
+    pub fn into_retained(self, previous: Option<RetainedRenderSequence>) -> RetainedRenderSequence {
+        let mut retained = previous.unwrap_or_default();
+        let batches = self.into_batches();
+        retained.uploaded = 0;
+        retained.batches.truncate(batches.len());
+        for (i, batch) in batches.into_iter().enumerate() {
+            if i < retained.batches.len() {
+                retained.uploaded += retained.batches[i].update(batch);
+            } else {
+                retained.uploaded += batch.pts.len();
+                retained.batches.push(GpuBatch::upload(batch));
+            }
+        }
+        retained
+    }
+}
+
+/// Vertices of one draw call, spans tell which widget drew which vertices
+struct BatchVertices {
+    mode: DrawMode,
+    tex: Option<u32>,
+    transparent: bool,
+    pts: Vec<Vec3>,
+    clr: Vec<Vec4>,
+    tpt: Option<Vec<Vec2>>,
+    spans: Vec<(Option<usize>, Range<usize>)>,
+}
+
+impl BatchVertices {
+    fn same_layout(&self, other: &BatchVertices) -> bool {
+        self.mode == other.mode
+            && self.tex == other.tex
+            && self.transparent == other.transparent
+            && self.pts.len() == other.pts.len()
+            && self.tpt.as_ref().map(Vec::len) == other.tpt.as_ref().map(Vec::len)
+    }
+}
+
+/// Spans holding vertices that differ, neighbouring spans are merged into one upload
+fn changed_ranges<T: PartialEq>(
+    old: &[T],
+    new: &[T],
+    spans: &[(Option<usize>, Range<usize>)],
+) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = vec![];
+    for (_, span) in spans {
+        if old[span.clone()] == new[span.clone()] {
+            continue;
+        }
+        match ranges.last_mut() {
+            Some(last) if last.end == span.start => last.end = span.end,
+            _ => ranges.push(span.clone()),
+        }
+    }
+    ranges
+}
+
+fn update_ranges<T: GlNum + PartialEq + Clone>(
+    buf: &mut Buffer<T>,
+    old: &[T],
+    new: &[T],
+    spans: &[(Option<usize>, Range<usize>)],
+) -> usize {
+    let mut uploaded = 0;
+    for range in changed_ranges(old, new, spans) {
+        uploaded += range.end - range.start;
+        buf.update(&new[range.clone()].to_vec(), range.start);
+    }
+    uploaded
+}
+
+/// A batch with its long-lived buffers and the vertices last uploaded to them
+struct GpuBatch {
+    vertices: BatchVertices,
+    pbuf: Buffer<Vec3>,
+    cbuf: Buffer<Vec4>,
+    tbuf: Option<Buffer<Vec2>>,
+    command: RenderCommand,
+}
+
+impl GpuBatch {
+    fn upload(vertices: BatchVertices) -> GpuBatch {
+        let pbuf = Buffer::from_vec(&vertices.pts);
+        let cbuf = Buffer::from_vec(&vertices.clr);
+        let tbuf = vertices.tpt.as_ref().map(Buffer::from_vec);
+        let mut vao = VertexArray::new();
+        vao.attrib_buffer(0, &pbuf);
+        vao.attrib_buffer(1, &cbuf);
+
+        let mut uniforms = vec![];
+        let shader = match (&tbuf, vertices.tex) {
+            (Some(tbuf), Some(tex)) => {
+                vao.attrib_buffer(2, tbuf);
+                uniforms.push(Uniform::Texture2D("tex".to_owned(), tex));
+                DrawShaderSelector::Textured
+            }
+            _ => DrawShaderSelector::Colored,
+        };
+
+        GpuBatch {
+            command: RenderCommand {
+                vao,
+                mode: vertices.mode,
+                shader,
+                uniforms,
+                transparent: vertices.transparent,
+                instances: 1,
+                wireframe: false,
+            },
+            vertices,
+            pbuf,
+            cbuf,
+            tbuf,
+        }
+    }
+
+    /// Upload the difference to `vertices`, returns the number of vertices sent
+    fn update(&mut self, vertices: BatchVertices) -> usize {
+        if !self.vertices.same_layout(&vertices) {
+            let uploaded = vertices.pts.len();
+            *self = GpuBatch::upload(vertices);
+            return uploaded;
+        }
+
+        let old = &self.vertices;
+        let spans = &vertices.spans;
+        let mut uploaded = update_ranges(&mut self.pbuf, &old.pts, &vertices.pts, spans);
+        uploaded += update_ranges(&mut self.cbuf, &old.clr, &vertices.clr, spans);
+        if let (Some(tbuf), Some(old_tpt), Some(tpt)) = (&mut self.tbuf, &old.tpt, &vertices.tpt) {
+            uploaded += update_ranges(tbuf, old_tpt, tpt, spans);
+        }
+        self.vertices = vertices;
+        uploaded
+    }
+}
+
+/// Gui render sequence keeping its vertex buffers between redraws
+#[derive(Default)]
+pub struct RetainedRenderSequence {
+    batches: Vec<GpuBatch>,
+    uploaded: usize,
+}
+
+impl RetainedRenderSequence {
+    pub fn execute(&self, resources: &DrawResources) {
+        let mut previous: Option<&RenderCommand> = None;
+        for batch in &self.batches {
+            batch.command.execute_prev(previous, resources);
+            previous = Some(&batch.command);
+        }
+    }
+    /// Vertex attributes sent to the gpu by the last update
+    pub fn uploaded_vertices(&self) -> usize {
+        self.uploaded
+    }
 }
@@ -6,8 +6,30 @@ use tools::*;
 
 use super::align::*;
 use super::draw::*;
+use super::path::*;
 use super::widget::*;
 
+mod color_picker;
+mod menu;
+mod plot;
+mod shortcut_area;
+mod split_panel;
+mod stack;
+mod tab_view;
+mod table;
+mod tree_view;
+mod virtual_list;
+pub use self::color_picker::{ColorPicker, HueShape};
+pub use self::menu::{ContextMenu, MenuBar, MenuItem, MenuItemKind};
+pub use self::plot::{Plot, PlotKind, PlotSeries};
+pub use self::shortcut_area::ShortcutArea;
+pub use self::split_panel::{SplitDirection, SplitPanel, SplitSide};
+pub use self::stack::{Anchor, Stack, StackItem};
+pub use self::tab_view::{Tab, TabView};
+pub use self::table::{SortOrder, Table, TableColumn, TableValue};
+pub use self::tree_view::{SelectionMode, TreeNode, TreeView};
+pub use self::virtual_list::{RowHeight, VirtualList, VirtualListView};
+
 #[derive(Default, Clone)]
 pub struct SkipCell {}
 
@@ -43,6 +65,7 @@ impl Widget for VertLayout {
     fn child_constraint(&self) -> Option<WidgetConstraints> {
         Some(WidgetConstraints {
             max_size: Vec2px::new(self.private.size.x, std::f32::INFINITY),
+            ..Default::default()
         })
     }
 
@@ -79,6 +102,7 @@ impl PanelDirection {
 #[derive(Default, Clone)]
 pub struct PanelPrivate {
     total_size: Vec2px,
+    constraints: WidgetConstraints,
     child_id: u32,
 }
 
@@ -93,21 +117,23 @@ impl_widget_building_for!(FixedPanel);
 impl Widget for FixedPanel {
     fn constraint(&mut self, self_constraint: WidgetConstraints) {
         self.private.total_size = self_constraint.max_size;
+        self.private.constraints = self_constraint;
     }
     fn place_child(&mut self, _child_size: Vec2px, _child_descent: f32) -> WidgetPosition {
         let ci = self.private.child_id;
         self.private.child_id += 1;
         let s = self.size();
+        let p = self.panel_size();
         match ci {
             0 => match self.dir {
                 PanelDirection::Left | PanelDirection::Top => Vec2px::zero(),
-                PanelDirection::Right => Vec2px::new(s.x - self.size.to_units(s.x), 0.0),
-                PanelDirection::Bottom => Vec2px::new(0.0, s.y - self.size.to_units(s.y)),
+                PanelDirection::Right => Vec2px::new(s.x - p, 0.0),
+                PanelDirection::Bottom => Vec2px::new(0.0, s.y - p),
             },
             1 => match self.dir {
                 PanelDirection::Right | PanelDirection::Bottom => Vec2px::zero(),
-                PanelDirection::Left => Vec2px::new(self.size.to_units(s.x), 0.0),
-                PanelDirection::Top => Vec2px::new(0.0, self.size.to_units(s.y)),
+                PanelDirection::Left => Vec2px::new(p, 0.0),
+                PanelDirection::Top => Vec2px::new(0.0, p),
             },
             _ => Vec2px::zero(),
         }
@@ -120,9 +146,11 @@ impl Widget for FixedPanel {
         match ci {
             0 | 1 => Some(WidgetConstraints {
                 max_size: self.child_space(ci),
+                ..Default::default()
             }),
             _ => Some(WidgetConstraints {
                 max_size: Vec2px::zero(),
+                ..Default::default()
             }),
         }
     }
@@ -133,18 +161,26 @@ impl Widget for FixedPanel {
 }
 
 impl FixedPanel {
+    /// Extent of the fixed panel along the direction
+    fn panel_size(&self) -> f32 {
+        let vertical = match self.dir {
+            PanelDirection::Left | PanelDirection::Right => false,
+            PanelDirection::Top | PanelDirection::Bottom => true,
+        };
+        let ctx = DimensionContext::along(self.size(), vertical, &self.private.constraints);
+        self.size.resolve(&ctx)
+    }
     fn child_space(&self, ci: u32) -> Vec2px {
         let s = self.size();
-        let px = self.size.to_units(s.x);
-        let py = self.size.to_units(s.y);
+        let p = self.panel_size();
 
         use self::PanelDirection::{Bottom, Left, Right, Top};
 
         match (self.dir, ci) {
-            (Left, 0) | (Right, 0) => Vec2px::new(px, s.y),
-            (Left, 1) | (Right, 1) => Vec2px::new(s.x - px, s.y),
-            (Top, 0) | (Bottom, 0) => Vec2px::new(s.x, py),
-            (Top, 1) | (Bottom, 1) => Vec2px::new(s.x, s.y - py),
+            (Left, 0) | (Right, 0) => Vec2px::new(p, s.y),
+            (Left, 1) | (Right, 1) => Vec2px::new(s.x - p, s.y),
+            (Top, 0) | (Bottom, 0) => Vec2px::new(s.x, p),
+            (Top, 1) | (Bottom, 1) => Vec2px::new(s.x, s.y - p),
             _ => Vec2px::zero(),
         }
     }
@@ -170,7 +206,7 @@ pub struct GridLayout {
 impl_widget_building_for!(GridLayout);
 impl Widget for GridLayout {
     fn constraint(&mut self, self_constraint: WidgetConstraints) {
-        self.private.real_size = self.size.to_units(self_constraint.max_size);
+        self.private.real_size = self.size.resolve(&self_constraint);
         if self.row_heights.is_empty() {
             self.row_heights.push(GuiDimension::Relative(1.0));
         }
@@ -178,32 +214,18 @@ impl Widget for GridLayout {
             self.col_widths.push(GuiDimension::Relative(1.0));
         }
         let s = self.size();
-        let tot_rel_w: f32 = self.col_widths.iter().map(|d| d.relative()).sum();
-        let tot_abs_w: f32 = self.col_widths.iter().map(|d| d.absolute()).sum();
-        let tot_rel_h: f32 = self.row_heights.iter().map(|d| d.relative()).sum();
-        let tot_abs_h: f32 = self.row_heights.iter().map(|d| d.absolute()).sum();
-        let unit_per_rel_w = if tot_rel_w == 0.0 {
-            self.private.real_size.x = tot_abs_w;
-            1.0
-        } else {
-            (s.x - tot_abs_w) / tot_rel_w
-        };
-        let unit_per_rel_h = if tot_rel_h == 0.0 {
-            self.private.real_size.y = tot_abs_h;
-            1.0
-        } else {
-            (s.y - tot_abs_h) / tot_rel_h
-        };
-        self.private.col_widths_unit = self
-            .col_widths
-            .iter()
-            .map(|w| w.to_units(unit_per_rel_w))
-            .collect();
-        self.private.row_heights_unit = self
-            .row_heights
-            .iter()
-            .map(|h| h.to_units(unit_per_rel_h))
-            .collect();
+        let c = &self_constraint;
+        // relative tracks share what the others leave, `Aspect` follows the other axis
+        let unit_per_rel_w = solve_shares(&self.col_widths, s.x, s.y, c);
+        let unit_per_rel_h = solve_shares(&self.row_heights, s.y, s.x, c);
+        self.private.col_widths_unit = resolve_tracks(&self.col_widths, unit_per_rel_w, s.y, c);
+        self.private.row_heights_unit = resolve_tracks(&self.row_heights, unit_per_rel_h, s.x, c);
+        if unit_per_rel_w.is_none() {
+            self.private.real_size.x = self.private.col_widths_unit.iter().sum();
+        }
+        if unit_per_rel_h.is_none() {
+            self.private.real_size.y = self.private.row_heights_unit.iter().sum();
+        }
     }
     fn place_child(&mut self, _child_size: Vec2px, _child_descent: f32) -> WidgetPosition {
         let p = self.private.child_pos;
@@ -225,6 +247,7 @@ impl Widget for GridLayout {
                 self.private.col_widths_unit[self.private.child_id % self.col_widths.len()],
                 self.private.row_heights_unit[self.private.child_id / self.col_widths.len()],
             ),
+            ..Default::default()
         })
     }
 
@@ -233,11 +256,29 @@ impl Widget for GridLayout {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+/// Tracks of a grid, relative ones get `share` units per unit of weight
+fn resolve_tracks(
+    tracks: &[GuiDimension],
+    share: Option<f32>,
+    cross: f32,
+    constraints: &WidgetConstraints,
+) -> Vec<f32> {
+    let ctx = DimensionContext {
+        container: share.unwrap_or(1.0),
+        cross,
+        viewport: constraints.viewport,
+        em: constraints.em(),
+    };
+    tracks.iter().map(|t| t.resolve(&ctx)).collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum PaddingValue {
     Default,
     Relative(f32),
     Units(f32),
+    /// Any dimension, relative to the padded size along the same axis
+    Dimension(GuiDimension),
 }
 
 impl Default for PaddingValue {
@@ -246,12 +287,27 @@ impl Default for PaddingValue {
     }
 }
 
+impl From<GuiDimension> for PaddingValue {
+    fn from(d: GuiDimension) -> PaddingValue {
+        PaddingValue::Dimension(d)
+    }
+}
+
 impl PaddingValue {
-    pub fn to_units(self, size: f32) -> f32 {
+    pub fn to_units(&self, size: f32) -> f32 {
+        self.resolve(&DimensionContext {
+            container: size,
+            cross: size,
+            viewport: Vec2px::zero(),
+            em: DEFAULT_EM,
+        })
+    }
+    pub fn resolve(&self, ctx: &DimensionContext) -> f32 {
         match self {
             PaddingValue::Default => 0.0,
-            PaddingValue::Relative(r) => size * r,
-            PaddingValue::Units(x) => x,
+            PaddingValue::Relative(r) => ctx.container * r,
+            PaddingValue::Units(x) => *x,
+            PaddingValue::Dimension(d) => d.resolve(ctx),
         }
     }
 }
@@ -259,6 +315,7 @@ impl PaddingValue {
 #[derive(Debug, Default, Clone)]
 pub struct PaddingPrivate {
     all_size: Vec2px,
+    constraints: WidgetConstraints,
     stacking_depth: f32,
 }
 
@@ -275,14 +332,15 @@ impl_widget_building_for!(Padding);
 impl Widget for Padding {
     fn constraint(&mut self, self_constraint: WidgetConstraints) {
         self.private.all_size = self_constraint.max_size;
+        self.private.constraints = self_constraint;
     }
     fn place_child(&mut self, child_size: Vec2px, child_descent: f32) -> WidgetPosition {
         let s = self.size();
         let sd = self.private.stacking_depth;
         self.private.stacking_depth += child_descent + 0.01;
 
-        let padx = self.left.to_units(s.x);
-        let pady = self.top.to_units(s.y);
+        let padx = self.left.resolve(&self.axis(false));
+        let pady = self.top.resolve(&self.axis(true));
 
         WidgetPosition::new(
             (s - self.pad_size() - child_size) / 2.0 + Vec2px::new(padx, pady),
@@ -292,6 +350,7 @@ impl Widget for Padding {
     fn child_constraint(&self) -> Option<WidgetConstraints> {
         Some(WidgetConstraints {
             max_size: self.size() - self.pad_size(),
+            ..Default::default()
         })
     }
 
@@ -301,11 +360,14 @@ impl Widget for Padding {
 }
 
 impl Padding {
+    fn axis(&self, vertical: bool) -> DimensionContext {
+        DimensionContext::along(self.size(), vertical, &self.private.constraints)
+    }
     fn pad_size(&self) -> Vec2px {
-        let s = self.size();
+        let (x, y) = (self.axis(false), self.axis(true));
         Vec2px::new(
-            self.left.to_units(s.x) + self.right.to_units(s.x),
-            self.top.to_units(s.y) + self.bottom.to_units(s.y),
+            self.left.resolve(&x) + self.right.resolve(&x),
+            self.top.resolve(&y) + self.bottom.resolve(&y),
         )
     }
     pub fn absolute(amount: f32) -> Padding {
@@ -384,7 +446,7 @@ impl FontSize {
     pub fn to_pixels(&self, text_area: f32, gui_scale: f32) -> f32 {
         f32::max(
             match self {
-                FontSize::Em(x) => x * 20.0 * gui_scale,
+                FontSize::Em(x) => x * DEFAULT_EM * gui_scale,
                 FontSize::Relative(r) => r * text_area * gui_scale,
                 FontSize::RelativeSteps(r, (mn, mx), s) => {
                     let stepped = f32::round(r * text_area / s) * s;
@@ -429,7 +491,7 @@ impl Default for Text {
 impl_widget_building_for!(Text);
 impl Widget for Text {
     fn constraint(&mut self, self_constraint: WidgetConstraints) {
-        self.private.real_size = self.size.to_units(self_constraint.max_size);
+        self.private.real_size = self.size.resolve(&self_constraint);
     }
     fn on_draw_build(&self, builder: &mut DrawBuilder) {
         builder.add_text(
@@ -504,11 +566,12 @@ impl Default for Button {
 impl_widget_building_for!(Button);
 impl Widget for Button {
     fn constraint(&mut self, self_constraint: WidgetConstraints) {
-        self.private.real_size = self.size.to_units(self_constraint.max_size);
+        self.private.real_size = self.size.resolve(&self_constraint);
     }
     fn child_constraint(&self) -> Option<WidgetConstraints> {
         Some(WidgetConstraints {
             max_size: self.size(),
+            ..Default::default()
         })
     }
     fn on_press(
@@ -524,6 +587,10 @@ impl Widget for Button {
         self.private.state = ButtonState::Hovered;
         EventResponse::HandledRedraw
     }
+    fn on_pointer_lost(&mut self, _executor: &mut CallbackExecutor) -> EventResponse {
+        self.private.state = ButtonState::Normal;
+        EventResponse::HandledRedraw
+    }
     fn on_cursor_enter(&mut self, _executor: &mut CallbackExecutor) -> EventResponse {
         self.private.state = ButtonState::Hovered;
         EventResponse::HandledRedraw
@@ -594,29 +661,11 @@ fn build_draw_for_button(
             builder.add_clr_rect(Rect::from_min_max(Vec2::origin(), size.as_vec2()), clr);
         }
         ButtonBckg::RoundRect(_, radius) => {
-            let n = ((radius * 2.0 * PI / 4.0).ceil() * 4.0) as usize;
-            let mut pts = Vec::with_capacity(n);
-            let s = size / 2.0 - Vec2px::new_xy(radius);
-            let mut r0 = PI / 2.0;
-
-            for offset in [
-                Vec2px::new(1.0, 1.0),
-                Vec2px::new(1.0, -1.0),
-                Vec2px::new(-1.0, -1.0),
-                Vec2px::new(-1.0, 1.0),
-            ]
-            .iter()
-            {
-                let circle_mid = size / 2.0 + s * *offset;
-
-                for i in 0..n / 4 {
-                    let t = i as f32 / (n / 4) as f32;
-                    pts.push(Vec2px::pol(radius, r0 - t * PI / 2.0) + circle_mid);
-                }
-
-                r0 -= PI / 2.0;
-            }
-            builder.add_clr_convex(pts, clr, true);
+            builder.add_round_rect(
+                Rect::from_min_max(Vec2::origin(), size.as_vec2()),
+                CornerRadii::all(radius),
+                DrawFill::Solid(clr),
+            );
         }
         ButtonBckg::Image(name, _, _, _) => {
             builder.add_tex_rect(
@@ -664,7 +713,7 @@ impl Default for LinearBar {
 impl_widget_building_for!(LinearBar);
 impl Widget for LinearBar {
     fn constraint(&mut self, self_constraint: WidgetConstraints) {
-        self.private.real_size = self.size.to_units(self_constraint.max_size);
+        self.private.real_size = self.size.resolve(&self_constraint);
     }
     fn on_press(
         &mut self,
@@ -681,6 +730,10 @@ impl Widget for LinearBar {
         self.private.state = ButtonState::Hovered;
         EventResponse::HandledRedraw
     }
+    fn on_pointer_lost(&mut self, _executor: &mut CallbackExecutor) -> EventResponse {
+        self.private.state = ButtonState::Normal;
+        EventResponse::HandledRedraw
+    }
     fn on_cursor_enter(&mut self, _executor: &mut CallbackExecutor) -> EventResponse {
         self.private.state = ButtonState::Hovered;
         EventResponse::HandledRedraw
@@ -776,7 +829,7 @@ pub struct Image {
 impl_widget_building_for!(Image);
 impl Widget for Image {
     fn constraint(&mut self, self_constraint: WidgetConstraints) {
-        self.private.real_size = self.size.to_units(self_constraint.max_size);
+        self.private.real_size = self.size.resolve(&self_constraint);
     }
     fn on_draw_build(&self, builder: &mut DrawBuilder) {
         let size = self.size().to_pixels(1.0);
@@ -829,6 +882,7 @@ impl Widget for Square {
     fn child_constraint(&self) -> Option<WidgetConstraints> {
         Some(WidgetConstraints {
             max_size: self.private.inner_size,
+            ..Default::default()
         })
     }
 
@@ -864,6 +918,7 @@ impl Widget for OuterSquare {
     fn child_constraint(&self) -> Option<WidgetConstraints> {
         Some(WidgetConstraints {
             max_size: self.private.inner_size,
+            ..Default::default()
         })
     }
 
@@ -919,6 +974,7 @@ impl Widget for Overlay {
     fn child_constraint(&self) -> Option<WidgetConstraints> {
         Some(WidgetConstraints {
             max_size: self.private.size,
+            ..Default::default()
         })
     }
 
@@ -936,24 +992,39 @@ pub struct LinesPrivate {
     real_size: Vec2px,
 }
 
-#[derive(Default, Clone)]
+#[derive(Clone)]
 pub struct Lines {
     pub size: WidgetSize,
     pub lines: Vec<(GuiPoint, GuiPoint)>,
     pub color: Vec4,
+    pub width: f32,
     pub private: LinesPrivate,
 }
 
+impl Default for Lines {
+    fn default() -> Lines {
+        Lines {
+            size: Default::default(),
+            lines: Default::default(),
+            color: Default::default(),
+            width: 1.0,
+            private: Default::default(),
+        }
+    }
+}
+
 impl_widget_building_for!(Lines);
 impl Widget for Lines {
     fn constraint(&mut self, self_constraint: WidgetConstraints) {
-        self.private.real_size = self.size.to_units(self_constraint.max_size);
+        self.private.real_size = self.size.resolve(&self_constraint);
     }
     fn on_draw_build(&self, builder: &mut DrawBuilder) {
         let s = self.size();
+        let mut path = Path::new();
         for (a, b) in &self.lines {
-            builder.add_line_strip(vec![a.to_units(s), b.to_units(s)], self.color);
+            path.move_to(a.to_units(s)).line_to(b.to_units(s));
         }
+        builder.stroke_path(&path, &StrokeStyle::new(self.width), self.color);
     }
     fn size(&self) -> Vec2px {
         self.private.real_size
@@ -1027,7 +1098,7 @@ pub struct SplineEditor {
 impl_widget_building_for!(SplineEditor);
 impl Widget for SplineEditor {
     fn constraint(&mut self, self_constraint: WidgetConstraints) {
-        self.private.real_size = self.size.to_units(self_constraint.max_size);
+        self.private.real_size = self.size.resolve(&self_constraint);
     }
     fn on_press(
         &mut self,
@@ -1053,6 +1124,10 @@ impl Widget for SplineEditor {
         self.private.hover = None;
         EventResponse::Handled
     }
+    fn on_pointer_lost(&mut self, _executor: &mut CallbackExecutor) -> EventResponse {
+        self.private.grab = None;
+        EventResponse::Handled
+    }
 
     fn on_cursor_move(
         &mut self,
@@ -1093,7 +1168,11 @@ impl Widget for SplineEditor {
             Rect::from_pos_size(Vec2::origin(), self.size().to_pixels(1.0)),
             Vec4::WHITE.with_w(0.2),
         );
-        builder.add_line_strip(pts, self.color);
+        builder.stroke_path(
+            &Path::from_points(pts, false),
+            &StrokeStyle::round(2.0),
+            self.color,
+        );
         for i in 0..self.points.len() {
             let p = self.points[i];
             let c = if self.private.grab == Some(i) {
@@ -0,0 +1,581 @@
+use std::f32::consts::{FRAC_1_SQRT_2, PI};
+use std::ops::{Neg, Shl};
+
+use gui::widget::*;
+use gui::*;
+use mecs::GlutinKey;
+use tools::*;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum HueShape {
+    Ring,
+    Bar,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum PickerPart {
+    Hue,
+    SatVal,
+    Alpha,
+    Hex,
+    Swatch(usize),
+}
+
+#[derive(Default, Clone)]
+pub struct ColorPickerPrivate {
+    real_size: Vec2px,
+    hsv: Option<Vec4>,
+    drag: Option<PickerPart>,
+    hex_edit: Option<String>,
+    base: Option<Vec<Vec4>>,
+}
+
+#[derive(Clone)]
+pub struct ColorPicker {
+    pub size: WidgetSize,
+    pub color: Vec4,
+    pub hue_shape: HueShape,
+    pub alpha: bool,
+    pub history: Vec<Vec4>,
+    pub history_len: usize,
+    pub background: Vec4,
+    pub text_color: Vec4,
+    pub font: String,
+    pub font_size: FontSize,
+    pub callback: GuiCallback<ColorPicker>,
+    pub private: ColorPickerPrivate,
+}
+
+impl Default for ColorPicker {
+    fn default() -> ColorPicker {
+        ColorPicker {
+            size: Default::default(),
+            color: Vec4::WHITE,
+            hue_shape: HueShape::Ring,
+            alpha: true,
+            history: vec![],
+            history_len: 8,
+            background: Vec4::grey(0.1),
+            text_color: Vec4::WHITE,
+            font: "sans-serif".to_owned(),
+            font_size: FontSize::Em(0.7),
+            callback: Default::default(),
+            private: Default::default(),
+        }
+    }
+}
+
+const PAD: f32 = 6.0;
+const ROW: f32 = 20.0;
+const ALPHA_HEIGHT: f32 = 12.0;
+const SWATCH: f32 = 16.0;
+const CHECKER: f32 = 6.0;
+
+struct PickerLayout {
+    center: Vec2,
+    outer_radius: f32,
+    ring_width: f32,
+    hue_bar: Rect,
+    sat_val: Rect,
+    alpha: Rect,
+    swatch: Rect,
+    hex: Rect,
+    history: Vec<Rect>,
+}
+
+fn px(p: Vec2) -> Vec2px {
+    Vec2px::new(p.x, p.y)
+}
+
+fn ratio(x: f32, lo: f32, hi: f32) -> f32 {
+    if hi > lo {
+        ((x - lo) / (hi - lo)).clamp(0.0, 1.0)
+    } else {
+        0.0
+    }
+}
+
+fn same_color(a: Vec4, b: Vec4) -> bool {
+    let d = a - b;
+    d.x.abs() < 1e-3 && d.y.abs() < 1e-3 && d.z.abs() < 1e-3 && d.w.abs() < 1e-3
+}
+
+fn add_checker(builder: &mut DrawBuilder, rct: Rect) {
+    builder.add_clr_rect(rct, Vec4::grey(0.8));
+    let cols = (rct.width() / CHECKER).ceil() as usize;
+    let rows = (rct.height() / CHECKER).ceil() as usize;
+    for i in 0..cols {
+        for j in (i % 2..rows).step_by(2) {
+            let min = Vec2::new(rct.left + i as f32 * CHECKER, rct.top + j as f32 * CHECKER);
+            let max = Vec2::new(
+                f32::min(min.x + CHECKER, rct.right),
+                f32::min(min.y + CHECKER, rct.bottom),
+            );
+            builder.add_clr_rect(Rect::from_min_max(min, max), Vec4::grey(0.5));
+        }
+    }
+}
+
+fn add_marker(builder: &mut DrawBuilder, p: Vec2, radius: f32, clr: Vec4) {
+    builder.add_round_rect_border(
+        Rect::from_min_max(p - Vec2::new_xy(radius), p + Vec2::new_xy(radius)),
+        CornerRadii::all(radius),
+        2.0,
+        DrawFill::Solid(clr),
+    );
+}
+
+impl ColorPicker {
+    pub fn new(color: Vec4, callback: GuiCallback<ColorPicker>) -> ColorPicker {
+        ColorPicker {
+            color,
+            callback,
+            ..Default::default()
+        }
+    }
+
+    fn layout(&self) -> PickerLayout {
+        let s = self.size().as_vec2();
+        let history_top = s.y - PAD - SWATCH;
+        let hex_top = if self.history_len > 0 {
+            history_top - PAD - ROW
+        } else {
+            s.y - PAD - ROW
+        };
+        let alpha_top = hex_top - PAD - ALPHA_HEIGHT;
+        let top = Rect::from_min_max(
+            Vec2::new_xy(PAD),
+            Vec2::new(
+                f32::max(s.x - PAD, PAD),
+                f32::max(if self.alpha { alpha_top } else { hex_top } - PAD, PAD),
+            ),
+        );
+
+        let center = top.mid();
+        let outer_radius = top.min_wh() / 2.0;
+        let ring_width = f32::max(outer_radius * 0.15, 6.0);
+        let (hue_bar, sat_val) = match self.hue_shape {
+            HueShape::Ring => {
+                let half = f32::max((outer_radius - ring_width) * FRAC_1_SQRT_2 - 3.0, 0.0);
+                (
+                    Rect::new(),
+                    Rect::from_min_max(center - Vec2::new_xy(half), center + Vec2::new_xy(half)),
+                )
+            }
+            HueShape::Bar => {
+                let split = f32::max(top.right - SWATCH, top.left);
+                (
+                    Rect::from_min_max(Vec2::new(split, top.top), Vec2::new(top.right, top.bottom)),
+                    Rect::from_min_max(
+                        Vec2::new(top.left, top.top),
+                        Vec2::new(f32::max(split - PAD, top.left), top.bottom),
+                    ),
+                )
+            }
+        };
+
+        let history = (0..self.history_len)
+            .map(|i| {
+                Rect::from_pos_size(
+                    Vec2::new(PAD + i as f32 * (SWATCH + 4.0), history_top),
+                    Vec2::new_xy(SWATCH),
+                )
+            })
+            .filter(|r| r.right <= s.x - PAD)
+            .collect();
+
+        PickerLayout {
+            center,
+            outer_radius,
+            ring_width,
+            hue_bar,
+            sat_val,
+            alpha: Rect::from_min_max(
+                Vec2::new(PAD, alpha_top),
+                Vec2::new(s.x - PAD, alpha_top + ALPHA_HEIGHT),
+            ),
+            swatch: Rect::from_pos_size(Vec2::new(PAD, hex_top), Vec2::new(ROW * 2.0, ROW)),
+            hex: Rect::from_min_max(
+                Vec2::new(PAD * 2.0 + ROW * 2.0, hex_top),
+                Vec2::new(s.x - PAD, hex_top + ROW),
+            ),
+            history,
+        }
+    }
+
+    fn part_at(&self, p: Vec2) -> Option<PickerPart> {
+        let layout = self.layout();
+        let dist = (p - layout.center).length();
+        let on_hue = match self.hue_shape {
+            HueShape::Ring => {
+                dist <= layout.outer_radius && dist >= layout.outer_radius - layout.ring_width
+            }
+            HueShape::Bar => layout.hue_bar.contains(p),
+        };
+
+        if on_hue {
+            Some(PickerPart::Hue)
+        } else if layout.sat_val.contains(p) {
+            Some(PickerPart::SatVal)
+        } else if self.alpha && layout.alpha.contains(p) {
+            Some(PickerPart::Alpha)
+        } else if layout.hex.contains(p) {
+            Some(PickerPart::Hex)
+        } else {
+            layout
+                .history
+                .iter()
+                .take(self.history.len())
+                .position(|r| r.contains(p))
+                .map(PickerPart::Swatch)
+        }
+    }
+
+    /// The edited color in hsv, remembering the hue of greys while the picker lives
+    fn hsv(&self) -> Vec4 {
+        match self.private.hsv {
+            Some(hsv) if same_color(Vec4::from_hsv(hsv.x, hsv.y, hsv.z, hsv.w), self.color) => hsv,
+            _ => self.color.to_hsv(),
+        }
+    }
+
+    fn set_hsv(&mut self, hsv: Vec4) {
+        self.private.hsv = Some(hsv);
+        self.color = Vec4::from_hsv(hsv.x, hsv.y, hsv.z, hsv.w);
+    }
+
+    fn drag_to(&mut self, part: PickerPart, p: Vec2) {
+        let layout = self.layout();
+        let mut hsv = self.hsv();
+        match part {
+            PickerPart::Hue => {
+                hsv.x = match self.hue_shape {
+                    HueShape::Ring => {
+                        let d = p - layout.center;
+                        let angle = d.y.atan2(d.x) / (2.0 * PI);
+                        angle - angle.floor()
+                    }
+                    HueShape::Bar => ratio(p.y, layout.hue_bar.top, layout.hue_bar.bottom),
+                }
+            }
+            PickerPart::SatVal => {
+                hsv.y = ratio(p.x, layout.sat_val.left, layout.sat_val.right);
+                hsv.z = 1.0 - ratio(p.y, layout.sat_val.top, layout.sat_val.bottom);
+            }
+            PickerPart::Alpha => hsv.w = ratio(p.x, layout.alpha.left, layout.alpha.right),
+            _ => {}
+        }
+        self.set_hsv(hsv);
+    }
+
+    fn remember_color(&mut self) {
+        if self.history_len == 0 {
+            return;
+        }
+        let color = self.color;
+        self.history.retain(|c| !same_color(*c, color));
+        self.history.insert(0, color);
+        self.history.truncate(self.history_len);
+    }
+
+    fn commit_hex(&mut self, executor: &mut CallbackExecutor) {
+        let text = match self.private.hex_edit.take() {
+            Some(text) => text,
+            None => return,
+        };
+        if let Some(color) = Vec4::from_hex(&text) {
+            let digits = text.trim_start_matches('#').len();
+            self.color = if digits == 3 || digits == 6 {
+                color.with_w(self.color.w)
+            } else {
+                color
+            };
+            self.private.hsv = None;
+            self.remember_color();
+            executor.execute(&self.callback, self);
+        }
+    }
+
+    fn build_hue(&self, builder: &mut DrawBuilder, layout: &PickerLayout, hue: f32) {
+        match self.hue_shape {
+            HueShape::Ring => {
+                let n = 72;
+                let radius = layout.outer_radius - layout.ring_width / 2.0;
+                let pts: Vec<(Vec2px, Vec4)> = (0..n)
+                    .map(|i| {
+                        let t = i as f32 / n as f32;
+                        (
+                            px(layout.center + Vec2::pol(radius, t * 2.0 * PI)),
+                            Vec4::from_hsv(t, 1.0, 1.0, 1.0),
+                        )
+                    })
+                    .collect();
+                builder.add_gradient_strip(&pts, layout.ring_width, true);
+                add_marker(
+                    builder,
+                    layout.center + Vec2::pol(radius, hue * 2.0 * PI),
+                    layout.ring_width / 2.0,
+                    Vec4::WHITE,
+                );
+            }
+            HueShape::Bar => {
+                let bar = layout.hue_bar;
+                let x = bar.mid().x;
+                let pts: Vec<(Vec2px, Vec4)> = (0..=6)
+                    .map(|i| {
+                        let t = i as f32 / 6.0;
+                        (
+                            Vec2px::new(x, bar.top + t * bar.height()),
+                            Vec4::from_hsv(t, 1.0, 1.0, 1.0),
+                        )
+                    })
+                    .collect();
+                builder.add_gradient_strip(&pts, bar.width(), false);
+                let y = bar.top + hue * bar.height();
+                builder.add_round_rect_border(
+                    Rect::from_min_max(
+                        Vec2::new(bar.left - 2.0, y - 3.0),
+                        Vec2::new(bar.right + 2.0, y + 3.0),
+                    ),
+                    CornerRadii::all(2.0),
+                    2.0,
+                    DrawFill::Solid(Vec4::WHITE),
+                );
+            }
+        }
+    }
+
+    fn build_sat_val(&self, builder: &mut DrawBuilder, layout: &PickerLayout, hsv: Vec4) {
+        let sv = layout.sat_val;
+        builder.add_round_rect(
+            sv,
+            CornerRadii::all(0.0),
+            DrawFill::linear(
+                Vec2px::new(sv.left, sv.top),
+                Vec4::WHITE,
+                Vec2px::new(sv.right, sv.top),
+                Vec4::from_hsv(hsv.x, 1.0, 1.0, 1.0),
+            ),
+        );
+        builder.add_round_rect(
+            sv,
+            CornerRadii::all(0.0),
+            DrawFill::linear(
+                Vec2px::new(sv.left, sv.top),
+                Vec4::BLACK.with_w(0.0),
+                Vec2px::new(sv.left, sv.bottom),
+                Vec4::BLACK,
+            ),
+        );
+        let p = Vec2::new(
+            sv.left + hsv.y * sv.width(),
+            sv.top + (1.0 - hsv.z) * sv.height(),
+        );
+        let clr = if hsv.z > 0.5 && hsv.y < 0.5 {
+            Vec4::BLACK
+        } else {
+            Vec4::WHITE
+        };
+        add_marker(builder, p, 5.0, clr);
+    }
+
+    fn build_alpha(&self, builder: &mut DrawBuilder, layout: &PickerLayout) {
+        let bar = layout.alpha;
+        add_checker(builder, bar);
+        builder.add_round_rect(
+            bar,
+            CornerRadii::all(0.0),
+            DrawFill::linear(
+                Vec2px::new(bar.left, bar.top),
+                self.color.with_w(0.0),
+                Vec2px::new(bar.right, bar.top),
+                self.color.with_w(1.0),
+            ),
+        );
+        let x = bar.left + self.color.w * bar.width();
+        builder.add_round_rect_border(
+            Rect::from_min_max(
+                Vec2::new(x - 3.0, bar.top - 2.0),
+                Vec2::new(x + 3.0, bar.bottom + 2.0),
+            ),
+            CornerRadii::all(2.0),
+            2.0,
+            DrawFill::Solid(Vec4::WHITE),
+        );
+    }
+
+    fn build_hex(&self, builder: &mut DrawBuilder, layout: &PickerLayout) {
+        add_checker(builder, layout.swatch);
+        builder.add_clr_rect(layout.swatch, self.color);
+
+        let editing = self.private.hex_edit.is_some();
+        let field = layout.hex;
+        builder.add_round_rect(
+            field,
+            CornerRadii::all(3.0),
+            DrawFill::Solid((self.background * 1.5 + Vec4::grey(0.05)).with_w(self.background.w)),
+        );
+        if editing {
+            builder.add_round_rect_border(
+                field,
+                CornerRadii::all(3.0),
+                1.0,
+                DrawFill::Solid(self.text_color.with_w(0.6)),
+            );
+        }
+
+        let (text, clr) = match self.private.hex_edit {
+            Some(ref text) if text.is_empty() => ("#|".to_owned(), self.text_color.with_w(0.5)),
+            Some(ref text) => (format!("{}|", text), self.text_color),
+            None => (self.color.to_hex(), self.text_color),
+        };
+        let o = builder.offset;
+        builder.offset = o + Vec3::from_vec2(field.pos() + Vec2::new(4.0, 0.0), 0.0);
+        builder.add_text(
+            &text,
+            &self.font,
+            Vec2px::new(field.width() - 8.0, field.height()),
+            clr,
+            Align::left(),
+            self.font_size.to_pixels(field.height(), 1.0),
+        );
+        builder.offset = o;
+    }
+}
+
+impl_widget_building_for!(ColorPicker);
+impl Widget for ColorPicker {
+    fn constraint(&mut self, self_constraint: WidgetConstraints) {
+        self.private.real_size = self.size.resolve(&self_constraint);
+        if self.private.base.is_none() {
+            self.private.base = Some(self.history.clone());
+        }
+    }
+    fn adopt_state(&mut self, previous: &dyn Widget) {
+        if let Some(previous) = previous.downcast_ref::<ColorPicker>() {
+            // swatches added by picking stay until the data sets a new history
+            if previous.private.base.as_ref() == Some(&self.history) {
+                self.history = previous.history.clone();
+                self.private.base = previous.private.base.clone();
+            }
+            self.private.hsv = previous.private.hsv;
+            self.private.drag = previous.private.drag;
+            self.private.hex_edit = previous.private.hex_edit.clone();
+        }
+    }
+    fn on_press(
+        &mut self,
+        local_cursor_pos: Vec2px,
+        executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        let part = self.part_at(local_cursor_pos.as_vec2());
+        if part != Some(PickerPart::Hex) {
+            self.commit_hex(executor);
+        }
+
+        match part {
+            Some(PickerPart::Hex) => {
+                self.private.hex_edit = Some(String::new());
+            }
+            Some(PickerPart::Swatch(i)) => {
+                self.color = self.history[i];
+                self.private.hsv = None;
+                self.remember_color();
+                executor.execute(&self.callback, self);
+            }
+            Some(part) => {
+                self.private.drag = Some(part);
+                self.drag_to(part, local_cursor_pos.as_vec2());
+                executor.execute(&self.callback, self);
+            }
+            None => {}
+        }
+        EventResponse::HandledRedraw
+    }
+    fn on_release(&mut self, executor: &mut CallbackExecutor) -> EventResponse {
+        if self.private.drag.take().is_some() {
+            self.remember_color();
+            executor.execute(&self.callback, self);
+        }
+        EventResponse::HandledRedraw
+    }
+    fn on_pointer_lost(&mut self, _executor: &mut CallbackExecutor) -> EventResponse {
+        self.private.drag = None;
+        EventResponse::HandledRedraw
+    }
+    fn on_cursor_move(
+        &mut self,
+        local_cursor_pos: Vec2px,
+        executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        match self.private.drag {
+            Some(part) => {
+                self.drag_to(part, local_cursor_pos.as_vec2());
+                executor.execute(&self.callback, self);
+                EventResponse::HandledRedraw
+            }
+            None => EventResponse::Pass,
+        }
+    }
+    fn on_key_press(&mut self, key: GlutinKey, executor: &mut CallbackExecutor) -> EventResponse {
+        if self.private.hex_edit.is_none() {
+            return EventResponse::Pass;
+        }
+        match key {
+            GlutinKey::Back => {
+                if let Some(ref mut text) = self.private.hex_edit {
+                    text.pop();
+                }
+            }
+            GlutinKey::Return | GlutinKey::NumpadEnter => self.commit_hex(executor),
+            GlutinKey::Escape => self.private.hex_edit = None,
+            _ => return EventResponse::Handled,
+        }
+        EventResponse::HandledRedraw
+    }
+    fn on_char(&mut self, ch: char, _executor: &mut CallbackExecutor) -> EventResponse {
+        match self.private.hex_edit {
+            Some(ref mut text) => {
+                let accepted = ch.is_ascii_hexdigit() || (ch == '#' && text.is_empty());
+                if accepted && text.trim_start_matches('#').len() < 8 {
+                    text.push(ch.to_ascii_uppercase());
+                    EventResponse::HandledRedraw
+                } else {
+                    EventResponse::Handled
+                }
+            }
+            None => EventResponse::Pass,
+        }
+    }
+    fn on_focus_lost(&mut self, executor: &mut CallbackExecutor) -> EventResponse {
+        if self.private.hex_edit.is_some() {
+            self.commit_hex(executor);
+            EventResponse::HandledRedraw
+        } else {
+            EventResponse::Pass
+        }
+    }
+    fn on_draw_build(&self, builder: &mut DrawBuilder) {
+        let layout = self.layout();
+        let hsv = self.hsv();
+
+        builder.add_round_rect(
+            Rect::from_min_max(Vec2::origin(), self.size().as_vec2()),
+            CornerRadii::all(4.0),
+            DrawFill::Solid(self.background),
+        );
+        self.build_hue(builder, &layout, hsv.x);
+        self.build_sat_val(builder, &layout, hsv);
+        if self.alpha {
+            self.build_alpha(builder, &layout);
+        }
+        self.build_hex(builder, &layout);
+        for (rct, clr) in layout.history.iter().zip(self.history.iter()) {
+            add_checker(builder, *rct);
+            builder.add_clr_rect(*rct, *clr);
+        }
+    }
+    fn size(&self) -> Vec2px {
+        self.private.real_size
+    }
+}
@@ -0,0 +1,888 @@
+use std::ops::{Neg, Shl};
+
+use gui::widget::*;
+use gui::*;
+use mecs::{GlutinButton, GlutinKey, StaticWorld};
+use tools::*;
+
+#[derive(Clone)]
+pub enum MenuItemKind {
+    Action,
+    Check(bool),
+    Separator,
+    Submenu(Vec<MenuItem>),
+}
+
+/// Entry of a menu bar or context menu, the callback receives the item with its new check state
+#[derive(Clone)]
+pub struct MenuItem {
+    pub text: String,
+    pub kind: MenuItemKind,
+    pub shortcut: Option<Shortcut>,
+    pub enabled: bool,
+    pub callback: GuiCallback<MenuItem>,
+}
+
+impl Default for MenuItem {
+    fn default() -> MenuItem {
+        MenuItem {
+            text: String::new(),
+            kind: MenuItemKind::Action,
+            shortcut: None,
+            enabled: true,
+            callback: Default::default(),
+        }
+    }
+}
+
+impl MenuItem {
+    pub fn action(text: &str, callback: GuiCallback<MenuItem>) -> MenuItem {
+        MenuItem {
+            text: text.to_owned(),
+            callback,
+            ..Default::default()
+        }
+    }
+    pub fn check(text: &str, checked: bool, callback: GuiCallback<MenuItem>) -> MenuItem {
+        MenuItem {
+            kind: MenuItemKind::Check(checked),
+            ..MenuItem::action(text, callback)
+        }
+    }
+    pub fn submenu(text: &str, items: Vec<MenuItem>) -> MenuItem {
+        MenuItem {
+            kind: MenuItemKind::Submenu(items),
+            text: text.to_owned(),
+            ..Default::default()
+        }
+    }
+    pub fn separator() -> MenuItem {
+        MenuItem {
+            kind: MenuItemKind::Separator,
+            ..Default::default()
+        }
+    }
+    pub fn shortcut(mut self, shortcut: Shortcut) -> MenuItem {
+        self.shortcut = Some(shortcut);
+        self
+    }
+    pub fn disabled(mut self) -> MenuItem {
+        self.enabled = false;
+        self
+    }
+
+    pub fn is_checked(&self) -> bool {
+        match self.kind {
+            MenuItemKind::Check(checked) => checked,
+            _ => false,
+        }
+    }
+    fn is_selectable(&self) -> bool {
+        match self.kind {
+            MenuItemKind::Separator => false,
+            _ => self.enabled,
+        }
+    }
+    fn children(&self) -> Option<&[MenuItem]> {
+        match &self.kind {
+            MenuItemKind::Submenu(items) => Some(items),
+            _ => None,
+        }
+    }
+    fn height(&self) -> f32 {
+        match self.kind {
+            MenuItemKind::Separator => SEPARATOR_HEIGHT,
+            _ => ITEM_HEIGHT,
+        }
+    }
+}
+
+// items only need to be widgets so they can own callbacks
+impl Widget for MenuItem {
+    fn size(&self) -> Vec2px {
+        Vec2px::zero()
+    }
+}
+
+const ITEM_HEIGHT: f32 = 22.0;
+const SEPARATOR_HEIGHT: f32 = 7.0;
+const LIST_PAD: f32 = 4.0;
+const CHECK_SPACE: f32 = 22.0;
+const ARROW_SPACE: f32 = 18.0;
+const POPUP_DEPTH: f32 = 0.45;
+
+fn list_height(items: &[MenuItem]) -> f32 {
+    LIST_PAD * 2.0 + items.iter().map(MenuItem::height).sum::<f32>()
+}
+fn row_top(items: &[MenuItem], index: usize) -> f32 {
+    LIST_PAD + items[..index].iter().map(MenuItem::height).sum::<f32>()
+}
+
+/// Fires the item's callback, check items are handed over already toggled
+fn trigger(item: &MenuItem, executor: &mut CallbackExecutor) {
+    match item.kind {
+        MenuItemKind::Check(checked) => {
+            let mut toggled = item.clone();
+            toggled.kind = MenuItemKind::Check(!checked);
+            executor.execute(&item.callback, &toggled);
+        }
+        _ => {
+            executor.execute(&item.callback, item);
+        }
+    }
+}
+
+fn find_shortcut<'a>(
+    items: &'a [MenuItem],
+    key: GlutinKey,
+    world: &StaticWorld,
+) -> Option<&'a MenuItem> {
+    for item in items.iter().filter(|item| item.enabled) {
+        if let Some(children) = item.children() {
+            if let Some(found) = find_shortcut(children, key, world) {
+                return Some(found);
+            }
+        } else if let Some(shortcut) = item.shortcut {
+            if shortcut.matches(key, world) {
+                return Some(item);
+            }
+        }
+    }
+    None
+}
+
+/// Colors and font shared by the popups of menu bars and context menus
+struct MenuLook<'a> {
+    font: &'a str,
+    font_size: f32,
+    text_color: Vec4,
+    disabled_color: Vec4,
+    background: Vec4,
+    hover_color: Vec4,
+}
+
+/// Open popup of a menu with its chain of open submenus
+#[derive(Default, Clone)]
+struct MenuPopup {
+    origin: Vec2,
+    open: Vec<usize>,
+    hover: Option<(usize, usize)>,
+}
+
+impl MenuPopup {
+    fn at(origin: Vec2) -> MenuPopup {
+        MenuPopup {
+            origin,
+            ..Default::default()
+        }
+    }
+
+    /// Item list and placement of the top popup and every open submenu
+    fn levels<'a>(&self, items: &'a [MenuItem], width: f32) -> Vec<(&'a [MenuItem], Rect)> {
+        let mut levels = vec![(
+            items,
+            Rect::from_pos_size(self.origin, Vec2::new(width, list_height(items))),
+        )];
+        for &index in &self.open {
+            let (list, rct) = levels[levels.len() - 1];
+            match list.get(index).and_then(MenuItem::children) {
+                Some(children) => {
+                    let pos = Vec2::new(rct.right - 2.0, rct.top + row_top(list, index) - LIST_PAD);
+                    let size = Vec2::new(width, list_height(children));
+                    levels.push((children, Rect::from_pos_size(pos, size)));
+                }
+                None => break,
+            }
+        }
+        levels
+    }
+    fn area(&self, items: &[MenuItem], width: f32) -> Rect {
+        let levels = self.levels(items, width);
+        let mut area = levels[0].1;
+        for (_, rct) in &levels[1..] {
+            area = Rect::from_min_max(
+                Vec2::new(area.left.min(rct.left), area.top.min(rct.top)),
+                Vec2::new(area.right.max(rct.right), area.bottom.max(rct.bottom)),
+            );
+        }
+        area
+    }
+    fn contains(&self, items: &[MenuItem], width: f32, p: Vec2) -> bool {
+        self.levels(items, width)
+            .iter()
+            .any(|(_, rct)| rct.contains(p))
+    }
+    fn item_at(&self, items: &[MenuItem], width: f32, p: Vec2) -> Option<(usize, usize)> {
+        let levels = self.levels(items, width);
+        for (level, (list, rct)) in levels.iter().enumerate().rev() {
+            if !rct.contains(p) {
+                continue;
+            }
+            return (0..list.len())
+                .find(|&i| {
+                    let top = rct.top + row_top(list, i);
+                    p.y >= top && p.y < top + list[i].height()
+                })
+                .map(|i| (level, i));
+        }
+        None
+    }
+    fn item<'a>(&self, items: &'a [MenuItem], width: f32, at: (usize, usize)) -> &'a MenuItem {
+        &self.levels(items, width)[at.0].0[at.1]
+    }
+
+    /// Tracks the hovered item and opens submenus under the cursor
+    fn hover(&mut self, items: &[MenuItem], width: f32, p: Vec2) -> bool {
+        let hover = self.item_at(items, width, p);
+        if hover == self.hover {
+            return false;
+        }
+        if let Some((level, index)) = hover {
+            self.open.truncate(level);
+            let item = self.item(items, width, (level, index));
+            if item.enabled && item.children().is_some() {
+                self.open.push(index);
+            }
+        }
+        self.hover = hover;
+        true
+    }
+
+    /// Moves the keyboard selection, returns the item to trigger on enter
+    fn navigate(
+        &mut self,
+        items: &[MenuItem],
+        width: f32,
+        key: GlutinKey,
+    ) -> Option<(usize, usize)> {
+        let levels = self.levels(items, width);
+        let (level, index) = match self.hover {
+            Some(hover) if hover.0 < levels.len() => hover,
+            _ => (levels.len() - 1, usize::MAX),
+        };
+        let list = levels[level].0;
+        let step = |from: usize, forward: bool| {
+            let n = list.len();
+            let mut i = from;
+            for _ in 0..n {
+                i = match (i, forward) {
+                    (i, true) if i >= n - 1 => 0,
+                    (i, true) => i + 1,
+                    (0, false) => n - 1,
+                    (i, false) if i >= n => n - 1,
+                    (i, false) => i - 1,
+                };
+                if list[i].is_selectable() {
+                    return Some(i);
+                }
+            }
+            None
+        };
+        match key {
+            GlutinKey::Down | GlutinKey::Up if !list.is_empty() => {
+                if let Some(i) = step(index, key == GlutinKey::Down) {
+                    self.open.truncate(level);
+                    self.hover = Some((level, i));
+                }
+            }
+            GlutinKey::Right if index < list.len() => {
+                if let Some(children) = list[index].children().filter(|_| list[index].enabled) {
+                    self.open.truncate(level);
+                    self.open.push(index);
+                    self.hover = children
+                        .iter()
+                        .position(MenuItem::is_selectable)
+                        .map(|i| (level + 1, i));
+                }
+            }
+            GlutinKey::Left if level > 0 => {
+                self.hover = Some((level - 1, self.open[level - 1]));
+                self.open.truncate(level - 1);
+            }
+            GlutinKey::Return | GlutinKey::NumpadEnter if index < list.len() => {
+                return Some((level, index));
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn build(&self, builder: &mut DrawBuilder, items: &[MenuItem], width: f32, look: &MenuLook) {
+        let o = builder.offset;
+        for (level, (list, rct)) in self.levels(items, width).iter().enumerate() {
+            // deeper submenus cover their parents
+            builder.offset = o + Vec3::new(0.0, 0.0, POPUP_DEPTH + level as f32 * 0.01);
+            builder.add_round_rect(
+                *rct,
+                CornerRadii::all(4.0),
+                DrawFill::Solid(look.background),
+            );
+            builder.add_round_rect_border(
+                *rct,
+                CornerRadii::all(4.0),
+                1.0,
+                DrawFill::Solid(look.hover_color),
+            );
+
+            for (i, item) in list.iter().enumerate() {
+                let top = rct.top + row_top(list, i);
+                let row = Rect::from_pos_size(
+                    Vec2::new(rct.left, top),
+                    Vec2::new(rct.width(), item.height()),
+                );
+                if let MenuItemKind::Separator = item.kind {
+                    let mut line = Path::new();
+                    line.move_to(Vec2px::new(row.left + 6.0, row.mid().y))
+                        .line_to(Vec2px::new(row.right - 6.0, row.mid().y));
+                    builder.stroke_path(&line, &StrokeStyle::new(1.0), look.hover_color);
+                    continue;
+                }
+
+                let highlighted =
+                    self.hover == Some((level, i)) || self.open.get(level) == Some(&i);
+                if highlighted && item.enabled {
+                    builder.add_clr_rect(row, look.hover_color);
+                }
+                let clr = if item.enabled {
+                    look.text_color
+                } else {
+                    look.disabled_color
+                };
+
+                if item.is_checked() {
+                    let c = Vec2::new(row.left + CHECK_SPACE / 2.0, row.mid().y);
+                    let mut tick = Path::new();
+                    tick.move_to(Vec2px::new(c.x - 4.0, c.y))
+                        .line_to(Vec2px::new(c.x - 1.0, c.y + 3.0))
+                        .line_to(Vec2px::new(c.x + 4.0, c.y - 4.0));
+                    builder.stroke_path(&tick, &StrokeStyle::new(1.5), clr);
+                }
+                add_label(
+                    builder,
+                    &item.text,
+                    Rect::from_min_max(
+                        Vec2::new(row.left + CHECK_SPACE, row.top),
+                        Vec2::new(row.right - ARROW_SPACE, row.bottom),
+                    ),
+                    Align::left(),
+                    clr,
+                    look,
+                );
+                if let Some(shortcut) = item.shortcut {
+                    add_label(
+                        builder,
+                        &shortcut.to_string(),
+                        Rect::from_min_max(row.pos(), Vec2::new(row.right - 8.0, row.bottom)),
+                        Align::right(),
+                        clr,
+                        look,
+                    );
+                }
+                if item.children().is_some() {
+                    let c = Vec2::new(row.right - ARROW_SPACE / 2.0, row.mid().y);
+                    let mut arrow = Path::new();
+                    arrow
+                        .move_to(Vec2px::new(c.x - 2.0, c.y - 4.0))
+                        .line_to(Vec2px::new(c.x + 2.0, c.y))
+                        .line_to(Vec2px::new(c.x - 2.0, c.y + 4.0))
+                        .close();
+                    builder.fill_path(&arrow, FillRule::NonZero, DrawFill::Solid(clr));
+                }
+            }
+        }
+        builder.offset = o;
+    }
+}
+
+fn add_label(
+    builder: &mut DrawBuilder,
+    text: &str,
+    rct: Rect,
+    align: Align,
+    clr: Vec4,
+    look: &MenuLook,
+) {
+    let o = builder.offset;
+    builder.offset = o + Vec3::from_vec2(rct.pos(), 0.0);
+    builder.add_text(
+        text,
+        look.font,
+        Vec2px::new(rct.width().max(0.0), rct.height()),
+        clr,
+        align,
+        look.font_size,
+    );
+    builder.offset = o;
+}
+
+/// Presses the popup item at `at`, returns whether the popup should close
+fn press_item(
+    popup: &mut MenuPopup,
+    items: &[MenuItem],
+    width: f32,
+    at: (usize, usize),
+    executor: &mut CallbackExecutor,
+) -> bool {
+    let item = popup.item(items, width, at);
+    if !item.is_selectable() {
+        return false;
+    }
+    if item.children().is_some() {
+        popup.open.truncate(at.0);
+        popup.open.push(at.1);
+        return false;
+    }
+    trigger(item, executor);
+    true
+}
+
+#[derive(Default, Clone)]
+pub struct MenuBarPrivate {
+    real_size: Vec2px,
+    open: Option<usize>,
+    popup: MenuPopup,
+    hover: Option<usize>,
+}
+
+/// Row of menu titles opening popups with nested submenus
+#[derive(Clone)]
+pub struct MenuBar {
+    pub size: WidgetSize,
+    /// Top level menus, their submenu items fill the popups
+    pub menus: Vec<MenuItem>,
+    pub title_width: f32,
+    pub menu_width: f32,
+    pub font: String,
+    pub font_size: FontSize,
+    pub text_color: Vec4,
+    pub disabled_color: Vec4,
+    pub background: Vec4,
+    pub popup_background: Vec4,
+    pub hover_color: Vec4,
+    pub private: MenuBarPrivate,
+}
+
+impl Default for MenuBar {
+    fn default() -> MenuBar {
+        MenuBar {
+            size: WidgetSize {
+                x: GuiDimension::Default,
+                y: GuiDimension::Units(24.0),
+            },
+            menus: vec![],
+            title_width: 64.0,
+            menu_width: 200.0,
+            font: "sans-serif".to_owned(),
+            font_size: FontSize::Em(0.65),
+            text_color: Vec4::WHITE,
+            disabled_color: Vec4::grey(0.5),
+            background: Vec4::grey(0.15),
+            popup_background: Vec4::grey(0.12),
+            hover_color: Vec4::grey(1.0).with_w(0.12),
+            private: Default::default(),
+        }
+    }
+}
+
+impl MenuBar {
+    pub fn new(menus: Vec<MenuItem>) -> MenuBar {
+        MenuBar {
+            menus,
+            ..Default::default()
+        }
+    }
+
+    fn open_items(&self) -> &[MenuItem] {
+        self.private
+            .open
+            .and_then(|i| self.menus.get(i))
+            .and_then(MenuItem::children)
+            .unwrap_or(&[])
+    }
+    fn title_at(&self, p: Vec2) -> Option<usize> {
+        if p.y < 0.0 || p.y >= self.size().y || p.x < 0.0 {
+            return None;
+        }
+        Some((p.x / self.title_width) as usize).filter(|&i| i < self.menus.len())
+    }
+    fn open_menu(&mut self, menu: Option<usize>) {
+        self.private.open = menu.filter(|&i| self.menus[i].enabled);
+        self.private.popup = MenuPopup::at(Vec2::new(
+            menu.unwrap_or(0) as f32 * self.title_width,
+            self.size().y,
+        ));
+    }
+    fn look(&self) -> MenuLook<'_> {
+        MenuLook {
+            font: &self.font,
+            font_size: self.font_size.to_pixels(ITEM_HEIGHT, 1.0),
+            text_color: self.text_color,
+            disabled_color: self.disabled_color,
+            background: self.popup_background,
+            hover_color: self.hover_color,
+        }
+    }
+}
+
+impl_widget_building_for!(MenuBar);
+impl Widget for MenuBar {
+    fn constraint(&mut self, self_constraint: WidgetConstraints) {
+        self.private.real_size = self.size.resolve(&self_constraint);
+        // menus removed by a rebuild close
+        if self
+            .private
+            .open
+            .filter(|&i| i < self.menus.len())
+            .is_none()
+        {
+            self.open_menu(None);
+        }
+    }
+    fn popup_area(&self) -> Option<Rect> {
+        self.private
+            .open
+            .map(|_| self.private.popup.area(self.open_items(), self.menu_width))
+    }
+    fn adopt_state(&mut self, previous: &dyn Widget) {
+        if let Some(previous) = previous.downcast_ref::<MenuBar>() {
+            self.private.open = previous.private.open;
+            self.private.popup = previous.private.popup.clone();
+        }
+    }
+    fn on_press(
+        &mut self,
+        local_cursor_pos: Vec2px,
+        executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        let p = local_cursor_pos.as_vec2();
+        if self.private.open.is_some() {
+            let items = self.open_items().to_vec();
+            if let Some(at) = self.private.popup.item_at(&items, self.menu_width, p) {
+                if press_item(
+                    &mut self.private.popup,
+                    &items,
+                    self.menu_width,
+                    at,
+                    executor,
+                ) {
+                    self.open_menu(None);
+                }
+                return EventResponse::HandledRedraw;
+            }
+        }
+        match self.title_at(p) {
+            Some(i) if self.private.open != Some(i) => self.open_menu(Some(i)),
+            _ => self.open_menu(None),
+        }
+        EventResponse::HandledRedraw
+    }
+    fn on_cursor_move(
+        &mut self,
+        local_cursor_pos: Vec2px,
+        _executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        let p = local_cursor_pos.as_vec2();
+        let title = self.title_at(p);
+        if self.private.open.is_some() && title.is_some() && title != self.private.open {
+            // sliding along the bar switches between open menus
+            self.open_menu(title);
+            self.private.hover = title;
+            return EventResponse::HandledRedraw;
+        }
+
+        let mut changed = title != self.private.hover;
+        self.private.hover = title;
+        if self.private.open.is_some() {
+            let items = self.open_items().to_vec();
+            changed |= self.private.popup.hover(&items, self.menu_width, p);
+        }
+        if changed {
+            EventResponse::HandledRedraw
+        } else {
+            EventResponse::Handled
+        }
+    }
+    fn on_cursor_leave(&mut self, _executor: &mut CallbackExecutor) -> EventResponse {
+        self.private.hover = None;
+        self.private.popup.hover = None;
+        EventResponse::HandledRedraw
+    }
+    fn on_key_press(&mut self, key: GlutinKey, executor: &mut CallbackExecutor) -> EventResponse {
+        if self.private.open.is_none() {
+            return EventResponse::Pass;
+        }
+        let items = self.open_items().to_vec();
+        let popup = &mut self.private.popup;
+        // the hover adopted from before a rebuild may point past the items left
+        let (on_top_level, hovers_submenu) = match popup.hover {
+            Some((0, i)) => (true, items.get(i).and_then(MenuItem::children).is_some()),
+            Some(_) => (false, false),
+            None => (true, false),
+        };
+        match key {
+            GlutinKey::Escape if popup.open.is_empty() => self.open_menu(None),
+            GlutinKey::Escape => {
+                popup.hover = Some((popup.open.len() - 1, popup.open[popup.open.len() - 1]));
+                popup.open.pop();
+            }
+            GlutinKey::Left | GlutinKey::Right
+                if on_top_level && (key == GlutinKey::Left || !hovers_submenu) =>
+            {
+                // walk to the neighbouring menu of the bar
+                let n = self.menus.len();
+                let open = self.private.open.unwrap_or(0);
+                let next = if key == GlutinKey::Left {
+                    (open + n - 1) % n
+                } else {
+                    (open + 1) % n
+                };
+                self.open_menu(Some(next));
+            }
+            _ => {
+                if let Some(at) = popup.navigate(&items, self.menu_width, key) {
+                    if press_item(popup, &items, self.menu_width, at, executor) {
+                        self.open_menu(None);
+                    }
+                }
+            }
+        }
+        EventResponse::HandledRedraw
+    }
+    fn on_accelerator(&mut self, key: GlutinKey, executor: &mut CallbackExecutor) -> EventResponse {
+        match find_shortcut(&self.menus, key, executor.world()) {
+            Some(item) => {
+                let item = item.clone();
+                trigger(&item, executor);
+                self.open_menu(None);
+                EventResponse::HandledRedraw
+            }
+            None => EventResponse::Pass,
+        }
+    }
+    fn on_focus_lost(&mut self, _executor: &mut CallbackExecutor) -> EventResponse {
+        if self.private.open.is_some() {
+            self.open_menu(None);
+            EventResponse::HandledRedraw
+        } else {
+            EventResponse::Pass
+        }
+    }
+    fn on_draw_build(&self, builder: &mut DrawBuilder) {
+        let size = self.size();
+        builder.add_clr_rect(
+            Rect::from_min_max(Vec2::origin(), size.as_vec2()),
+            self.background,
+        );
+
+        let look = self.look();
+        for (i, menu) in self.menus.iter().enumerate() {
+            let rct = Rect::from_pos_size(
+                Vec2::new(i as f32 * self.title_width, 0.0),
+                Vec2::new(self.title_width, size.y),
+            );
+            if menu.enabled && (self.private.open == Some(i) || self.private.hover == Some(i)) {
+                builder.add_clr_rect(rct, self.hover_color);
+            }
+            let clr = if menu.enabled {
+                self.text_color
+            } else {
+                self.disabled_color
+            };
+            add_label(
+                builder,
+                &menu.text,
+                rct,
+                Align::from(HAlign::Center, VAlign::Center),
+                clr,
+                &look,
+            );
+        }
+
+        if self.private.open.is_some() {
+            self.private
+                .popup
+                .build(builder, self.open_items(), self.menu_width, &look);
+        }
+    }
+    fn size(&self) -> Vec2px {
+        self.private.real_size
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct ContextMenuPrivate {
+    real_size: Vec2px,
+    popup: Option<MenuPopup>,
+}
+
+/// Container opening a menu at the cursor when its content is right-clicked
+#[derive(Clone)]
+pub struct ContextMenu {
+    pub size: WidgetSize,
+    pub items: Vec<MenuItem>,
+    pub menu_width: f32,
+    pub font: String,
+    pub font_size: FontSize,
+    pub text_color: Vec4,
+    pub disabled_color: Vec4,
+    pub background: Vec4,
+    pub hover_color: Vec4,
+    pub private: ContextMenuPrivate,
+}
+
+impl Default for ContextMenu {
+    fn default() -> ContextMenu {
+        ContextMenu {
+            size: Default::default(),
+            items: vec![],
+            menu_width: 200.0,
+            font: "sans-serif".to_owned(),
+            font_size: FontSize::Em(0.65),
+            text_color: Vec4::WHITE,
+            disabled_color: Vec4::grey(0.5),
+            background: Vec4::grey(0.12),
+            hover_color: Vec4::grey(1.0).with_w(0.12),
+            private: Default::default(),
+        }
+    }
+}
+
+impl ContextMenu {
+    pub fn new(items: Vec<MenuItem>) -> ContextMenu {
+        ContextMenu {
+            items,
+            ..Default::default()
+        }
+    }
+
+    fn look(&self) -> MenuLook<'_> {
+        MenuLook {
+            font: &self.font,
+            font_size: self.font_size.to_pixels(ITEM_HEIGHT, 1.0),
+            text_color: self.text_color,
+            disabled_color: self.disabled_color,
+            background: self.background,
+            hover_color: self.hover_color,
+        }
+    }
+}
+
+impl_widget_building_for!(ContextMenu);
+impl Widget for ContextMenu {
+    fn constraint(&mut self, self_constraint: WidgetConstraints) {
+        self.private.real_size = self.size.resolve(&self_constraint);
+    }
+    fn place_child(&mut self, _child_size: Vec2px, _child_descent: f32) -> WidgetPosition {
+        WidgetPosition::new(Vec2px::zero(), 0.01)
+    }
+    fn child_constraint(&self) -> Option<WidgetConstraints> {
+        Some(WidgetConstraints {
+            max_size: self.size(),
+            ..Default::default()
+        })
+    }
+    fn popup_area(&self) -> Option<Rect> {
+        self.private
+            .popup
+            .as_ref()
+            .map(|popup| popup.area(&self.items, self.menu_width))
+    }
+    fn adopt_state(&mut self, previous: &dyn Widget) {
+        if let Some(previous) = previous.downcast_ref::<ContextMenu>() {
+            self.private.popup = previous.private.popup.clone();
+        }
+    }
+    fn on_button_press(
+        &mut self,
+        button: GlutinButton,
+        local_cursor_pos: Vec2px,
+        _executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        if button != GlutinButton::Right || self.items.is_empty() {
+            return EventResponse::Pass;
+        }
+        self.private.popup = Some(MenuPopup::at(local_cursor_pos.as_vec2()));
+        EventResponse::HandledRedraw
+    }
+    fn on_press(
+        &mut self,
+        local_cursor_pos: Vec2px,
+        executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        let popup = match self.private.popup.as_mut() {
+            Some(popup) => popup,
+            None => return EventResponse::Pass,
+        };
+        let p = local_cursor_pos.as_vec2();
+        let close = match popup.item_at(&self.items, self.menu_width, p) {
+            Some(at) => press_item(popup, &self.items, self.menu_width, at, executor),
+            None => !popup.contains(&self.items, self.menu_width, p),
+        };
+        if close {
+            self.private.popup = None;
+        }
+        EventResponse::HandledRedraw
+    }
+    fn on_cursor_move(
+        &mut self,
+        local_cursor_pos: Vec2px,
+        _executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        let changed = match self.private.popup.as_mut() {
+            Some(popup) => popup.hover(&self.items, self.menu_width, local_cursor_pos.as_vec2()),
+            None => return EventResponse::Pass,
+        };
+        if changed {
+            EventResponse::HandledRedraw
+        } else {
+            EventResponse::Handled
+        }
+    }
+    fn on_key_press(&mut self, key: GlutinKey, executor: &mut CallbackExecutor) -> EventResponse {
+        let popup = match self.private.popup.as_mut() {
+            Some(popup) => popup,
+            None => return EventResponse::Pass,
+        };
+        let close = match key {
+            GlutinKey::Escape if popup.open.is_empty() => true,
+            GlutinKey::Escape => {
+                popup.hover = Some((popup.open.len() - 1, popup.open[popup.open.len() - 1]));
+                popup.open.pop();
+                false
+            }
+            _ => match popup.navigate(&self.items, self.menu_width, key) {
+                Some(at) => press_item(popup, &self.items, self.menu_width, at, executor),
+                None => false,
+            },
+        };
+        if close {
+            self.private.popup = None;
+        }
+        EventResponse::HandledRedraw
+    }
+    fn on_accelerator(&mut self, key: GlutinKey, executor: &mut CallbackExecutor) -> EventResponse {
+        match find_shortcut(&self.items, key, executor.world()) {
+            Some(item) => {
+                trigger(item, executor);
+                self.private.popup = None;
+                EventResponse::HandledRedraw
+            }
+            None => EventResponse::Pass,
+        }
+    }
+    fn on_focus_lost(&mut self, _executor: &mut CallbackExecutor) -> EventResponse {
+        if self.private.popup.take().is_some() {
+            EventResponse::HandledRedraw
+        } else {
+            EventResponse::Pass
+        }
+    }
+    fn on_draw_build(&self, builder: &mut DrawBuilder) {
+        if let Some(popup) = &self.private.popup {
+            popup.build(builder, &self.items, self.menu_width, &self.look());
+        }
+    }
+    fn size(&self) -> Vec2px {
+        self.private.real_size
+    }
+}
@@ -0,0 +1,689 @@
+use std::ops::{Neg, Shl};
+use std::time::{Duration, Instant};
+
+use gui::widget::*;
+use gui::*;
+use tools::*;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PlotKind {
+    Line,
+    Smooth,
+    Scatter,
+    Bars,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlotSeries {
+    pub name: String,
+    pub points: Vec<Vec2>,
+    pub kind: PlotKind,
+    pub color: Vec4,
+    /// Line width for lines, marker radius for scatters and relative bar width for bars
+    pub width: f32,
+}
+
+impl PlotSeries {
+    pub fn new(name: &str, kind: PlotKind, points: Vec<Vec2>, color: Vec4) -> PlotSeries {
+        PlotSeries {
+            name: name.to_owned(),
+            points,
+            kind,
+            color,
+            width: match kind {
+                PlotKind::Line | PlotKind::Smooth => 2.0,
+                PlotKind::Scatter => 3.0,
+                PlotKind::Bars => 0.8,
+            },
+        }
+    }
+    pub fn line(name: &str, points: Vec<Vec2>, color: Vec4) -> PlotSeries {
+        PlotSeries::new(name, PlotKind::Line, points, color)
+    }
+    pub fn smooth(name: &str, points: Vec<Vec2>, color: Vec4) -> PlotSeries {
+        PlotSeries::new(name, PlotKind::Smooth, points, color)
+    }
+    pub fn scatter(name: &str, points: Vec<Vec2>, color: Vec4) -> PlotSeries {
+        PlotSeries::new(name, PlotKind::Scatter, points, color)
+    }
+    pub fn bars(name: &str, points: Vec<Vec2>, color: Vec4) -> PlotSeries {
+        PlotSeries::new(name, PlotKind::Bars, points, color)
+    }
+    pub fn histogram(name: &str, samples: &[f32], bins: usize, color: Vec4) -> PlotSeries {
+        let bins = usize::max(bins, 1);
+        let mn = samples.iter().cloned().fold(std::f32::INFINITY, f32::min);
+        let mx = samples
+            .iter()
+            .cloned()
+            .fold(std::f32::NEG_INFINITY, f32::max);
+        if samples.is_empty() {
+            return PlotSeries::bars(name, vec![], color);
+        }
+
+        let bin_width = if mx > mn {
+            (mx - mn) / bins as f32
+        } else {
+            1.0
+        };
+        let mut counts = vec![0; bins];
+        for s in samples {
+            let i = ((s - mn) / bin_width) as usize;
+            counts[usize::min(i, bins - 1)] += 1;
+        }
+
+        let points = counts
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| Vec2::new(mn + (i as f32 + 0.5) * bin_width, c as f32))
+            .collect();
+        PlotSeries {
+            width: 1.0,
+            ..PlotSeries::bars(name, points, color)
+        }
+    }
+
+    fn bar_width(&self) -> f32 {
+        let mut xs: Vec<f32> = self
+            .points
+            .iter()
+            .map(|p| p.x)
+            .filter(|x| x.is_finite())
+            .collect();
+        xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let spacing = xs
+            .windows(2)
+            .map(|w| w[1] - w[0])
+            .filter(|d| *d > 0.0)
+            .fold(std::f32::INFINITY, f32::min);
+
+        if spacing.is_finite() {
+            spacing * self.width
+        } else {
+            self.width
+        }
+    }
+
+    fn curve(&self) -> Vec<Vec2> {
+        if self.kind != PlotKind::Smooth || self.points.len() < 3 {
+            return self.points.clone();
+        }
+
+        let ys = self
+            .points
+            .iter()
+            .map(|p| Vec3::from_vec2(*p, 0.0))
+            .collect();
+        Spline::fit_cubic((0.0..1.0).linspace(self.points.len()), ys)
+            .quantize(self.points.len() * 8)
+            .iter()
+            .map(|p| p.xy())
+            .collect()
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct PlotPrivate {
+    real_size: Vec2px,
+    view: Option<(Vec2, Vec2)>,
+    /// Range fitting the data when the view was moved, the view is kept while it stays so
+    view_base: Option<(Vec2, Vec2)>,
+    last_press: Option<Instant>,
+    drag: Option<Vec2px>,
+    cursor: Option<Vec2px>,
+    hover: Option<(usize, usize)>,
+}
+
+/// Chart of line, scatter and bar series
+///
+/// Dragging pans and scrolling zooms the view. A double click or data with a different range
+/// brings back the range fitting the data.
+#[derive(Clone)]
+pub struct Plot {
+    pub size: WidgetSize,
+    pub series: Vec<PlotSeries>,
+    pub x_range: Option<(f32, f32)>,
+    pub y_range: Option<(f32, f32)>,
+    pub ticks: usize,
+    pub background: Vec4,
+    pub grid_color: Vec4,
+    pub text_color: Vec4,
+    pub font: String,
+    pub font_size: FontSize,
+    pub legend: bool,
+    pub private: PlotPrivate,
+}
+
+impl Default for Plot {
+    fn default() -> Plot {
+        Plot {
+            size: Default::default(),
+            series: vec![],
+            x_range: None,
+            y_range: None,
+            ticks: 5,
+            background: Vec4::grey(0.1),
+            grid_color: Vec4::grey(0.5).with_w(0.3),
+            text_color: Vec4::WHITE,
+            font: "sans-serif".to_owned(),
+            font_size: FontSize::Em(0.6),
+            legend: true,
+            private: Default::default(),
+        }
+    }
+}
+
+const MARGIN: f32 = 8.0;
+const MARGIN_LEFT: f32 = 48.0;
+const MARGIN_BOTTOM: f32 = 22.0;
+const HOVER_DISTANCE: f32 = 12.0;
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+const MAX_GRID_TICKS: i64 = 1000;
+/// Narrowest view zooming reaches, relative to the magnitude of the values in view
+const MIN_RELATIVE_SPAN: f32 = 1e-4;
+
+fn nice_step(span: f32, count: usize) -> f32 {
+    let raw = span / usize::max(count, 1) as f32;
+    let magnitude = f32::powf(10.0, raw.log10().floor());
+    let normalized = raw / magnitude;
+    let nice = if normalized < 1.5 {
+        1.0
+    } else if normalized < 3.0 {
+        2.0
+    } else if normalized < 7.0 {
+        5.0
+    } else {
+        10.0
+    };
+    nice * magnitude
+}
+
+/// Multiples of `step` from `mn` to `mx`, counted in steps since zoomed far in adding a step
+/// to a value may not change it
+fn grid_ticks(mn: f32, mx: f32, step: f32) -> Vec<f32> {
+    if !(mn.is_finite() && mx.is_finite() && step.is_finite()) || step <= 0.0 {
+        return vec![];
+    }
+    let first = (mn / step).ceil() as i64;
+    let last = i64::min(
+        (mx / step).floor() as i64,
+        first.saturating_add(MAX_GRID_TICKS - 1),
+    );
+    (first..=last).map(|k| k as f32 * step).collect()
+}
+
+/// Factor scaling the view span from `mn` to `mx` by `factor`, limited so the span stays
+/// distinguishable from zero
+fn zoom_factor(mn: f32, mx: f32, factor: f32) -> f32 {
+    let span = mx - mn;
+    let min_span = f32::max(mn.abs(), mx.abs()) * MIN_RELATIVE_SPAN;
+    if span > 0.0 {
+        f32::max(factor, f32::min(min_span / span, 1.0))
+    } else {
+        1.0
+    }
+}
+
+fn tick_label(value: f32, step: f32) -> String {
+    let decimals = f32::max(-step.log10().floor(), 0.0) as usize;
+    format!("{:.*}", decimals, value)
+}
+
+/// Liang-Barsky clipping of the segment a-b to the rectangle
+fn clip_segment(a: Vec2, b: Vec2, r: Rect) -> Option<(Vec2, Vec2)> {
+    let d = b - a;
+    let mut t0: f32 = 0.0;
+    let mut t1: f32 = 1.0;
+    for &(p, q) in [
+        (-d.x, a.x - r.left),
+        (d.x, r.right - a.x),
+        (-d.y, a.y - r.top),
+        (d.y, r.bottom - a.y),
+    ]
+    .iter()
+    {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else {
+            let t = q / p;
+            if p < 0.0 {
+                t0 = t0.max(t);
+            } else {
+                t1 = t1.min(t);
+            }
+        }
+    }
+    if t0 > t1 {
+        None
+    } else {
+        Some((a + d * t0, a + d * t1))
+    }
+}
+
+/// Draws `text` into `rct` like a `Text` would
+///
+/// Labels follow the view, which pans and zooms on a redraw without any rebuild, so they can't
+/// be `Text` children created when building.
+fn add_label(
+    builder: &mut DrawBuilder,
+    text: &str,
+    rct: Rect,
+    align: Align,
+    font: &str,
+    clr: Vec4,
+    font_size: f32,
+) {
+    let o = builder.offset;
+    builder.offset = o + Vec3::from_vec2(rct.pos(), 0.0);
+    builder.add_text(
+        text,
+        font,
+        Vec2px::new(rct.width(), rct.height()),
+        clr,
+        align,
+        font_size,
+    );
+    builder.offset = o;
+}
+
+impl Plot {
+    pub fn new(series: Vec<PlotSeries>) -> Plot {
+        Plot {
+            series,
+            ..Default::default()
+        }
+    }
+
+    fn area(&self) -> Rect {
+        let s = self.size();
+        Rect::from_min_max(
+            Vec2::new(MARGIN_LEFT, MARGIN),
+            Vec2::new(
+                f32::max(s.x - MARGIN, MARGIN_LEFT + 1.0),
+                f32::max(s.y - MARGIN_BOTTOM, MARGIN + 1.0),
+            ),
+        )
+    }
+
+    fn data_bounds(&self) -> (Vec2, Vec2) {
+        let mut mn = Vec2::inf();
+        let mut mx = -Vec2::inf();
+        for series in &self.series {
+            let half_bar = if series.kind == PlotKind::Bars {
+                series.bar_width() / 2.0
+            } else {
+                0.0
+            };
+            for p in &series.points {
+                mn.x = mn.x.min(p.x - half_bar);
+                mx.x = mx.x.max(p.x + half_bar);
+                mn.y = mn.y.min(p.y);
+                mx.y = mx.y.max(p.y);
+            }
+            if series.kind == PlotKind::Bars && !series.points.is_empty() {
+                mn.y = mn.y.min(0.0);
+                mx.y = mx.y.max(0.0);
+            }
+        }
+
+        let fit = |lo: f32, hi: f32, fixed: Option<(f32, f32)>| match fixed {
+            Some(range) => range,
+            None if !lo.is_finite() || !hi.is_finite() => (0.0, 1.0),
+            None if hi - lo <= 0.0 => (lo - 0.5, hi + 0.5),
+            None => ((lo - (hi - lo) * 0.05), (hi + (hi - lo) * 0.05)),
+        };
+        let (x0, x1) = fit(mn.x, mx.x, self.x_range);
+        let (y0, y1) = fit(mn.y, mx.y, self.y_range);
+        (Vec2::new(x0, y0), Vec2::new(x1, y1))
+    }
+
+    fn view(&self) -> (Vec2, Vec2) {
+        self.private.view.unwrap_or_else(|| self.data_bounds())
+    }
+    fn set_view(&mut self, view: (Vec2, Vec2)) {
+        if self.private.view.is_none() {
+            self.private.view_base = Some(self.data_bounds());
+        }
+        self.private.view = Some(view);
+    }
+
+    fn to_local(&self, p: Vec2, view: (Vec2, Vec2)) -> Vec2 {
+        let area = self.area();
+        let (mn, mx) = view;
+        Vec2::new(
+            area.left + (p.x - mn.x) / (mx.x - mn.x) * area.width(),
+            area.bottom - (p.y - mn.y) / (mx.y - mn.y) * area.height(),
+        )
+    }
+
+    fn to_data(&self, p: Vec2, view: (Vec2, Vec2)) -> Vec2 {
+        let area = self.area();
+        let (mn, mx) = view;
+        Vec2::new(
+            mn.x + (p.x - area.left) / area.width() * (mx.x - mn.x),
+            mn.y + (area.bottom - p.y) / area.height() * (mx.y - mn.y),
+        )
+    }
+
+    fn nearest_sample(&self, cursor: Vec2) -> Option<(usize, usize)> {
+        if !self.area().contains(cursor) {
+            return None;
+        }
+
+        let view = self.view();
+        let mut best = None;
+        let mut best_dist = HOVER_DISTANCE;
+        for (i, series) in self.series.iter().enumerate() {
+            for (j, p) in series.points.iter().enumerate() {
+                let d = (self.to_local(*p, view) - cursor).length();
+                if d < best_dist {
+                    best_dist = d;
+                    best = Some((i, j));
+                }
+            }
+        }
+        best
+    }
+
+    fn build_grid(&self, builder: &mut DrawBuilder, view: (Vec2, Vec2), font_size: f32) {
+        let area = self.area();
+        let (mn, mx) = view;
+        let mut grid = Path::new();
+
+        let step = nice_step(mx.x - mn.x, self.ticks);
+        for x in grid_ticks(mn.x, mx.x, step) {
+            let lx = self.to_local(Vec2::new(x, mn.y), view).x;
+            grid.move_to(Vec2px::new(lx, area.top))
+                .line_to(Vec2px::new(lx, area.bottom));
+            add_label(
+                builder,
+                &tick_label(x, step),
+                Rect::from_min_max(
+                    Vec2::new(lx - 30.0, area.bottom + 2.0),
+                    Vec2::new(lx + 30.0, area.bottom + MARGIN_BOTTOM),
+                ),
+                Align::top(),
+                &self.font,
+                self.text_color,
+                font_size,
+            );
+        }
+
+        let step = nice_step(mx.y - mn.y, self.ticks);
+        for y in grid_ticks(mn.y, mx.y, step) {
+            let ly = self.to_local(Vec2::new(mn.x, y), view).y;
+            grid.move_to(Vec2px::new(area.left, ly))
+                .line_to(Vec2px::new(area.right, ly));
+            add_label(
+                builder,
+                &tick_label(y, step),
+                Rect::from_min_max(
+                    Vec2::new(0.0, ly - 10.0),
+                    Vec2::new(area.left - 4.0, ly + 10.0),
+                ),
+                Align::right(),
+                &self.font,
+                self.text_color,
+                font_size,
+            );
+        }
+
+        builder.stroke_path(&grid, &StrokeStyle::new(1.0), self.grid_color);
+    }
+
+    fn build_series(&self, builder: &mut DrawBuilder, series: &PlotSeries, view: (Vec2, Vec2)) {
+        let area = self.area();
+        match series.kind {
+            PlotKind::Line | PlotKind::Smooth => {
+                let pts: Vec<Vec2> = series
+                    .curve()
+                    .iter()
+                    .map(|p| self.to_local(*p, view))
+                    .collect();
+                let mut path = Path::new();
+                let mut last = None;
+                for w in pts.windows(2) {
+                    if let Some((a, b)) = clip_segment(w[0], w[1], area) {
+                        if last != Some(a) {
+                            path.move_to(Vec2px::new(a.x, a.y));
+                        }
+                        path.line_to(Vec2px::new(b.x, b.y));
+                        last = Some(b);
+                    }
+                }
+                builder.stroke_path(&path, &StrokeStyle::round(series.width), series.color);
+            }
+            PlotKind::Scatter => {
+                for p in &series.points {
+                    let p = self.to_local(*p, view);
+                    if area.contains(p) {
+                        let r = Vec2::new_xy(series.width);
+                        builder.add_round_rect(
+                            Rect::from_min_max(p - r, p + r),
+                            CornerRadii::all(series.width),
+                            DrawFill::Solid(series.color),
+                        );
+                    }
+                }
+            }
+            PlotKind::Bars => {
+                let half = series.bar_width() / 2.0;
+                for p in &series.points {
+                    let a = self.to_local(Vec2::new(p.x - half, 0.0), view);
+                    let b = self.to_local(Vec2::new(p.x + half, p.y), view);
+                    let bar = Rect {
+                        left: a.x.min(b.x).max(area.left),
+                        right: a.x.max(b.x).min(area.right),
+                        top: a.y.min(b.y).max(area.top),
+                        bottom: a.y.max(b.y).min(area.bottom),
+                    };
+                    if bar.width() > 0.0 && bar.height() > 0.0 {
+                        builder.add_clr_rect(bar, series.color);
+                    }
+                }
+            }
+        }
+    }
+
+    fn build_legend(&self, builder: &mut DrawBuilder, font_size: f32) {
+        let named: Vec<&PlotSeries> = self.series.iter().filter(|s| !s.name.is_empty()).collect();
+        if named.is_empty() {
+            return;
+        }
+
+        let area = self.area();
+        let row = 16.0;
+        let legend = Rect::from_pos_size(
+            Vec2::new(area.right - 110.0, area.top + 4.0),
+            Vec2::new(106.0, row * named.len() as f32 + 4.0),
+        );
+        builder.add_round_rect(
+            legend,
+            CornerRadii::all(4.0),
+            DrawFill::Solid(self.background.with_w(0.8)),
+        );
+        for (i, series) in named.iter().enumerate() {
+            let y = legend.top + 2.0 + row * i as f32;
+            builder.add_clr_rect(
+                Rect::from_pos_size(Vec2::new(legend.left + 6.0, y + 4.0), Vec2::new_xy(8.0)),
+                series.color,
+            );
+            add_label(
+                builder,
+                &series.name,
+                Rect::from_pos_size(Vec2::new(legend.left + 20.0, y), Vec2::new(84.0, row)),
+                Align::left(),
+                &self.font,
+                self.text_color,
+                font_size,
+            );
+        }
+    }
+
+    fn build_hover(&self, builder: &mut DrawBuilder, view: (Vec2, Vec2), font_size: f32) {
+        let (i, j) = match self.private.hover {
+            Some(hover) => hover,
+            None => return,
+        };
+        let series = &self.series[i];
+        let sample = series.points[j];
+        let p = self.to_local(sample, view);
+
+        builder.add_round_rect_border(
+            Rect::from_min_max(p - Vec2::new_xy(5.0), p + Vec2::new_xy(5.0)),
+            CornerRadii::all(5.0),
+            1.5,
+            DrawFill::Solid(self.text_color),
+        );
+
+        let text = if series.name.is_empty() {
+            format!("{:.3}, {:.3}", sample.x, sample.y)
+        } else {
+            format!("{}: {:.3}, {:.3}", series.name, sample.x, sample.y)
+        };
+        let size = Vec2::new(font_size * 0.6 * text.len() as f32 + 12.0, font_size + 8.0);
+        let area = self.area();
+        let mut pos = p + Vec2::new(8.0, -size.y - 8.0);
+        if pos.x + size.x > area.right {
+            pos.x = p.x - size.x - 8.0;
+        }
+        if pos.y < area.top {
+            pos.y = p.y + 8.0;
+        }
+
+        let rct = Rect::from_pos_size(pos, size);
+        builder.add_round_rect(
+            rct,
+            CornerRadii::all(4.0),
+            DrawFill::Solid(self.background.with_w(0.9)),
+        );
+        add_label(
+            builder,
+            &text,
+            rct,
+            Default::default(),
+            &self.font,
+            self.text_color,
+            font_size,
+        );
+    }
+}
+
+impl_widget_building_for!(Plot);
+impl Widget for Plot {
+    fn constraint(&mut self, self_constraint: WidgetConstraints) {
+        self.private.real_size = self.size.resolve(&self_constraint);
+    }
+    fn adopt_state(&mut self, previous: &dyn Widget) {
+        if let Some(previous) = previous.downcast_ref::<Plot>() {
+            if previous.private.view_base == Some(self.data_bounds()) {
+                self.private.view = previous.private.view;
+                self.private.view_base = previous.private.view_base;
+            }
+            self.private.last_press = previous.private.last_press;
+            self.private.drag = previous.private.drag;
+            self.private.cursor = previous.private.cursor;
+        }
+    }
+    fn on_press(
+        &mut self,
+        local_cursor_pos: Vec2px,
+        _executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        let now = Instant::now();
+        let double_click = match self.private.last_press {
+            Some(last) => now.duration_since(last) < DOUBLE_CLICK,
+            None => false,
+        };
+        if double_click {
+            self.private.view = None;
+            self.private.last_press = None;
+            return EventResponse::HandledRedraw;
+        }
+
+        self.private.last_press = Some(now);
+        self.private.drag = Some(local_cursor_pos);
+        EventResponse::Handled
+    }
+    fn on_release(&mut self, _executor: &mut CallbackExecutor) -> EventResponse {
+        self.private.drag = None;
+        EventResponse::Handled
+    }
+    fn on_pointer_lost(&mut self, _executor: &mut CallbackExecutor) -> EventResponse {
+        self.private.drag = None;
+        EventResponse::Handled
+    }
+    fn on_cursor_leave(&mut self, _executor: &mut CallbackExecutor) -> EventResponse {
+        self.private.cursor = None;
+        self.private.hover = None;
+        EventResponse::HandledRedraw
+    }
+    fn on_cursor_move(
+        &mut self,
+        local_cursor_pos: Vec2px,
+        _executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        self.private.cursor = Some(local_cursor_pos);
+
+        if let Some(last) = self.private.drag {
+            let (mn, mx) = self.view();
+            let area = self.area();
+            let d = local_cursor_pos - last;
+            let shift = Vec2::new(
+                -d.x / area.width() * (mx.x - mn.x),
+                d.y / area.height() * (mx.y - mn.y),
+            );
+            self.set_view((mn + shift, mx + shift));
+            self.private.drag = Some(local_cursor_pos);
+            return EventResponse::HandledRedraw;
+        }
+
+        let hover = self.nearest_sample(local_cursor_pos.as_vec2());
+        if hover != self.private.hover {
+            self.private.hover = hover;
+            EventResponse::HandledRedraw
+        } else {
+            EventResponse::Handled
+        }
+    }
+    fn on_scroll(
+        &mut self,
+        local_cursor_pos: Vec2px,
+        delta: Vec2,
+        _executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        let view = self.view();
+        let center = self.to_data(local_cursor_pos.as_vec2(), view);
+        let factor = f32::powf(0.85, delta.y);
+        let factor = Vec2::new(
+            zoom_factor(view.0.x, view.1.x, factor),
+            zoom_factor(view.0.y, view.1.y, factor),
+        );
+        self.set_view((
+            center + (view.0 - center) * factor,
+            center + (view.1 - center) * factor,
+        ));
+        EventResponse::HandledRedraw
+    }
+    fn on_draw_build(&self, builder: &mut DrawBuilder) {
+        let view = self.view();
+        let font_size = self.font_size.to_pixels(self.size().minxy(), 1.0);
+
+        builder.add_clr_rect(
+            Rect::from_min_max(Vec2::origin(), self.size().as_vec2()),
+            self.background,
+        );
+        self.build_grid(builder, view, font_size);
+        for series in &self.series {
+            self.build_series(builder, series, view);
+        }
+        if self.legend {
+            self.build_legend(builder, font_size);
+        }
+        self.build_hover(builder, view, font_size);
+    }
+    fn size(&self) -> Vec2px {
+        self.private.real_size
+    }
+}
@@ -0,0 +1,49 @@
+use std::ops::{Neg, Shl};
+
+use gui::widget::*;
+use gui::*;
+use tools::*;
+
+#[derive(Default, Clone)]
+pub struct ShortcutAreaPrivate {
+    size: Vec2px,
+    stacking_depth: f32,
+}
+
+/// Invisible container activating the `ShortcutScope::Focus` of its name while a widget inside
+/// it has the focus
+#[derive(Default, Clone)]
+pub struct ShortcutArea {
+    pub scope: String,
+    pub private: ShortcutAreaPrivate,
+}
+
+impl ShortcutArea {
+    pub fn new(scope: &str) -> ShortcutArea {
+        ShortcutArea {
+            scope: scope.to_owned(),
+            ..Default::default()
+        }
+    }
+}
+
+impl_widget_building_for!(ShortcutArea);
+impl Widget for ShortcutArea {
+    fn constraint(&mut self, self_constraint: WidgetConstraints) {
+        self.private.size = self_constraint.max_size;
+    }
+    fn place_child(&mut self, _child_size: Vec2px, child_descent: f32) -> WidgetPosition {
+        let sd = self.private.stacking_depth;
+        self.private.stacking_depth += child_descent + 0.01;
+        WidgetPosition::new(Vec2px::zero(), sd)
+    }
+    fn child_constraint(&self) -> Option<WidgetConstraints> {
+        Some(WidgetConstraints {
+            max_size: self.private.size,
+            ..Default::default()
+        })
+    }
+    fn size(&self) -> Vec2px {
+        self.private.size
+    }
+}
@@ -0,0 +1,289 @@
+use std::ops::{Neg, Shl};
+use std::time::{Duration, Instant};
+
+use gui::widget::*;
+use gui::*;
+use tools::*;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SplitDirection {
+    /// Children side by side with a vertical divider
+    Horizontal,
+    /// Children stacked with a horizontal divider
+    Vertical,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SplitSide {
+    First,
+    Second,
+}
+
+/// Ratio and collapsed side of a split panel
+type SplitState = (f32, Option<SplitSide>);
+
+#[derive(Default, Clone)]
+pub struct SplitPanelPrivate {
+    real_size: Vec2px,
+    child_id: usize,
+    base: Option<SplitState>,
+    drag: Option<(f32, f32)>,
+    hover: bool,
+    last_press: Option<Instant>,
+}
+
+/// Two children separated by a divider the user can drag, double-click collapses a side
+#[derive(Clone)]
+pub struct SplitPanel {
+    pub size: WidgetSize,
+    pub direction: SplitDirection,
+    /// Share of the space beside the divider given to the first child
+    pub ratio: f32,
+    /// Minimum and maximum extent of the first child in units
+    pub first_limits: (f32, f32),
+    pub second_limits: (f32, f32),
+    pub collapsed: Option<SplitSide>,
+    /// Side a double-click on the divider collapses, None disables collapsing
+    pub collapsible: Option<SplitSide>,
+    pub divider_width: f32,
+    pub divider_color: Vec4,
+    pub hover_color: Vec4,
+    pub callback: GuiCallback<SplitPanel>,
+    pub private: SplitPanelPrivate,
+}
+
+impl Default for SplitPanel {
+    fn default() -> SplitPanel {
+        SplitPanel {
+            size: Default::default(),
+            direction: SplitDirection::Horizontal,
+            ratio: 0.5,
+            first_limits: (0.0, f32::INFINITY),
+            second_limits: (0.0, f32::INFINITY),
+            collapsed: None,
+            collapsible: Some(SplitSide::First),
+            divider_width: 6.0,
+            divider_color: Vec4::grey(0.2),
+            hover_color: Vec4::grey(0.35),
+            callback: Default::default(),
+            private: Default::default(),
+        }
+    }
+}
+
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+fn px(v: Vec2) -> Vec2px {
+    Vec2px::new(v.x, v.y)
+}
+
+impl SplitPanel {
+    pub fn new(direction: SplitDirection, ratio: f32) -> SplitPanel {
+        SplitPanel {
+            direction,
+            ratio,
+            ..Default::default()
+        }
+    }
+
+    fn along(&self, v: Vec2) -> f32 {
+        match self.direction {
+            SplitDirection::Horizontal => v.x,
+            SplitDirection::Vertical => v.y,
+        }
+    }
+    fn across(&self, v: Vec2) -> f32 {
+        match self.direction {
+            SplitDirection::Horizontal => v.y,
+            SplitDirection::Vertical => v.x,
+        }
+    }
+    /// Vector from extents along and across the split direction
+    fn oriented(&self, along: f32, across: f32) -> Vec2 {
+        match self.direction {
+            SplitDirection::Horizontal => Vec2::new(along, across),
+            SplitDirection::Vertical => Vec2::new(across, along),
+        }
+    }
+
+    fn available(&self) -> f32 {
+        f32::max(self.along(self.size().as_vec2()) - self.divider_width, 0.0)
+    }
+    fn clamp_first(&self, first: f32) -> f32 {
+        let available = self.available();
+        let low = f32::max(self.first_limits.0, available - self.second_limits.1);
+        let high = f32::min(self.first_limits.1, available - self.second_limits.0);
+        first.min(high).max(low).clamp(0.0, available)
+    }
+    /// Extent of the first child after limits and collapsing
+    pub fn first_size(&self) -> f32 {
+        match self.collapsed {
+            Some(SplitSide::First) => 0.0,
+            Some(SplitSide::Second) => self.available(),
+            None => self.clamp_first(self.ratio * self.available()),
+        }
+    }
+
+    fn divider_rect(&self) -> Rect {
+        let across = self.across(self.size().as_vec2());
+        Rect::from_pos_size(
+            self.oriented(self.first_size(), 0.0),
+            self.oriented(self.divider_width, across),
+        )
+    }
+    fn child_rect(&self, index: usize) -> Rect {
+        let across = self.across(self.size().as_vec2());
+        let first = self.first_size();
+        match index {
+            0 => Rect::from_pos_size(Vec2::origin(), self.oriented(first, across)),
+            1 => Rect::from_pos_size(
+                self.oriented(first + self.divider_width, 0.0),
+                self.oriented(self.available() - first, across),
+            ),
+            _ => Rect::new(),
+        }
+    }
+    /// Index of the child being laid out, collapsed children are skipped by the layout
+    fn current_child(&self) -> usize {
+        let mut index = self.private.child_id;
+        while index < 2 && !self.is_child_shown(index) {
+            index += 1;
+        }
+        index
+    }
+}
+
+impl_widget_building_for!(SplitPanel);
+impl Widget for SplitPanel {
+    fn constraint(&mut self, self_constraint: WidgetConstraints) {
+        self.private.real_size = self.size.resolve(&self_constraint);
+        if self.private.base.is_none() {
+            self.private.base = Some((self.ratio, self.collapsed));
+        }
+    }
+    fn place_child(&mut self, _child_size: Vec2px, _child_descent: f32) -> WidgetPosition {
+        let index = self.current_child();
+        self.private.child_id = index + 1;
+        px(self.child_rect(index).pos()).into()
+    }
+    fn child_constraint(&self) -> Option<WidgetConstraints> {
+        Some(WidgetConstraints {
+            max_size: px(self.child_rect(self.current_child()).size()),
+            ..Default::default()
+        })
+    }
+    fn is_child_shown(&self, child_index: usize) -> bool {
+        match (child_index, self.collapsed) {
+            (0, Some(SplitSide::First)) | (1, Some(SplitSide::Second)) => false,
+            (index, _) => index < 2,
+        }
+    }
+    fn adopt_state(&mut self, previous: &dyn Widget) {
+        if let Some(previous) = previous.downcast_ref::<SplitPanel>() {
+            // a divider moved by the user stays until the data sets a new ratio
+            if previous.private.base == Some((self.ratio, self.collapsed)) {
+                self.ratio = previous.ratio;
+                self.collapsed = previous.collapsed;
+                self.private.base = previous.private.base;
+            }
+            self.private.drag = previous.private.drag;
+            self.private.hover = previous.private.hover;
+            self.private.last_press = previous.private.last_press;
+        }
+    }
+    fn on_press(
+        &mut self,
+        local_cursor_pos: Vec2px,
+        executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        let p = local_cursor_pos.as_vec2();
+        if !self.divider_rect().contains(p) {
+            return EventResponse::Pass;
+        }
+
+        let now = Instant::now();
+        let double_click = match self.private.last_press {
+            Some(last) => now.duration_since(last) < DOUBLE_CLICK,
+            None => false,
+        };
+        if let (true, Some(side)) = (double_click, self.collapsible) {
+            self.collapsed = match self.collapsed {
+                Some(_) => None,
+                None => Some(side),
+            };
+            self.private.last_press = None;
+            executor.execute(&self.callback, self);
+            return EventResponse::HandledRebuild;
+        }
+
+        self.private.last_press = Some(now);
+        self.private.drag = Some((self.along(p), self.first_size()));
+        EventResponse::HandledRedraw
+    }
+    fn on_release(&mut self, executor: &mut CallbackExecutor) -> EventResponse {
+        match self.private.drag.take() {
+            Some((_, start_first)) => {
+                if start_first != self.first_size() {
+                    executor.execute(&self.callback, self);
+                }
+                EventResponse::HandledRedraw
+            }
+            None => EventResponse::Pass,
+        }
+    }
+    fn on_pointer_lost(&mut self, _executor: &mut CallbackExecutor) -> EventResponse {
+        self.private.drag = None;
+        EventResponse::HandledRedraw
+    }
+    fn on_cursor_move(
+        &mut self,
+        local_cursor_pos: Vec2px,
+        _executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        let p = local_cursor_pos.as_vec2();
+        if let Some((start, start_first)) = self.private.drag {
+            let available = self.available();
+            let first = self.clamp_first(start_first + self.along(p) - start);
+            if available <= 0.0 || (first == self.first_size() && self.collapsed.is_none()) {
+                return EventResponse::Handled;
+            }
+            self.ratio = first / available;
+            self.collapsed = None;
+            return EventResponse::HandledRebuild;
+        }
+
+        let hover = self.divider_rect().contains(p);
+        if hover != self.private.hover {
+            self.private.hover = hover;
+            EventResponse::HandledRedraw
+        } else {
+            EventResponse::Pass
+        }
+    }
+    fn on_cursor_leave(&mut self, _executor: &mut CallbackExecutor) -> EventResponse {
+        self.private.hover = false;
+        EventResponse::HandledRedraw
+    }
+    fn on_draw_build(&self, builder: &mut DrawBuilder) {
+        let rct = self.divider_rect();
+        let clr = if self.private.hover || self.private.drag.is_some() {
+            self.hover_color
+        } else {
+            self.divider_color
+        };
+        builder.add_clr_rect(rct, clr);
+
+        // grip in the middle of the divider
+        let c = rct.mid();
+        let mut grip = Path::new();
+        for i in -1..2 {
+            let d = self.oriented(0.0, i as f32 * 4.0);
+            let half = self.oriented(0.0, 1.0);
+            grip.move_to(px(c + d - half)).line_to(px(c + d + half));
+        }
+        builder.stroke_path(&grip, &StrokeStyle::new(2.0), self.divider_color * 2.0);
+    }
+    fn size(&self) -> Vec2px {
+        self.private.real_size
+    }
+}
@@ -0,0 +1,235 @@
+use std::ops::{Neg, Shl};
+
+use gui::widget::*;
+use gui::*;
+use tools::*;
+
+/// Point of a `Stack` relative to its size, `Custom` takes (0, 0) as the top left and (1, 1)
+/// as the bottom right
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+    Custom(Vec2),
+}
+
+impl Anchor {
+    pub fn point(self) -> Vec2 {
+        match self {
+            Anchor::TopLeft => Vec2::new(0.0, 0.0),
+            Anchor::Top => Vec2::new(0.5, 0.0),
+            Anchor::TopRight => Vec2::new(1.0, 0.0),
+            Anchor::Left => Vec2::new(0.0, 0.5),
+            Anchor::Center => Vec2::new(0.5, 0.5),
+            Anchor::Right => Vec2::new(1.0, 0.5),
+            Anchor::BottomLeft => Vec2::new(0.0, 1.0),
+            Anchor::Bottom => Vec2::new(0.5, 1.0),
+            Anchor::BottomRight => Vec2::new(1.0, 1.0),
+            Anchor::Custom(p) => p,
+        }
+    }
+}
+
+/// Placement of a child of a `Stack`
+///
+/// Along an axis where both anchors coincide the child keeps its own size and its `pivot` is
+/// put onto the anchor, moved by `offset_min`. Where they differ the child is stretched from
+/// `anchor_min + offset_min` to `anchor_max + offset_max`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct StackItem {
+    pub anchor_min: Vec2,
+    pub anchor_max: Vec2,
+    pub offset_min: Vec2px,
+    pub offset_max: Vec2px,
+    /// Point of the child relative to its size, (0.5, 0.5) is its center
+    pub pivot: Vec2,
+    /// Children of higher z are drawn above and get the cursor first, equal ones stack in order
+    pub z: i32,
+}
+
+impl Default for StackItem {
+    fn default() -> Self {
+        StackItem::at(Anchor::TopLeft)
+    }
+}
+
+impl StackItem {
+    /// Child of its own size inside the stack at `anchor`, e.g. in the corner for `TopRight`
+    pub fn at(anchor: Anchor) -> StackItem {
+        let p = anchor.point();
+        StackItem {
+            anchor_min: p,
+            anchor_max: p,
+            offset_min: Vec2px::zero(),
+            offset_max: Vec2px::zero(),
+            pivot: p,
+            z: 0,
+        }
+    }
+    /// Child spanning from `min` to `max`, like `Left` to `Right` for a bar across the middle
+    pub fn stretch(min: Anchor, max: Anchor) -> StackItem {
+        StackItem {
+            anchor_max: max.point(),
+            ..StackItem::at(min)
+        }
+    }
+    pub fn fill() -> StackItem {
+        StackItem::stretch(Anchor::TopLeft, Anchor::BottomRight)
+    }
+
+    /// Moves the child by `offset`
+    pub fn offset(mut self, offset: Vec2px) -> StackItem {
+        self.offset_min += offset;
+        self.offset_max += offset;
+        self
+    }
+    /// Shrinks a stretched child by `amount` on every side
+    pub fn inset(mut self, amount: f32) -> StackItem {
+        self.offset_min += Vec2px::new_xy(amount);
+        self.offset_max -= Vec2px::new_xy(amount);
+        self
+    }
+    pub fn pivot(mut self, pivot: Vec2) -> StackItem {
+        self.pivot = pivot;
+        self
+    }
+    pub fn z(mut self, z: i32) -> StackItem {
+        self.z = z;
+        self
+    }
+
+    /// Area given to the child inside a stack of `size`
+    fn area(&self, size: Vec2px) -> Vec2px {
+        let axis = |min: f32, max: f32, off_min: f32, off_max: f32, s: f32| {
+            if min == max {
+                s
+            } else {
+                f32::max((max - min) * s + off_max - off_min, 0.0)
+            }
+        };
+        Vec2px::new(
+            axis(
+                self.anchor_min.x,
+                self.anchor_max.x,
+                self.offset_min.x,
+                self.offset_max.x,
+                size.x,
+            ),
+            axis(
+                self.anchor_min.y,
+                self.anchor_max.y,
+                self.offset_min.y,
+                self.offset_max.y,
+                size.y,
+            ),
+        )
+    }
+    /// Top left corner of a child of `child_size`
+    fn place(&self, size: Vec2px, child_size: Vec2px) -> Vec2px {
+        let axis = |min: f32, max: f32, off: f32, pivot: f32, s: f32, c: f32| {
+            if min == max {
+                min * s + off - pivot * c
+            } else {
+                min * s + off
+            }
+        };
+        Vec2px::new(
+            axis(
+                self.anchor_min.x,
+                self.anchor_max.x,
+                self.offset_min.x,
+                self.pivot.x,
+                size.x,
+                child_size.x,
+            ),
+            axis(
+                self.anchor_min.y,
+                self.anchor_max.y,
+                self.offset_min.y,
+                self.pivot.y,
+                size.y,
+                child_size.y,
+            ),
+        )
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct StackPrivate {
+    real_size: Vec2px,
+    child_id: usize,
+}
+
+/// Container placing each child by anchors into its area, see `StackItem`
+///
+/// `items[i]` belongs to the i-th child, children without an item sit in the top left corner.
+#[derive(Clone)]
+pub struct Stack {
+    pub items: Vec<StackItem>,
+    pub size: WidgetSize,
+    pub private: StackPrivate,
+}
+
+impl Default for Stack {
+    fn default() -> Self {
+        Stack {
+            items: Vec::new(),
+            size: WidgetSize::fill(),
+            private: Default::default(),
+        }
+    }
+}
+
+impl Stack {
+    pub fn new(items: Vec<StackItem>) -> Stack {
+        Stack {
+            items,
+            ..Default::default()
+        }
+    }
+    fn item(&self, child_id: usize) -> StackItem {
+        self.items.get(child_id).copied().unwrap_or_default()
+    }
+}
+
+impl_widget_building_for!(Stack);
+impl Widget for Stack {
+    fn constraint(&mut self, self_constraint: WidgetConstraints) {
+        self.private.real_size = self.size.resolve(&self_constraint);
+    }
+    fn place_child(&mut self, child_size: Vec2px, _child_descent: f32) -> WidgetPosition {
+        let item = self.item(self.private.child_id);
+        self.private.child_id += 1;
+        // the depth follows in `child_depths`
+        item.place(self.size(), child_size).into()
+    }
+    fn child_depths(&self, descents: &[f32]) -> Option<Vec<f32>> {
+        // whole subtrees stack from the lowest z up, so none reaches into a higher level
+        let mut order: Vec<usize> = (0..descents.len()).collect();
+        order.sort_by_key(|&i| self.item(i).z);
+        let mut depths = vec![0.0; descents.len()];
+        let mut stacking_depth = 0.0;
+        for i in order {
+            stacking_depth += 0.01;
+            depths[i] = stacking_depth;
+            stacking_depth += descents[i];
+        }
+        Some(depths)
+    }
+    fn child_constraint(&self) -> Option<WidgetConstraints> {
+        Some(WidgetConstraints {
+            max_size: self.item(self.private.child_id).area(self.size()),
+            ..Default::default()
+        })
+    }
+    fn size(&self) -> Vec2px {
+        self.private.real_size
+    }
+}
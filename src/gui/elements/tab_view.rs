@@ -0,0 +1,523 @@
+use std::ops::{Neg, Shl};
+
+use gui::widget::*;
+use gui::*;
+use mecs::GlutinKey;
+use tools::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tab {
+    pub title: String,
+    pub closable: bool,
+}
+
+impl Tab {
+    pub fn new(title: &str) -> Tab {
+        Tab {
+            title: title.to_owned(),
+            closable: false,
+        }
+    }
+    pub fn closable(mut self) -> Tab {
+        self.closable = true;
+        self
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum TabPart {
+    Tab(usize),
+    Close(usize),
+    Menu,
+    MenuItem(usize),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct TabDrag {
+    tab: usize,
+    moved: bool,
+}
+
+/// Active tab and display order of a tab view
+type TabLayout = (usize, Vec<usize>);
+
+#[derive(Default, Clone)]
+pub struct TabViewPrivate {
+    real_size: Vec2px,
+    base: Option<TabLayout>,
+    closing: Option<usize>,
+    drag: Option<TabDrag>,
+    hover: Option<TabPart>,
+    menu_open: bool,
+    first: usize,
+}
+
+/// Container showing a tab strip, only the child of the active tab is laid out and drawn
+#[derive(Clone)]
+pub struct TabView {
+    pub size: WidgetSize,
+    pub tabs: Vec<Tab>,
+    pub active: usize,
+    /// Display order of the tabs, missing tabs are appended in data order
+    pub order: Vec<usize>,
+    pub tab_width: f32,
+    pub strip_height: f32,
+    pub font: String,
+    pub font_size: FontSize,
+    pub text_color: Vec4,
+    pub background: Vec4,
+    pub strip_background: Vec4,
+    pub tab_color: Vec4,
+    pub active_tab_color: Vec4,
+    pub hover_color: Vec4,
+    pub callback: GuiCallback<TabView>,
+    pub on_close: GuiCallback<TabView>,
+    pub private: TabViewPrivate,
+}
+
+impl Default for TabView {
+    fn default() -> TabView {
+        TabView {
+            size: Default::default(),
+            tabs: vec![],
+            active: 0,
+            order: vec![],
+            tab_width: 110.0,
+            strip_height: 24.0,
+            font: "sans-serif".to_owned(),
+            font_size: FontSize::Em(0.65),
+            text_color: Vec4::WHITE,
+            background: Vec4::grey(0.1),
+            strip_background: Vec4::grey(0.05),
+            tab_color: Vec4::grey(0.15),
+            active_tab_color: Vec4::grey(0.25),
+            hover_color: Vec4::grey(1.0).with_w(0.08),
+            callback: Default::default(),
+            on_close: Default::default(),
+            private: Default::default(),
+        }
+    }
+}
+
+const TAB_PAD: f32 = 8.0;
+const CLOSE_SIZE: f32 = 16.0;
+const MENU_WIDTH: f32 = 22.0;
+const MENU_DEPTH: f32 = 0.4;
+
+impl TabView {
+    pub fn new(tabs: Vec<Tab>) -> TabView {
+        TabView {
+            tabs,
+            ..Default::default()
+        }
+    }
+
+    /// Index of the tab whose close button was pressed, while `on_close` runs
+    pub fn closing(&self) -> Option<usize> {
+        self.private.closing
+    }
+
+    /// Tab indices in the order they are displayed
+    pub fn display_order(&self) -> Vec<usize> {
+        let n = self.tabs.len();
+        let mut order: Vec<usize> = vec![];
+        for &t in &self.order {
+            if t < n && !order.contains(&t) {
+                order.push(t);
+            }
+        }
+        for t in 0..n {
+            if !order.contains(&t) {
+                order.push(t);
+            }
+        }
+        order
+    }
+
+    fn overflows(&self) -> bool {
+        self.tabs.len() as f32 * self.tab_width > self.size().x
+    }
+    fn visible_count(&self) -> usize {
+        if self.overflows() {
+            usize::max(
+                ((self.size().x - MENU_WIDTH) / self.tab_width).floor() as usize,
+                1,
+            )
+        } else {
+            self.tabs.len()
+        }
+    }
+    fn scroll_to_active(&mut self) {
+        let visible = self.visible_count();
+        let position = self
+            .display_order()
+            .iter()
+            .position(|&t| t == self.active)
+            .unwrap_or(0);
+        let first = usize::min(self.private.first, self.tabs.len().saturating_sub(visible));
+        self.private.first = if position < first {
+            position
+        } else if position >= first + visible {
+            position + 1 - visible
+        } else {
+            first
+        };
+    }
+
+    fn tab_rect(&self, slot: usize) -> Rect {
+        Rect::from_pos_size(
+            Vec2::new(slot as f32 * self.tab_width, 0.0),
+            Vec2::new(self.tab_width, self.strip_height),
+        )
+    }
+    fn close_rect(&self, slot: usize) -> Rect {
+        let rct = self.tab_rect(slot);
+        Rect::from_pos_size(
+            Vec2::new(
+                rct.right - CLOSE_SIZE - 4.0,
+                (self.strip_height - CLOSE_SIZE) / 2.0,
+            ),
+            Vec2::new(CLOSE_SIZE, CLOSE_SIZE),
+        )
+    }
+    fn menu_button_rect(&self) -> Rect {
+        Rect::from_pos_size(
+            Vec2::new(self.size().x - MENU_WIDTH, 0.0),
+            Vec2::new(MENU_WIDTH, self.strip_height),
+        )
+    }
+    fn menu_rect(&self) -> Rect {
+        let width = f32::min(self.tab_width * 1.5, self.size().x);
+        Rect::from_pos_size(
+            Vec2::new(self.size().x - width, self.strip_height),
+            Vec2::new(width, self.tabs.len() as f32 * self.strip_height),
+        )
+    }
+
+    fn slot_at(&self, x: f32) -> Option<usize> {
+        let slot = (x / self.tab_width).floor();
+        if slot < 0.0 || slot as usize >= self.visible_count() {
+            return None;
+        }
+        Some(slot as usize).filter(|s| self.private.first + s < self.tabs.len())
+    }
+    fn part_at(&self, p: Vec2) -> Option<TabPart> {
+        if self.private.menu_open && self.menu_rect().contains(p) {
+            let row = ((p.y - self.strip_height) / self.strip_height) as usize;
+            return self.display_order().get(row).map(|&t| TabPart::MenuItem(t));
+        }
+        if p.y < 0.0 || p.y >= self.strip_height {
+            return None;
+        }
+        if self.overflows() && self.menu_button_rect().contains(p) {
+            return Some(TabPart::Menu);
+        }
+        let slot = self.slot_at(p.x)?;
+        let tab = self.display_order()[self.private.first + slot];
+        if self.tabs[tab].closable && self.close_rect(slot).contains(p) {
+            Some(TabPart::Close(tab))
+        } else {
+            Some(TabPart::Tab(tab))
+        }
+    }
+
+    fn activate(&mut self, tab: usize, executor: &mut CallbackExecutor) -> EventResponse {
+        if tab == self.active {
+            return EventResponse::HandledRedraw;
+        }
+        self.active = tab;
+        self.scroll_to_active();
+        executor.execute(&self.callback, self);
+        EventResponse::HandledRebuild
+    }
+
+    fn add_label(&self, builder: &mut DrawBuilder, text: &str, rct: Rect, font_size: f32) {
+        let o = builder.offset;
+        builder.offset = o + Vec3::from_vec2(rct.pos() + Vec2::new(TAB_PAD, 0.0), 0.0);
+        builder.add_text(
+            text,
+            &self.font,
+            Vec2px::new(f32::max(rct.width() - TAB_PAD * 2.0, 0.0), rct.height()),
+            self.text_color,
+            Align::left(),
+            font_size,
+        );
+        builder.offset = o;
+    }
+
+    fn build_strip(&self, builder: &mut DrawBuilder, font_size: f32) {
+        builder.add_clr_rect(
+            Rect::from_pos_size(Vec2::origin(), Vec2::new(self.size().x, self.strip_height)),
+            self.strip_background,
+        );
+
+        let order = self.display_order();
+        for slot in 0..self.visible_count() {
+            let tab = match order.get(self.private.first + slot) {
+                Some(&tab) => tab,
+                None => break,
+            };
+            let rct = self.tab_rect(slot);
+            let inner = Rect::from_min_max(
+                rct.pos() + Vec2::new(1.0, 2.0),
+                Vec2::new(rct.right - 1.0, rct.bottom),
+            );
+            let clr = if tab == self.active {
+                self.active_tab_color
+            } else {
+                self.tab_color
+            };
+            builder.add_round_rect(inner, CornerRadii::top(4.0), DrawFill::Solid(clr));
+            if self.private.hover == Some(TabPart::Tab(tab)) && tab != self.active {
+                builder.add_round_rect(
+                    inner,
+                    CornerRadii::top(4.0),
+                    DrawFill::Solid(self.hover_color),
+                );
+            }
+
+            let label_rct = if self.tabs[tab].closable {
+                Rect::from_min_max(rct.pos(), Vec2::new(rct.right - CLOSE_SIZE, rct.bottom))
+            } else {
+                rct
+            };
+            self.add_label(builder, &self.tabs[tab].title, label_rct, font_size);
+
+            if self.tabs[tab].closable {
+                let close = self.close_rect(slot);
+                if self.private.hover == Some(TabPart::Close(tab)) {
+                    builder.add_round_rect(
+                        close,
+                        CornerRadii::all(3.0),
+                        DrawFill::Solid(self.hover_color),
+                    );
+                }
+                let c = close.mid();
+                let mut cross = Path::new();
+                cross
+                    .move_to(Vec2px::new(c.x - 3.5, c.y - 3.5))
+                    .line_to(Vec2px::new(c.x + 3.5, c.y + 3.5))
+                    .move_to(Vec2px::new(c.x + 3.5, c.y - 3.5))
+                    .line_to(Vec2px::new(c.x - 3.5, c.y + 3.5));
+                builder.stroke_path(&cross, &StrokeStyle::new(1.5), self.text_color);
+            }
+        }
+
+        if self.overflows() {
+            let rct = self.menu_button_rect();
+            if self.private.menu_open || self.private.hover == Some(TabPart::Menu) {
+                builder.add_clr_rect(rct, self.hover_color);
+            }
+            let c = rct.mid();
+            let mut arrow = Path::new();
+            arrow
+                .move_to(Vec2px::new(c.x - 4.0, c.y - 2.0))
+                .line_to(Vec2px::new(c.x + 4.0, c.y - 2.0))
+                .line_to(Vec2px::new(c.x, c.y + 3.0))
+                .close();
+            builder.fill_path(&arrow, FillRule::NonZero, DrawFill::Solid(self.text_color));
+        }
+    }
+
+    fn build_menu(&self, builder: &mut DrawBuilder, font_size: f32) {
+        let o = builder.offset;
+        builder.offset = o + Vec3::new(0.0, 0.0, MENU_DEPTH);
+
+        let menu = self.menu_rect();
+        builder.add_round_rect(
+            menu,
+            CornerRadii::bottom(4.0),
+            DrawFill::Solid(self.strip_background),
+        );
+        for (row, &tab) in self.display_order().iter().enumerate() {
+            let rct = Rect::from_pos_size(
+                Vec2::new(menu.left, menu.top + row as f32 * self.strip_height),
+                Vec2::new(menu.width(), self.strip_height),
+            );
+            if tab == self.active {
+                builder.add_clr_rect(rct, self.active_tab_color);
+            } else if self.private.hover == Some(TabPart::MenuItem(tab)) {
+                builder.add_clr_rect(rct, self.hover_color);
+            }
+            self.add_label(builder, &self.tabs[tab].title, rct, font_size);
+        }
+
+        builder.offset = o;
+    }
+}
+
+impl_widget_building_for!(TabView);
+impl Widget for TabView {
+    fn constraint(&mut self, self_constraint: WidgetConstraints) {
+        self.private.real_size = self.size.resolve(&self_constraint);
+        if self.private.base.is_none() {
+            self.private.base = Some((self.active, self.order.clone()));
+        }
+        self.active = usize::min(self.active, self.tabs.len().saturating_sub(1));
+        self.private.menu_open = self.private.menu_open && self.overflows();
+        self.scroll_to_active();
+    }
+    fn place_child(&mut self, _child_size: Vec2px, _child_descent: f32) -> WidgetPosition {
+        WidgetPosition::new(Vec2px::new(0.0, self.strip_height), 0.01)
+    }
+    fn child_constraint(&self) -> Option<WidgetConstraints> {
+        Some(WidgetConstraints {
+            max_size: Vec2px::new(
+                self.size().x,
+                f32::max(self.size().y - self.strip_height, 0.0),
+            ),
+            ..Default::default()
+        })
+    }
+    fn is_child_shown(&self, child_index: usize) -> bool {
+        child_index == self.active
+    }
+    fn popup_area(&self) -> Option<Rect> {
+        if self.private.menu_open {
+            Some(self.menu_rect())
+        } else {
+            None
+        }
+    }
+    fn adopt_state(&mut self, previous: &dyn Widget) {
+        if let Some(previous) = previous.downcast_ref::<TabView>() {
+            // tabs picked or dragged by the user win until the data sets new ones
+            let base = (self.active, self.order.clone());
+            if previous.private.base.as_ref() == Some(&base) && previous.tabs == self.tabs {
+                self.active = previous.active;
+                self.order = previous.order.clone();
+                self.private.base = previous.private.base.clone();
+            }
+            self.private.menu_open = previous.private.menu_open;
+            self.private.first = previous.private.first;
+            self.private.drag = previous.private.drag;
+            self.private.hover = previous.private.hover;
+        }
+    }
+    fn on_press(
+        &mut self,
+        local_cursor_pos: Vec2px,
+        executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        match self.part_at(local_cursor_pos.as_vec2()) {
+            Some(TabPart::Close(tab)) => {
+                self.private.menu_open = false;
+                self.private.closing = Some(tab);
+                executor.execute(&self.on_close, self);
+                self.private.closing = None;
+                EventResponse::HandledRedraw
+            }
+            Some(TabPart::Tab(tab)) => {
+                self.private.menu_open = false;
+                self.private.drag = Some(TabDrag { tab, moved: false });
+                self.activate(tab, executor)
+            }
+            Some(TabPart::Menu) => {
+                self.private.menu_open = !self.private.menu_open;
+                EventResponse::HandledRedraw
+            }
+            Some(TabPart::MenuItem(tab)) => {
+                self.private.menu_open = false;
+                self.activate(tab, executor);
+                EventResponse::HandledRebuild
+            }
+            None if local_cursor_pos.y < self.strip_height => EventResponse::Handled,
+            None => EventResponse::Pass,
+        }
+    }
+    fn on_release(&mut self, executor: &mut CallbackExecutor) -> EventResponse {
+        match self.private.drag.take() {
+            Some(TabDrag { moved: true, .. }) => {
+                executor.execute(&self.callback, self);
+                EventResponse::Handled
+            }
+            Some(_) => EventResponse::Handled,
+            None => EventResponse::Pass,
+        }
+    }
+    fn on_pointer_lost(&mut self, _executor: &mut CallbackExecutor) -> EventResponse {
+        self.private.drag = None;
+        EventResponse::HandledRedraw
+    }
+    fn on_cursor_move(
+        &mut self,
+        local_cursor_pos: Vec2px,
+        _executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        let p = local_cursor_pos.as_vec2();
+        if let Some(drag) = self.private.drag {
+            let slot = match self.slot_at(p.x.clamp(0.0, self.size().x - 1.0)) {
+                Some(slot) => slot,
+                None => return EventResponse::Handled,
+            };
+            let mut order = self.display_order();
+            let from = order.iter().position(|&t| t == drag.tab).unwrap();
+            let to = self.private.first + slot;
+            if from == to {
+                return EventResponse::Handled;
+            }
+            order.remove(from);
+            order.insert(to, drag.tab);
+            self.order = order;
+            self.private.drag = Some(TabDrag {
+                tab: drag.tab,
+                moved: true,
+            });
+            return EventResponse::HandledRedraw;
+        }
+
+        let hover = self.part_at(p);
+        if hover != self.private.hover {
+            self.private.hover = hover;
+            EventResponse::HandledRedraw
+        } else {
+            EventResponse::Handled
+        }
+    }
+    fn on_cursor_leave(&mut self, _executor: &mut CallbackExecutor) -> EventResponse {
+        self.private.hover = None;
+        EventResponse::HandledRedraw
+    }
+    fn on_key_press(&mut self, key: GlutinKey, executor: &mut CallbackExecutor) -> EventResponse {
+        match key {
+            GlutinKey::Escape if self.private.menu_open => {
+                self.private.menu_open = false;
+                EventResponse::HandledRedraw
+            }
+            GlutinKey::Tab if executor.world().is_ctrl_pressed() && !self.tabs.is_empty() => {
+                let order = self.display_order();
+                let n = order.len();
+                let position = order.iter().position(|&t| t == self.active).unwrap_or(0);
+                let position = if executor.world().is_shift_pressed() {
+                    (position + n - 1) % n
+                } else {
+                    (position + 1) % n
+                };
+                self.activate(order[position], executor)
+            }
+            _ => EventResponse::Pass,
+        }
+    }
+    fn on_focus_lost(&mut self, _executor: &mut CallbackExecutor) -> EventResponse {
+        if self.private.menu_open {
+            self.private.menu_open = false;
+            EventResponse::HandledRedraw
+        } else {
+            EventResponse::Pass
+        }
+    }
+    fn on_draw_build(&self, builder: &mut DrawBuilder) {
+        let font_size = self.font_size.to_pixels(self.strip_height, 1.0);
+        builder.add_clr_rect(
+            Rect::from_min_max(Vec2::new(0.0, self.strip_height), self.size().as_vec2()),
+            self.background,
+        );
+        self.build_strip(builder, font_size);
+        if self.private.menu_open {
+            self.build_menu(builder, font_size);
+        }
+    }
+    fn size(&self) -> Vec2px {
+        self.private.real_size
+    }
+}
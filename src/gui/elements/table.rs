@@ -0,0 +1,620 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Neg, Shl};
+
+use gui::widget::*;
+use gui::*;
+use mecs::GlutinKey;
+use tools::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TableValue {
+    Text(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+}
+
+impl TableValue {
+    fn rank(&self) -> u8 {
+        match self {
+            TableValue::Bool(_) => 0,
+            TableValue::Int(_) | TableValue::Float(_) => 1,
+            TableValue::Text(_) => 2,
+        }
+    }
+    pub fn compare(&self, other: &TableValue) -> Ordering {
+        match (self, other) {
+            (TableValue::Text(a), TableValue::Text(b)) => a.cmp(b),
+            (TableValue::Int(a), TableValue::Int(b)) => a.cmp(b),
+            (TableValue::Bool(a), TableValue::Bool(b)) => a.cmp(b),
+            (TableValue::Int(a), TableValue::Float(b)) => {
+                (*a as f64).partial_cmp(b).unwrap_or(Ordering::Equal)
+            }
+            (TableValue::Float(a), TableValue::Int(b)) => {
+                a.partial_cmp(&(*b as f64)).unwrap_or(Ordering::Equal)
+            }
+            (TableValue::Float(a), TableValue::Float(b)) => {
+                a.partial_cmp(b).unwrap_or(Ordering::Equal)
+            }
+            (a, b) => a.rank().cmp(&b.rank()),
+        }
+    }
+    fn format(&self, precision: Option<usize>) -> String {
+        match (self, precision) {
+            (TableValue::Float(x), Some(p)) => format!("{:.*}", p, x),
+            (value, _) => format!("{}", value),
+        }
+    }
+}
+
+impl fmt::Display for TableValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TableValue::Text(s) => write!(f, "{}", s),
+            TableValue::Int(i) => write!(f, "{}", i),
+            TableValue::Float(x) => write!(f, "{}", x),
+            TableValue::Bool(b) => write!(f, "{}", b),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for TableValue {
+    fn from(s: &'a str) -> TableValue {
+        TableValue::Text(s.to_owned())
+    }
+}
+impl From<String> for TableValue {
+    fn from(s: String) -> TableValue {
+        TableValue::Text(s)
+    }
+}
+impl From<i32> for TableValue {
+    fn from(i: i32) -> TableValue {
+        TableValue::Int(i as i64)
+    }
+}
+impl From<i64> for TableValue {
+    fn from(i: i64) -> TableValue {
+        TableValue::Int(i)
+    }
+}
+impl From<usize> for TableValue {
+    fn from(i: usize) -> TableValue {
+        TableValue::Int(i as i64)
+    }
+}
+impl From<f32> for TableValue {
+    fn from(x: f32) -> TableValue {
+        TableValue::Float(x as f64)
+    }
+}
+impl From<f64> for TableValue {
+    fn from(x: f64) -> TableValue {
+        TableValue::Float(x)
+    }
+}
+impl From<bool> for TableValue {
+    fn from(b: bool) -> TableValue {
+        TableValue::Bool(b)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+#[derive(Clone)]
+pub struct TableColumn {
+    pub title: String,
+    pub width: f32,
+    pub min_width: f32,
+    pub sortable: bool,
+    pub align: Align,
+    pub precision: Option<usize>,
+}
+
+impl TableColumn {
+    pub fn new(title: &str, width: f32) -> TableColumn {
+        TableColumn {
+            title: title.to_owned(),
+            width,
+            min_width: 24.0,
+            sortable: true,
+            align: Align::left(),
+            precision: None,
+        }
+    }
+    pub fn align(mut self, align: Align) -> TableColumn {
+        self.align = align;
+        self
+    }
+    pub fn precision(mut self, decimals: usize) -> TableColumn {
+        self.precision = Some(decimals);
+        self
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum TableDrag {
+    Resize(usize, f32, f32),
+    Select,
+}
+
+/// Sorting and column widths of a table
+type TableLayout = (Option<(usize, SortOrder)>, Vec<f32>);
+
+#[derive(Default, Clone)]
+pub struct TablePrivate {
+    real_size: Vec2px,
+    order: Vec<usize>,
+    sorted_by: Option<(usize, SortOrder)>,
+    base: Option<TableLayout>,
+    drag: Option<TableDrag>,
+    anchor: Option<usize>,
+    cursor: Option<usize>,
+    hover: Option<usize>,
+    scroll: f32,
+}
+
+#[derive(Clone)]
+pub struct Table {
+    pub size: WidgetSize,
+    pub columns: Vec<TableColumn>,
+    pub rows: Vec<Vec<TableValue>>,
+    pub sort: Option<(usize, SortOrder)>,
+    pub selection_mode: SelectionMode,
+    pub selected: Vec<usize>,
+    pub row_height: f32,
+    pub header_height: f32,
+    pub font: String,
+    pub font_size: FontSize,
+    pub text_color: Vec4,
+    pub background: Vec4,
+    pub header_background: Vec4,
+    pub row_colors: (Vec4, Vec4),
+    pub hover_color: Vec4,
+    pub selection_color: Vec4,
+    pub grid_color: Vec4,
+    pub callback: GuiCallback<Table>,
+    pub private: TablePrivate,
+}
+
+impl Default for Table {
+    fn default() -> Table {
+        Table {
+            size: Default::default(),
+            columns: vec![],
+            rows: vec![],
+            sort: None,
+            selection_mode: SelectionMode::Single,
+            selected: vec![],
+            row_height: 20.0,
+            header_height: 22.0,
+            font: "sans-serif".to_owned(),
+            font_size: FontSize::Em(0.65),
+            text_color: Vec4::WHITE,
+            background: Vec4::grey(0.1),
+            header_background: Vec4::grey(0.2),
+            row_colors: (Vec4::grey(0.12), Vec4::grey(0.15)),
+            hover_color: Vec4::grey(1.0).with_w(0.08),
+            selection_color: Vec4::new(0.2, 0.4, 0.8, 0.6),
+            grid_color: Vec4::grey(0.5).with_w(0.3),
+            callback: Default::default(),
+            private: Default::default(),
+        }
+    }
+}
+
+const CELL_PAD: f32 = 4.0;
+const RESIZE_GRAB: f32 = 4.0;
+
+impl Table {
+    pub fn new(columns: Vec<TableColumn>, rows: Vec<Vec<TableValue>>) -> Table {
+        Table {
+            columns,
+            rows,
+            ..Default::default()
+        }
+    }
+
+    /// Data row indices in the order they are displayed
+    pub fn display_order(&self) -> &[usize] {
+        &self.private.order
+    }
+
+    fn widths(&self) -> Vec<f32> {
+        self.columns.iter().map(|c| c.width).collect()
+    }
+
+    fn update_order(&mut self) {
+        if self.private.order.len() == self.rows.len() && self.private.sorted_by == self.sort {
+            return;
+        }
+
+        let mut order: Vec<usize> = (0..self.rows.len()).collect();
+        if let Some((col, dir)) = self.sort {
+            let rows = &self.rows;
+            let empty = TableValue::Text(String::new());
+            order.sort_by(|&a, &b| {
+                let va = rows[a].get(col).unwrap_or(&empty);
+                let vb = rows[b].get(col).unwrap_or(&empty);
+                match dir {
+                    SortOrder::Ascending => va.compare(vb),
+                    SortOrder::Descending => vb.compare(va),
+                }
+            });
+        }
+        self.private.order = order;
+        self.private.sorted_by = self.sort;
+    }
+
+    fn body_height(&self) -> f32 {
+        f32::max(self.size().y - self.header_height, 0.0)
+    }
+    fn max_scroll(&self) -> f32 {
+        f32::max(
+            self.rows.len() as f32 * self.row_height - self.body_height(),
+            0.0,
+        )
+    }
+    fn scroll_to(&mut self, position: usize) {
+        let top = position as f32 * self.row_height;
+        if top < self.private.scroll {
+            self.private.scroll = top;
+        } else if top + self.row_height > self.private.scroll + self.body_height() {
+            self.private.scroll = top + self.row_height - self.body_height();
+        }
+        self.private.scroll = self.private.scroll.clamp(0.0, self.max_scroll());
+    }
+
+    /// Position in the display order under the given local height
+    fn position_at(&self, y: f32) -> Option<usize> {
+        if y < self.header_height {
+            return None;
+        }
+        let i = ((y - self.header_height + self.private.scroll) / self.row_height).floor() as usize;
+        if i < self.rows.len() {
+            Some(i)
+        } else {
+            None
+        }
+    }
+    fn column_at(&self, x: f32) -> Option<usize> {
+        let mut left = 0.0;
+        for (i, col) in self.columns.iter().enumerate() {
+            if x >= left && x < left + col.width {
+                return Some(i);
+            }
+            left += col.width;
+        }
+        None
+    }
+    fn border_at(&self, x: f32) -> Option<usize> {
+        let mut right = 0.0;
+        for (i, col) in self.columns.iter().enumerate() {
+            right += col.width;
+            if (x - right).abs() <= RESIZE_GRAB {
+                return Some(i);
+            }
+        }
+        None
+    }
+
+    fn select(&mut self, position: usize, toggle: bool, extend: bool) {
+        let row = self.private.order[position];
+        match self.selection_mode {
+            SelectionMode::None => return,
+            SelectionMode::Single => self.selected = vec![row],
+            SelectionMode::Multi => {
+                let anchor = self
+                    .private
+                    .anchor
+                    .and_then(|a| self.private.order.iter().position(|&r| r == a))
+                    .filter(|_| extend);
+                if let Some(a) = anchor {
+                    let (lo, hi) = (usize::min(a, position), usize::max(a, position));
+                    if !toggle {
+                        self.selected.clear();
+                    }
+                    for i in lo..=hi {
+                        let r = self.private.order[i];
+                        if !self.selected.contains(&r) {
+                            self.selected.push(r);
+                        }
+                    }
+                    self.private.cursor = Some(row);
+                    return;
+                } else if toggle {
+                    if self.selected.contains(&row) {
+                        self.selected.retain(|&r| r != row);
+                    } else {
+                        self.selected.push(row);
+                    }
+                } else {
+                    self.selected = vec![row];
+                }
+            }
+        }
+        self.private.anchor = Some(row);
+        self.private.cursor = Some(row);
+    }
+
+    fn add_cell_text(
+        &self,
+        builder: &mut DrawBuilder,
+        text: &str,
+        rct: Rect,
+        align: Align,
+        font_size: f32,
+    ) {
+        let o = builder.offset;
+        builder.offset = o + Vec3::from_vec2(rct.pos() + Vec2::new(CELL_PAD, 0.0), 0.0);
+        builder.add_text(
+            text,
+            &self.font,
+            Vec2px::new(f32::max(rct.width() - CELL_PAD * 2.0, 0.0), rct.height()),
+            self.text_color,
+            align,
+            font_size,
+        );
+        builder.offset = o;
+    }
+
+    fn build_header(&self, builder: &mut DrawBuilder, font_size: f32) {
+        let width = self.size().x;
+        builder.add_clr_rect(
+            Rect::from_min_max(Vec2::origin(), Vec2::new(width, self.header_height)),
+            self.header_background,
+        );
+
+        let mut left = 0.0;
+        let mut borders = Path::new();
+        for (i, col) in self.columns.iter().enumerate() {
+            if left >= width {
+                break;
+            }
+            let right = f32::min(left + col.width, width);
+            let rct =
+                Rect::from_min_max(Vec2::new(left, 0.0), Vec2::new(right, self.header_height));
+            let arrow_space = if self.sort.map(|s| s.0) == Some(i) {
+                let c = Vec2::new(right - 8.0, self.header_height / 2.0);
+                let dir = match self.sort {
+                    Some((_, SortOrder::Ascending)) => -1.0,
+                    _ => 1.0,
+                };
+                let mut arrow = Path::new();
+                arrow
+                    .move_to(Vec2px::new(c.x - 4.0, c.y - 2.0 * dir))
+                    .line_to(Vec2px::new(c.x + 4.0, c.y - 2.0 * dir))
+                    .line_to(Vec2px::new(c.x, c.y + 3.0 * dir))
+                    .close();
+                builder.fill_path(&arrow, FillRule::NonZero, DrawFill::Solid(self.text_color));
+                14.0
+            } else {
+                0.0
+            };
+            self.add_cell_text(
+                builder,
+                &col.title,
+                Rect::from_min_max(rct.pos(), Vec2::new(right - arrow_space, rct.bottom)),
+                col.align,
+                font_size,
+            );
+            borders
+                .move_to(Vec2px::new(right, 0.0))
+                .line_to(Vec2px::new(right, self.size().y));
+            left = right;
+        }
+        borders
+            .move_to(Vec2px::new(0.0, self.header_height))
+            .line_to(Vec2px::new(width, self.header_height));
+        builder.stroke_path(&borders, &StrokeStyle::new(1.0), self.grid_color);
+    }
+
+    fn build_rows(&self, builder: &mut DrawBuilder, font_size: f32) {
+        let size = self.size();
+        // rows cut by the header or the bottom edge are drawn in part
+        let first = (self.private.scroll / self.row_height).floor() as usize;
+        builder.set_clip(Some(Rect::from_min_max(
+            Vec2::new(0.0, self.header_height),
+            size.as_vec2(),
+        )));
+        for position in first..self.rows.len() {
+            let y = self.header_height + position as f32 * self.row_height - self.private.scroll;
+            if y >= size.y {
+                break;
+            }
+
+            let row = self.private.order[position];
+            let row_rct =
+                Rect::from_pos_size(Vec2::new(0.0, y), Vec2::new(size.x, self.row_height));
+            let bckg = if position % 2 == 0 {
+                self.row_colors.0
+            } else {
+                self.row_colors.1
+            };
+            builder.add_clr_rect(row_rct, bckg);
+            if self.selected.contains(&row) {
+                builder.add_clr_rect(row_rct, self.selection_color);
+            } else if self.private.hover == Some(position) {
+                builder.add_clr_rect(row_rct, self.hover_color);
+            }
+
+            let mut left = 0.0;
+            for (col, value) in self.columns.iter().zip(self.rows[row].iter()) {
+                if left >= size.x {
+                    break;
+                }
+                let right = f32::min(left + col.width, size.x);
+                self.add_cell_text(
+                    builder,
+                    &value.format(col.precision),
+                    Rect::from_min_max(Vec2::new(left, y), Vec2::new(right, y + self.row_height)),
+                    col.align,
+                    font_size,
+                );
+                left = right;
+            }
+        }
+        builder.set_clip(None);
+    }
+}
+
+impl_widget_building_for!(Table);
+impl Widget for Table {
+    fn constraint(&mut self, self_constraint: WidgetConstraints) {
+        self.private.real_size = self.size.resolve(&self_constraint);
+        if self.private.base.is_none() {
+            self.private.base = Some((self.sort, self.widths()));
+        }
+        self.update_order();
+        self.private.scroll = self.private.scroll.min(self.max_scroll());
+    }
+    fn adopt_state(&mut self, previous: &dyn Widget) {
+        if let Some(previous) = previous.downcast_ref::<Table>() {
+            // sorting and column widths changed by the user win until the data sets new ones
+            let base = (self.sort, self.widths());
+            if previous.private.base.as_ref() == Some(&base)
+                && previous.columns.len() == self.columns.len()
+            {
+                self.sort = previous.sort;
+                for (col, prev) in self.columns.iter_mut().zip(previous.columns.iter()) {
+                    col.width = prev.width;
+                }
+                self.private.base = previous.private.base.clone();
+            }
+            self.private.anchor = previous.private.anchor;
+            self.private.cursor = previous.private.cursor;
+            self.private.scroll = previous.private.scroll;
+        }
+    }
+    fn on_press(
+        &mut self,
+        local_cursor_pos: Vec2px,
+        executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        let p = local_cursor_pos.as_vec2();
+        if p.y < self.header_height {
+            if let Some(col) = self.border_at(p.x) {
+                self.private.drag = Some(TableDrag::Resize(col, p.x, self.columns[col].width));
+                return EventResponse::Handled;
+            }
+            if let Some(col) = self.column_at(p.x) {
+                if self.columns[col].sortable {
+                    self.sort = match self.sort {
+                        Some((c, SortOrder::Ascending)) if c == col => {
+                            Some((col, SortOrder::Descending))
+                        }
+                        _ => Some((col, SortOrder::Ascending)),
+                    };
+                    self.update_order();
+                    executor.execute(&self.callback, self);
+                    return EventResponse::HandledRedraw;
+                }
+            }
+            return EventResponse::Handled;
+        }
+
+        match self.position_at(p.y) {
+            Some(position) => {
+                let toggle = executor.world().is_ctrl_pressed();
+                let extend = executor.world().is_shift_pressed();
+                self.select(position, toggle, extend);
+                self.private.drag = Some(TableDrag::Select);
+                executor.execute(&self.callback, self);
+                EventResponse::HandledRedraw
+            }
+            None => EventResponse::Handled,
+        }
+    }
+    fn on_release(&mut self, executor: &mut CallbackExecutor) -> EventResponse {
+        if let Some(TableDrag::Resize(..)) = self.private.drag.take() {
+            executor.execute(&self.callback, self);
+        }
+        EventResponse::Handled
+    }
+    fn on_pointer_lost(&mut self, _executor: &mut CallbackExecutor) -> EventResponse {
+        self.private.drag = None;
+        EventResponse::HandledRedraw
+    }
+    fn on_cursor_move(
+        &mut self,
+        local_cursor_pos: Vec2px,
+        _executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        let p = local_cursor_pos.as_vec2();
+        if let Some(TableDrag::Resize(col, start_x, start_width)) = self.private.drag {
+            let column = &mut self.columns[col];
+            column.width = f32::max(start_width + p.x - start_x, column.min_width);
+            return EventResponse::HandledRedraw;
+        }
+
+        let hover = self.position_at(p.y);
+        if hover != self.private.hover {
+            self.private.hover = hover;
+            EventResponse::HandledRedraw
+        } else {
+            EventResponse::Handled
+        }
+    }
+    fn on_cursor_leave(&mut self, _executor: &mut CallbackExecutor) -> EventResponse {
+        self.private.hover = None;
+        EventResponse::HandledRedraw
+    }
+    fn on_scroll(
+        &mut self,
+        _local_cursor_pos: Vec2px,
+        delta: Vec2,
+        _executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        let max_scroll = self.max_scroll();
+        if max_scroll <= 0.0 {
+            return EventResponse::Pass;
+        }
+        self.private.scroll =
+            (self.private.scroll - delta.y * self.row_height * 3.0).clamp(0.0, max_scroll);
+        self.private.hover = None;
+        EventResponse::HandledRedraw
+    }
+    fn on_key_press(&mut self, key: GlutinKey, executor: &mut CallbackExecutor) -> EventResponse {
+        let n = self.rows.len();
+        if n == 0 || self.selection_mode == SelectionMode::None {
+            return EventResponse::Pass;
+        }
+        let current = self
+            .private
+            .cursor
+            .and_then(|a| self.private.order.iter().position(|&r| r == a));
+        let page = usize::max((self.body_height() / self.row_height) as usize, 1);
+        let target = match (key, current) {
+            (GlutinKey::Up, Some(i)) => i.saturating_sub(1),
+            (GlutinKey::Down, Some(i)) => usize::min(i + 1, n - 1),
+            (GlutinKey::PageUp, Some(i)) => i.saturating_sub(page),
+            (GlutinKey::PageDown, Some(i)) => usize::min(i + page, n - 1),
+            (GlutinKey::Up, None) | (GlutinKey::Down, None) | (GlutinKey::Home, _) => 0,
+            (GlutinKey::End, _) => n - 1,
+            _ => return EventResponse::Pass,
+        };
+
+        let extend = executor.world().is_shift_pressed();
+        self.select(target, false, extend);
+        self.scroll_to(target);
+        executor.execute(&self.callback, self);
+        EventResponse::HandledRedraw
+    }
+    fn on_draw_build(&self, builder: &mut DrawBuilder) {
+        let font_size = self.font_size.to_pixels(self.row_height, 1.0);
+        builder.add_clr_rect(
+            Rect::from_min_max(Vec2::origin(), self.size().as_vec2()),
+            self.background,
+        );
+        self.build_rows(builder, font_size);
+        self.build_header(builder, font_size);
+    }
+    fn size(&self) -> Vec2px {
+        self.private.real_size
+    }
+}
@@ -0,0 +1,575 @@
+use std::collections::HashSet;
+use std::ops::{Neg, Shl};
+
+use gui::widget::*;
+use gui::*;
+use mecs::GlutinKey;
+use tools::*;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SelectionMode {
+    None,
+    Single,
+    Multi,
+}
+
+#[derive(Clone)]
+pub struct TreeNode {
+    pub key: String,
+    pub text: Text,
+    pub icon: Option<Image>,
+    pub children: Vec<TreeNode>,
+    pub expanded: bool,
+    pub lazy: bool,
+}
+
+impl TreeNode {
+    pub fn new(key: &str, label: &str) -> TreeNode {
+        TreeNode {
+            key: key.to_owned(),
+            text: Text {
+                text: label.to_owned(),
+                color: Vec4::WHITE,
+                align: Align::left(),
+                font_size: FontSize::Em(0.7),
+                ..Default::default()
+            },
+            icon: None,
+            children: vec![],
+            expanded: false,
+            lazy: false,
+        }
+    }
+    pub fn with_icon(mut self, image: &str) -> TreeNode {
+        self.icon = Some(Image::from(image));
+        self
+    }
+    pub fn with_children(mut self, children: Vec<TreeNode>) -> TreeNode {
+        self.children = children;
+        self
+    }
+    pub fn expanded(mut self) -> TreeNode {
+        self.expanded = true;
+        self
+    }
+    /// The children are only asked for through the populate callback when the node is expanded
+    pub fn lazy(mut self) -> TreeNode {
+        self.lazy = true;
+        self
+    }
+
+    fn has_children(&self) -> bool {
+        !self.children.is_empty() || self.lazy
+    }
+}
+
+struct TreeRow {
+    path: Vec<usize>,
+    lasts: Vec<bool>,
+}
+
+impl TreeRow {
+    fn depth(&self) -> usize {
+        self.path.len() - 1
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct TreeViewPrivate {
+    real_size: Vec2px,
+    expanded: Option<HashSet<String>>,
+    cursor: Option<String>,
+    anchor: Option<String>,
+    hover: Option<usize>,
+    scroll: f32,
+    populating: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct TreeView {
+    pub size: WidgetSize,
+    pub roots: Vec<TreeNode>,
+    pub selection_mode: SelectionMode,
+    pub selected: Vec<String>,
+    pub row_height: f32,
+    pub indent: f32,
+    pub guides: bool,
+    pub background: Vec4,
+    pub guide_color: Vec4,
+    pub hover_color: Vec4,
+    pub selection_color: Vec4,
+    pub callback: GuiCallback<TreeView>,
+    pub populate: GuiCallback<TreeView>,
+    pub private: TreeViewPrivate,
+}
+
+impl Default for TreeView {
+    fn default() -> TreeView {
+        TreeView {
+            size: Default::default(),
+            roots: vec![],
+            selection_mode: SelectionMode::Single,
+            selected: vec![],
+            row_height: 20.0,
+            indent: 16.0,
+            guides: true,
+            background: Vec4::grey(0.1),
+            guide_color: Vec4::grey(0.5).with_w(0.5),
+            hover_color: Vec4::grey(1.0).with_w(0.08),
+            selection_color: Vec4::new(0.2, 0.4, 0.8, 0.6),
+            callback: Default::default(),
+            populate: Default::default(),
+            private: Default::default(),
+        }
+    }
+}
+
+fn collect_expanded(nodes: &[TreeNode], expanded: &mut HashSet<String>) {
+    for node in nodes {
+        if node.expanded {
+            expanded.insert(node.key.clone());
+        }
+        collect_expanded(&node.children, expanded);
+    }
+}
+
+fn find_path(nodes: &[TreeNode], key: &str, path: &mut Vec<usize>) -> bool {
+    for (i, node) in nodes.iter().enumerate() {
+        path.push(i);
+        if node.key == key || find_path(&node.children, key, path) {
+            return true;
+        }
+        path.pop();
+    }
+    false
+}
+
+impl TreeView {
+    pub fn new(roots: Vec<TreeNode>, callback: GuiCallback<TreeView>) -> TreeView {
+        TreeView {
+            roots,
+            callback,
+            ..Default::default()
+        }
+    }
+
+    /// Key of the lazy node whose children are asked for in the populate callback
+    pub fn populating(&self) -> Option<&str> {
+        self.private.populating.as_deref()
+    }
+    pub fn is_expanded(&self, key: &str) -> bool {
+        match self.private.expanded {
+            Some(ref expanded) => expanded.contains(key),
+            None => false,
+        }
+    }
+    pub fn is_selected(&self, key: &str) -> bool {
+        self.selected.iter().any(|k| k == key)
+    }
+
+    fn node(&self, path: &[usize]) -> &TreeNode {
+        let mut node = &self.roots[path[0]];
+        for &i in &path[1..] {
+            node = &node.children[i];
+        }
+        node
+    }
+
+    fn rows(&self) -> Vec<TreeRow> {
+        let mut rows = vec![];
+        self.push_rows(&self.roots, &mut vec![], &mut vec![], &mut rows);
+        rows
+    }
+    fn push_rows(
+        &self,
+        nodes: &[TreeNode],
+        path: &mut Vec<usize>,
+        lasts: &mut Vec<bool>,
+        rows: &mut Vec<TreeRow>,
+    ) {
+        for (i, node) in nodes.iter().enumerate() {
+            path.push(i);
+            lasts.push(i + 1 == nodes.len());
+            rows.push(TreeRow {
+                path: path.clone(),
+                lasts: lasts.clone(),
+            });
+            if self.is_expanded(&node.key) {
+                self.push_rows(&node.children, path, lasts, rows);
+            }
+            path.pop();
+            lasts.pop();
+        }
+    }
+
+    fn row_index(&self, rows: &[TreeRow], key: &str) -> Option<usize> {
+        rows.iter().position(|r| self.node(&r.path).key == key)
+    }
+    fn row_at(&self, rows: &[TreeRow], y: f32) -> Option<usize> {
+        let i = ((y + self.private.scroll) / self.row_height).floor();
+        if y >= 0.0 && i >= 0.0 && (i as usize) < rows.len() {
+            Some(i as usize)
+        } else {
+            None
+        }
+    }
+    fn expander_rect(&self, row: &TreeRow, y: f32) -> Rect {
+        Rect::from_pos_size(
+            Vec2::new(row.depth() as f32 * self.indent, y),
+            Vec2::new(self.indent, self.row_height),
+        )
+    }
+
+    fn max_scroll(&self, row_count: usize) -> f32 {
+        f32::max(row_count as f32 * self.row_height - self.size().y, 0.0)
+    }
+    fn scroll_to(&mut self, row: usize, row_count: usize) {
+        let top = row as f32 * self.row_height;
+        let bottom = top + self.row_height;
+        if top < self.private.scroll {
+            self.private.scroll = top;
+        } else if bottom > self.private.scroll + self.size().y {
+            self.private.scroll = bottom - self.size().y;
+        }
+        self.private.scroll = self.private.scroll.clamp(0.0, self.max_scroll(row_count));
+    }
+
+    fn set_expanded(&mut self, key: &str, expand: bool, executor: &mut CallbackExecutor) {
+        if self.is_expanded(key) == expand {
+            return;
+        }
+        let expanded = self.private.expanded.get_or_insert_with(HashSet::new);
+        if expand {
+            expanded.insert(key.to_owned());
+        } else {
+            expanded.remove(key);
+        }
+
+        let mut path = vec![];
+        if expand && find_path(&self.roots, key, &mut path) {
+            let node = self.node(&path);
+            if node.lazy && node.children.is_empty() {
+                self.private.populating = Some(key.to_owned());
+                executor.execute(&self.populate, self);
+                self.private.populating = None;
+            }
+        }
+        let row_count = self.rows().len();
+        self.private.scroll = self.private.scroll.min(self.max_scroll(row_count));
+    }
+
+    fn select(&mut self, rows: &[TreeRow], row: usize, toggle: bool, extend: bool) {
+        let key = self.node(&rows[row].path).key.clone();
+        match self.selection_mode {
+            SelectionMode::None => {}
+            SelectionMode::Single => self.selected = vec![key.clone()],
+            SelectionMode::Multi => {
+                let anchor = self
+                    .private
+                    .anchor
+                    .as_ref()
+                    .and_then(|a| self.row_index(rows, a))
+                    .filter(|_| extend);
+                if let Some(a) = anchor {
+                    let (lo, hi) = (usize::min(a, row), usize::max(a, row));
+                    let range: Vec<String> = (lo..=hi)
+                        .map(|i| self.node(&rows[i].path).key.clone())
+                        .collect();
+                    if !toggle {
+                        self.selected.clear();
+                    }
+                    for k in range {
+                        if !self.is_selected(&k) {
+                            self.selected.push(k);
+                        }
+                    }
+                } else if toggle {
+                    if self.is_selected(&key) {
+                        self.selected.retain(|k| *k != key);
+                    } else {
+                        self.selected.push(key.clone());
+                    }
+                    self.private.anchor = Some(key.clone());
+                } else {
+                    self.selected = vec![key.clone()];
+                    self.private.anchor = Some(key.clone());
+                }
+            }
+        }
+        self.private.cursor = Some(key);
+    }
+
+    fn build_guides(&self, builder: &mut DrawBuilder, row: &TreeRow, y: f32) {
+        let mut guides = Path::new();
+        let depth = row.depth();
+        for level in 0..depth {
+            let x = (level as f32 + 0.5) * self.indent;
+            if level + 1 == depth {
+                let bottom = if row.lasts[depth] {
+                    y + self.row_height / 2.0
+                } else {
+                    y + self.row_height
+                };
+                guides
+                    .move_to(Vec2px::new(x, y))
+                    .line_to(Vec2px::new(x, bottom))
+                    .move_to(Vec2px::new(x, y + self.row_height / 2.0))
+                    .line_to(Vec2px::new(
+                        x + self.indent / 2.0,
+                        y + self.row_height / 2.0,
+                    ));
+            } else if !row.lasts[level + 1] {
+                guides
+                    .move_to(Vec2px::new(x, y))
+                    .line_to(Vec2px::new(x, y + self.row_height));
+            }
+        }
+        builder.stroke_path(&guides, &StrokeStyle::new(1.0), self.guide_color);
+    }
+
+    fn build_expander(&self, builder: &mut DrawBuilder, rct: Rect, expanded: bool, clr: Vec4) {
+        let c = rct.mid();
+        let r = f32::min(rct.width(), rct.height()) * 0.2;
+        let mut arrow = Path::new();
+        if expanded {
+            arrow
+                .move_to(Vec2px::new(c.x - r, c.y - r * 0.5))
+                .line_to(Vec2px::new(c.x + r, c.y - r * 0.5))
+                .line_to(Vec2px::new(c.x, c.y + r * 0.7));
+        } else {
+            arrow
+                .move_to(Vec2px::new(c.x - r * 0.5, c.y - r))
+                .line_to(Vec2px::new(c.x + r * 0.7, c.y))
+                .line_to(Vec2px::new(c.x - r * 0.5, c.y + r));
+        }
+        arrow.close();
+        builder.fill_path(&arrow, FillRule::NonZero, DrawFill::Solid(clr));
+    }
+
+    fn build_row(&self, builder: &mut DrawBuilder, row: &TreeRow, index: usize, y: f32) {
+        let node = self.node(&row.path);
+        let width = self.size().x;
+        let row_rct = Rect::from_pos_size(Vec2::new(0.0, y), Vec2::new(width, self.row_height));
+
+        if self.is_selected(&node.key) {
+            builder.add_clr_rect(row_rct, self.selection_color);
+        } else if self.private.hover == Some(index) {
+            builder.add_clr_rect(row_rct, self.hover_color);
+        }
+        if self.private.cursor.as_ref() == Some(&node.key) {
+            builder.add_round_rect_border(
+                row_rct,
+                CornerRadii::all(0.0),
+                1.0,
+                DrawFill::Solid(self.selection_color.with_w(1.0)),
+            );
+        }
+        if self.guides {
+            self.build_guides(builder, row, y);
+        }
+
+        let expander = self.expander_rect(row, y);
+        if node.has_children() {
+            let expanded = self.is_expanded(&node.key);
+            self.build_expander(builder, expander, expanded, node.text.color);
+        }
+
+        let o = builder.offset;
+        let mut x = expander.right;
+        if let Some(ref icon) = node.icon {
+            let side = self.row_height - 4.0;
+            let mut icon = icon.clone();
+            icon.constraint(WidgetConstraints {
+                max_size: Vec2px::new_xy(side),
+                ..Default::default()
+            });
+            builder.offset = o + Vec3::new(x, y + 2.0, 0.0);
+            icon.on_draw_build(builder);
+            x += side + 4.0;
+        }
+        let mut text = node.text.clone();
+        text.constraint(WidgetConstraints {
+            max_size: Vec2px::new(f32::max(width - x, 0.0), self.row_height),
+            ..Default::default()
+        });
+        builder.offset = o + Vec3::new(x, y, 0.0);
+        text.on_draw_build(builder);
+        builder.offset = o;
+    }
+}
+
+impl_widget_building_for!(TreeView);
+impl Widget for TreeView {
+    fn constraint(&mut self, self_constraint: WidgetConstraints) {
+        self.private.real_size = self.size.resolve(&self_constraint);
+        if self.private.expanded.is_none() {
+            let mut expanded = HashSet::new();
+            collect_expanded(&self.roots, &mut expanded);
+            self.private.expanded = Some(expanded);
+        }
+    }
+    fn adopt_state(&mut self, previous: &dyn Widget) {
+        if let Some(previous) = previous.downcast_ref::<TreeView>() {
+            self.private.expanded = previous.private.expanded.clone();
+            self.private.cursor = previous.private.cursor.clone();
+            self.private.anchor = previous.private.anchor.clone();
+            self.private.scroll = previous.private.scroll;
+        }
+    }
+    fn on_press(
+        &mut self,
+        local_cursor_pos: Vec2px,
+        executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        let rows = self.rows();
+        let row = match self.row_at(&rows, local_cursor_pos.y) {
+            Some(row) => row,
+            None => return EventResponse::Handled,
+        };
+
+        let key = self.node(&rows[row].path).key.clone();
+        let y = row as f32 * self.row_height - self.private.scroll;
+        if self.node(&rows[row].path).has_children()
+            && self
+                .expander_rect(&rows[row], y)
+                .contains(local_cursor_pos.as_vec2())
+        {
+            let expand = !self.is_expanded(&key);
+            self.set_expanded(&key, expand, executor);
+            self.private.cursor = Some(key);
+            return EventResponse::HandledRedraw;
+        }
+
+        let toggle = executor.world().is_ctrl_pressed();
+        let extend = executor.world().is_shift_pressed();
+        self.select(&rows, row, toggle, extend);
+        executor.execute(&self.callback, self);
+        EventResponse::HandledRedraw
+    }
+    fn on_release(&mut self, _executor: &mut CallbackExecutor) -> EventResponse {
+        EventResponse::Handled
+    }
+    fn on_cursor_move(
+        &mut self,
+        local_cursor_pos: Vec2px,
+        _executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        let hover = self.row_at(&self.rows(), local_cursor_pos.y);
+        if hover != self.private.hover {
+            self.private.hover = hover;
+            EventResponse::HandledRedraw
+        } else {
+            EventResponse::Handled
+        }
+    }
+    fn on_cursor_leave(&mut self, _executor: &mut CallbackExecutor) -> EventResponse {
+        self.private.hover = None;
+        EventResponse::HandledRedraw
+    }
+    fn on_scroll(
+        &mut self,
+        _local_cursor_pos: Vec2px,
+        delta: Vec2,
+        _executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        let max_scroll = self.max_scroll(self.rows().len());
+        if max_scroll <= 0.0 {
+            return EventResponse::Pass;
+        }
+        self.private.scroll =
+            (self.private.scroll - delta.y * self.row_height * 3.0).clamp(0.0, max_scroll);
+        self.private.hover = None;
+        EventResponse::HandledRedraw
+    }
+    fn on_key_press(&mut self, key: GlutinKey, executor: &mut CallbackExecutor) -> EventResponse {
+        let rows = self.rows();
+        if rows.is_empty() {
+            return EventResponse::Pass;
+        }
+        let current = self
+            .private
+            .cursor
+            .as_ref()
+            .and_then(|k| self.row_index(&rows, k));
+        let current_row = current.unwrap_or(0);
+        let node = self.node(&rows[current_row].path);
+        let node_key = node.key.clone();
+        let has_children = node.has_children();
+        let expanded = self.is_expanded(&node_key);
+
+        let target = match key {
+            GlutinKey::Up => current.map_or(0, |i| i.saturating_sub(1)),
+            GlutinKey::Down => current.map_or(0, |i| usize::min(i + 1, rows.len() - 1)),
+            GlutinKey::Home => 0,
+            GlutinKey::End => rows.len() - 1,
+            GlutinKey::PageUp => {
+                let page = (self.size().y / self.row_height).floor() as usize;
+                current_row.saturating_sub(usize::max(page, 1))
+            }
+            GlutinKey::PageDown => {
+                let page = (self.size().y / self.row_height).floor() as usize;
+                usize::min(current_row + usize::max(page, 1), rows.len() - 1)
+            }
+            GlutinKey::Right if has_children && !expanded => {
+                self.set_expanded(&node_key, true, executor);
+                return EventResponse::HandledRedraw;
+            }
+            GlutinKey::Right
+                if expanded
+                    && current_row + 1 < rows.len()
+                    && rows[current_row + 1].depth() > rows[current_row].depth() =>
+            {
+                current_row + 1
+            }
+            GlutinKey::Left if expanded => {
+                self.set_expanded(&node_key, false, executor);
+                return EventResponse::HandledRedraw;
+            }
+            GlutinKey::Left if rows[current_row].depth() > 0 => {
+                let parent = &rows[current_row].path[..rows[current_row].depth()];
+                rows.iter().position(|r| r.path[..] == *parent).unwrap()
+            }
+            GlutinKey::Space | GlutinKey::Return => {
+                let toggle = self.selection_mode == SelectionMode::Multi;
+                self.select(&rows, current_row, toggle, false);
+                executor.execute(&self.callback, self);
+                return EventResponse::HandledRedraw;
+            }
+            GlutinKey::Left | GlutinKey::Right => return EventResponse::Handled,
+            _ => return EventResponse::Pass,
+        };
+
+        self.scroll_to(target, rows.len());
+        if self.selection_mode == SelectionMode::Multi && executor.world().is_ctrl_pressed() {
+            self.private.cursor = Some(self.node(&rows[target].path).key.clone());
+        } else {
+            let extend = executor.world().is_shift_pressed();
+            self.select(&rows, target, false, extend);
+            executor.execute(&self.callback, self);
+        }
+        EventResponse::HandledRedraw
+    }
+    fn on_draw_build(&self, builder: &mut DrawBuilder) {
+        let size = self.size();
+        builder.add_clr_rect(
+            Rect::from_min_max(Vec2::origin(), size.as_vec2()),
+            self.background,
+        );
+
+        // rows cut by the top or bottom edge are drawn in part
+        let rows = self.rows();
+        let first = (self.private.scroll / self.row_height).floor() as usize;
+        builder.set_clip(Some(Rect::from_min_max(Vec2::origin(), size.as_vec2())));
+        for (i, row) in rows.iter().enumerate().skip(first) {
+            let y = i as f32 * self.row_height - self.private.scroll;
+            if y >= size.y {
+                break;
+            }
+            self.build_row(builder, row, i, y);
+        }
+        builder.set_clip(None);
+    }
+    fn size(&self) -> Vec2px {
+        self.private.real_size
+    }
+}
@@ -0,0 +1,264 @@
+use std::collections::BTreeMap;
+use std::ops::{Neg, Range, Shl};
+
+use gui::widget::*;
+use gui::*;
+use tools::*;
+
+/// Height assumed for a list that was not laid out yet
+const FALLBACK_HEIGHT: f32 = 720.0;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum RowHeight {
+    Fixed(f32),
+    /// Rows take the height they need, the estimate stands in for those not measured yet
+    ///
+    /// Row widgets are laid out without a height limit, like in a `VertLayout`, so they need a
+    /// height of their own rather than filling the space given.
+    Estimated(f32),
+}
+
+impl RowHeight {
+    fn estimate(self) -> f32 {
+        match self {
+            RowHeight::Fixed(h) | RowHeight::Estimated(h) => h,
+        }
+    }
+}
+
+/// Scroll window of a `VirtualList`
+///
+/// It belongs in the gui data since building needs it to know which rows to create, the list
+/// hands back the changed view through its callback. What the layout reports, the height and
+/// the measured rows, is not recorded by the gui history, but scrolling is like any other
+/// callback; data that should not undo scrolling has to leave the view out of its `PartialEq`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct VirtualListView {
+    pub scroll: f32,
+    /// Height of the list at the last layout, zero before
+    pub height: f32,
+    /// Rows measured with `RowHeight::Estimated`
+    pub measured: BTreeMap<usize, f32>,
+}
+
+impl VirtualListView {
+    pub fn row_height(&self, row: usize, row_height: RowHeight) -> f32 {
+        match row_height {
+            RowHeight::Fixed(h) => h,
+            RowHeight::Estimated(h) => self.measured.get(&row).copied().unwrap_or(h),
+        }
+    }
+    /// Distance of the top of `row` from the top of the first row
+    pub fn row_top(&self, row: usize, row_height: RowHeight) -> f32 {
+        let estimate = row_height.estimate();
+        match row_height {
+            RowHeight::Fixed(_) => row as f32 * estimate,
+            RowHeight::Estimated(_) => self
+                .measured
+                .range(..row)
+                .fold(row as f32 * estimate, |top, (_, &h)| top + h - estimate),
+        }
+    }
+    /// Row covering the height `y` measured from the top of the first row
+    pub fn row_at(&self, y: f32, row_height: RowHeight) -> usize {
+        let estimate = row_height.estimate();
+        let unmeasured = |row: usize, top: f32| row + f32::max((y - top) / estimate, 0.0) as usize;
+        if let RowHeight::Fixed(_) = row_height {
+            return unmeasured(0, 0.0);
+        }
+        let (mut row, mut top) = (0, 0.0);
+        for (&i, &h) in self.measured.iter() {
+            let gap = (i - row) as f32 * estimate;
+            if y < top + gap {
+                return unmeasured(row, top);
+            }
+            top += gap;
+            if y < top + h {
+                return i;
+            }
+            top += h;
+            row = i + 1;
+        }
+        unmeasured(row, top)
+    }
+    /// Scrolls as little as needed to bring `row` fully into view
+    pub fn scroll_to(&mut self, row: usize, row_height: RowHeight) {
+        let top = self.row_top(row, row_height);
+        let bottom = top + self.row_height(row, row_height);
+        if top < self.scroll {
+            self.scroll = top;
+        } else if bottom > self.scroll + self.height {
+            self.scroll = bottom - self.height;
+        }
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct VirtualListPrivate {
+    real_size: Vec2px,
+    first: usize,
+    scroll: f32,
+    child_id: usize,
+    stacking_depth: f32,
+    measured: BTreeMap<usize, f32>,
+}
+
+/// List creating widgets only for the rows in its window, for counts too large to build them all
+///
+/// `build` adds the list with one child per row of the window, rows cut by its edges are drawn in
+/// part. Scrolling and the height the list gets are reported through `callback`, which should
+/// store `view` in the gui data for the next rebuild.
+#[derive(Clone)]
+pub struct VirtualList {
+    pub count: usize,
+    pub row_height: RowHeight,
+    /// Rows created but hidden beyond both ends of the window
+    pub overscan: usize,
+    pub view: VirtualListView,
+    pub size: WidgetSize,
+    pub callback: GuiCallback<VirtualList>,
+    pub private: VirtualListPrivate,
+}
+
+impl Default for VirtualList {
+    fn default() -> Self {
+        VirtualList {
+            count: 0,
+            row_height: RowHeight::Fixed(20.0),
+            overscan: 3,
+            view: Default::default(),
+            size: WidgetSize::fill(),
+            callback: Default::default(),
+            private: Default::default(),
+        }
+    }
+}
+
+impl VirtualList {
+    pub fn new(count: usize, row_height: RowHeight, view: &VirtualListView) -> VirtualList {
+        VirtualList {
+            count,
+            row_height,
+            view: view.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Rows created when building, the window and the overscan
+    pub fn rows(&self) -> Range<usize> {
+        if self.count == 0 {
+            return 0..0;
+        }
+        let height = if self.view.height > 0.0 {
+            self.view.height
+        } else {
+            FALLBACK_HEIGHT
+        };
+        let first = self.view.row_at(self.view.scroll, self.row_height);
+        let last = self.view.row_at(self.view.scroll + height, self.row_height);
+        let first = usize::min(first, self.count - 1).saturating_sub(self.overscan);
+        let last = usize::min(last.saturating_add(1 + self.overscan), self.count);
+        first..last
+    }
+    /// Adds the list with the rows of its window as children, `row` adds the widget of one row
+    pub fn build<F>(mut self, mut row: F)
+    where
+        F: FnMut(usize),
+    {
+        let rows = self.rows();
+        self.private.first = rows.start;
+        let _list = -self;
+        for i in rows {
+            row(i);
+        }
+    }
+
+    fn content_height(&self) -> f32 {
+        self.view.row_top(self.count, self.row_height)
+    }
+    fn max_scroll(&self) -> f32 {
+        f32::max(self.content_height() - self.size().y, 0.0)
+    }
+    fn child_top(&self, child_index: usize) -> f32 {
+        self.view
+            .row_top(self.private.first + child_index, self.row_height)
+            - self.private.scroll
+    }
+    fn current_child(&self) -> usize {
+        let mut index = self.private.child_id;
+        while self.private.first + index < self.count && !self.is_child_shown(index) {
+            index += 1;
+        }
+        index
+    }
+}
+
+impl_widget_building_for!(VirtualList);
+impl Widget for VirtualList {
+    fn constraint(&mut self, self_constraint: WidgetConstraints) {
+        self.private.real_size = self.size.resolve(&self_constraint);
+        self.private.scroll = self.view.scroll.clamp(0.0, self.max_scroll());
+    }
+    fn place_child(&mut self, child_size: Vec2px, child_descent: f32) -> WidgetPosition {
+        let index = self.current_child();
+        self.private.child_id = index + 1;
+        if let RowHeight::Estimated(_) = self.row_height {
+            self.private
+                .measured
+                .insert(self.private.first + index, child_size.y);
+        }
+        self.private.stacking_depth += 0.01;
+        let sd = self.private.stacking_depth;
+        self.private.stacking_depth += child_descent;
+        WidgetPosition::new(Vec2px::new(0.0, self.child_top(index)), sd)
+    }
+    fn child_constraint(&self) -> Option<WidgetConstraints> {
+        let height = match self.row_height {
+            RowHeight::Fixed(h) => h,
+            RowHeight::Estimated(_) => std::f32::INFINITY,
+        };
+        Some(WidgetConstraints {
+            max_size: Vec2px::new(self.size().x, height),
+            ..Default::default()
+        })
+    }
+    fn is_child_shown(&self, child_index: usize) -> bool {
+        let row = self.private.first + child_index;
+        let top = self.child_top(child_index);
+        row < self.count
+            && top < self.size().y
+            && top + self.view.row_height(row, self.row_height) > 0.0
+    }
+    fn clips_children(&self) -> bool {
+        true
+    }
+    fn on_layout(&mut self, executor: &mut CallbackExecutor) {
+        let mut view = self.view.clone();
+        view.scroll = self.private.scroll;
+        view.height = self.size().y;
+        view.measured.append(&mut self.private.measured);
+        view.measured.split_off(&self.count);
+        if view != self.view {
+            self.view = view;
+            executor.execute(&self.callback, self);
+        }
+    }
+    fn on_scroll(
+        &mut self,
+        _local_cursor_pos: Vec2px,
+        delta: Vec2,
+        executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        let max_scroll = self.max_scroll();
+        if max_scroll <= 0.0 {
+            return EventResponse::Pass;
+        }
+        let step = self.row_height.estimate() * 3.0;
+        self.view.scroll = (self.private.scroll - delta.y * step).clamp(0.0, max_scroll);
+        executor.execute(&self.callback, self);
+        EventResponse::HandledRebuild
+    }
+    fn size(&self) -> Vec2px {
+        self.private.real_size
+    }
+}
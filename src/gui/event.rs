@@ -0,0 +1,88 @@
+use mecs::{GlutinButton, GlutinKey};
+use tools::*;
+
+/// Where an event is on its way through the hierarchy
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EventPhase {
+    /// Travelling from the root down to the parent of the target
+    Capture,
+    /// At the widget the event is meant for
+    Target,
+    /// Travelling from the parent of the target back up to the root
+    Bubble,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum EventKind {
+    Press(GlutinButton),
+    Release(GlutinButton),
+    CursorMove,
+    Scroll(Vec2),
+    KeyPress(GlutinKey),
+    Char(char),
+    Gesture(Gesture),
+}
+
+/// Pointer or key event handed to `Widget::on_event` of every widget on the path to its target
+///
+/// The typed handlers like `on_press` are the default action, they run at the target and while
+/// bubbling unless a widget prevents the default.
+#[derive(Debug, Clone)]
+pub struct WidgetEvent {
+    pub kind: EventKind,
+    pub target: usize,
+    pub current: usize,
+    pub phase: EventPhase,
+    /// Cursor relative to the current widget
+    pub local_cursor_pos: Vec2px,
+    propagation_stopped: bool,
+    default_prevented: bool,
+    pointer_grabbed: bool,
+}
+
+impl WidgetEvent {
+    pub fn new(kind: EventKind, target: usize) -> WidgetEvent {
+        WidgetEvent {
+            kind,
+            target,
+            current: target,
+            phase: EventPhase::Target,
+            local_cursor_pos: Vec2px::zero(),
+            propagation_stopped: false,
+            default_prevented: false,
+            pointer_grabbed: false,
+        }
+    }
+
+    /// No widget after the current one sees the event
+    pub fn stop_propagation(&mut self) {
+        self.propagation_stopped = true;
+    }
+    pub fn is_propagation_stopped(&self) -> bool {
+        self.propagation_stopped
+    }
+    /// Skips the typed handlers and what the context does on its own, like accelerators
+    pub fn prevent_default(&mut self) {
+        self.default_prevented = true;
+    }
+    pub fn is_default_prevented(&self) -> bool {
+        self.default_prevented
+    }
+
+    /// Makes the current widget the owner of the pointer, e.g. a scrolling area taking a drag
+    /// from a child button
+    ///
+    /// The previous owner gets `on_pointer_lost`, moves and the release go to the current
+    /// widget from now on. Propagation stops here.
+    pub fn grab_pointer(&mut self) {
+        self.pointer_grabbed = true;
+        self.propagation_stopped = true;
+    }
+    pub(super) fn grabbed_by(&self) -> Option<usize> {
+        if self.pointer_grabbed {
+            Some(self.current)
+        } else {
+            None
+        }
+    }
+}
@@ -1,9 +1,7 @@
-use gui::widget_parser::StoredCallback;
+use gui::EventResponse;
 use gui::GuiCallback;
-use gui::Widget;
 use gui::WidgetParser;
 use mecs::{Message, StaticWorld};
-use std::any::Any;
 use std::fmt::Debug;
 use std::ops::Shl;
 use std::time::Duration;
@@ -66,57 +64,59 @@ impl Shl<WidgetAdder> for WidgetAdder {
 }
 
 pub trait GuiBuilder: Clone + PartialEq + Debug {
+    /// Callback receiving the gui data, the value reported by the widget and the world
+    ///
+    /// The returned response is applied by the context after the event, e.g. `HandledRebuild`
+    /// when the callback changed something the data comparison can not see.
+    fn make_callback<F, S>(&self, f: F) -> GuiCallback<S>
+    where
+        F: 'static + Fn(&mut Self, &S, &mut StaticWorld) -> EventResponse,
+        Self: Sized + 'static,
+    {
+        GuiCallback::new(f)
+    }
+
     fn make_callback3<F, S>(&self, f: F) -> GuiCallback<S>
     where
         F: 'static + Fn(&mut Self, &S, &mut StaticWorld),
-        S: Widget + 'static,
         Self: Sized + 'static,
     {
-        WidgetParser::add_callback(StoredCallback::ThreeParams(Box::new(
-            move |input: &mut dyn Any, instance: &dyn Any, world: &mut StaticWorld| {
-                f(
-                    input.downcast_mut().unwrap(),
-                    instance.downcast_ref().unwrap(),
-                    world,
-                );
-            },
-        )))
+        GuiCallback::new(move |data: &mut Self, value: &S, world: &mut StaticWorld| {
+            f(data, value, world);
+            EventResponse::Handled
+        })
     }
 
     fn make_callback2<F, S>(&self, f: F) -> GuiCallback<S>
     where
         F: 'static + Fn(&mut Self, &S),
-        S: Widget + 'static,
         Self: Sized + 'static,
     {
-        WidgetParser::add_callback(StoredCallback::TwoParams(Box::new(
-            move |input: &mut dyn Any, instance: &dyn Any| {
-                f(
-                    input.downcast_mut().unwrap(),
-                    instance.downcast_ref().unwrap(),
-                );
-            },
-        )))
+        GuiCallback::new(move |data: &mut Self, value: &S, _: &mut StaticWorld| {
+            f(data, value);
+            EventResponse::Handled
+        })
     }
     fn make_callback1<F, S>(&self, f: F) -> GuiCallback<S>
     where
         F: 'static + Fn(&mut Self),
-        S: Widget + 'static,
         Self: Sized + 'static,
     {
-        WidgetParser::add_callback(StoredCallback::OneParam(Box::new(
-            move |input: &mut dyn Any| {
-                f(input.downcast_mut().unwrap());
-            },
-        )))
+        GuiCallback::new(move |data: &mut Self, _: &S, _: &mut StaticWorld| {
+            f(data);
+            EventResponse::Handled
+        })
     }
 
     fn make_callback0<F, S>(&self, f: F) -> GuiCallback<S>
     where
         F: 'static + Fn(),
-        S: Widget + 'static,
+        Self: Sized + 'static,
     {
-        WidgetParser::add_callback(StoredCallback::ZeroParam(Box::new(f)))
+        GuiCallback::new(move |_: &mut Self, _: &S, _: &mut StaticWorld| {
+            f();
+            EventResponse::Handled
+        })
     }
 
     fn build(&self);
@@ -0,0 +1,157 @@
+use mecs::Message;
+
+struct HistoryEntry<D> {
+    label: Option<String>,
+    state: D,
+}
+
+struct HistoryGroup {
+    label: Option<String>,
+    recorded: bool,
+}
+
+/// Undo and redo stacks of gui data snapshots, see `GuiContext::enable_history`
+///
+/// An entry holds the data from before a change. Changes while a group is open go into a single
+/// entry, the context itself does not record while the pointer is grabbed, so a whole drag is
+/// one step.
+pub struct History<D>
+where
+    D: Clone + PartialEq,
+{
+    undo: Vec<HistoryEntry<D>>,
+    redo: Vec<HistoryEntry<D>>,
+    base: D,
+    next_label: Option<String>,
+    group: Option<HistoryGroup>,
+    /// Oldest entries are dropped beyond this many
+    pub limit: usize,
+}
+
+impl<D> History<D>
+where
+    D: Clone + PartialEq,
+{
+    pub fn new(current: &D, limit: usize) -> History<D> {
+        History {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            base: current.clone(),
+            next_label: None,
+            group: None,
+            limit,
+        }
+    }
+
+    /// Records the change since the last commit, if any
+    pub fn commit(&mut self, current: &D) -> bool {
+        if *current == self.base {
+            return false;
+        }
+        let previous = std::mem::replace(&mut self.base, current.clone());
+        self.redo.clear();
+
+        let label = self.next_label.take();
+        if let Some(group) = self.group.as_mut() {
+            if group.recorded {
+                return true;
+            }
+            group.recorded = true;
+            let label = group.label.clone().or(label);
+            self.push(label, previous);
+            return true;
+        }
+        self.push(label, previous);
+        true
+    }
+    /// Takes `current` as the new starting point without recording, for changes not to undo
+    pub fn sync(&mut self, current: &D) {
+        if *current != self.base {
+            self.base = current.clone();
+        }
+    }
+
+    /// Data before the latest entry, `current` moves to the redo stack
+    pub fn undo(&mut self, current: &D) -> Option<D> {
+        self.end_group();
+        let entry = self.undo.pop()?;
+        self.redo.push(HistoryEntry {
+            label: entry.label.clone(),
+            state: current.clone(),
+        });
+        self.base = entry.state.clone();
+        Some(entry.state)
+    }
+    pub fn redo(&mut self, current: &D) -> Option<D> {
+        self.end_group();
+        let entry = self.redo.pop()?;
+        self.undo.push(HistoryEntry {
+            label: entry.label.clone(),
+            state: current.clone(),
+        });
+        self.base = entry.state.clone();
+        Some(entry.state)
+    }
+
+    /// Label of the next recorded entry, like "Change color"
+    pub fn set_next_label(&mut self, label: &str) {
+        self.next_label = Some(label.to_owned());
+    }
+    /// Relabels the latest entry
+    pub fn label_last(&mut self, label: &str) {
+        if let Some(entry) = self.undo.last_mut() {
+            entry.label = Some(label.to_owned());
+        }
+    }
+    /// Every change until `end_group` becomes a single entry
+    pub fn begin_group(&mut self, label: Option<&str>) {
+        self.group = Some(HistoryGroup {
+            label: label.map(str::to_owned),
+            recorded: false,
+        });
+    }
+    pub fn end_group(&mut self) {
+        self.group = None;
+    }
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.next_label = None;
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+    pub fn undo_label(&self) -> Option<&str> {
+        self.undo.last().and_then(|e| e.label.as_deref())
+    }
+    pub fn redo_label(&self) -> Option<&str> {
+        self.redo.last().and_then(|e| e.label.as_deref())
+    }
+    /// Labels of the undo stack, oldest first
+    pub fn undo_labels(&self) -> Vec<Option<&str>> {
+        self.undo.iter().map(|e| e.label.as_deref()).collect()
+    }
+
+    fn push(&mut self, label: Option<String>, state: D) {
+        self.undo.push(HistoryEntry { label, state });
+        if self.undo.len() > self.limit {
+            let excess = self.undo.len() - self.limit;
+            self.undo.drain(..excess);
+        }
+    }
+}
+
+/// Steers the history of the gui contexts receiving it, e.g. from a callback or a menu
+#[derive(Debug, Clone)]
+pub enum GuiHistory {
+    Undo,
+    Redo,
+    /// Relabels the latest entry, callbacks record their change before a message arrives
+    Label(String),
+    Clear,
+}
+impl Message for GuiHistory {}
@@ -0,0 +1,240 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use gui::WidgetPosition;
+use gui::{Align, DrawBuilder, EventKind, EventResponse, HAlign, Shortcut, VAlign};
+use mecs::GlutinKey;
+use tools::*;
+
+/// Outcome of one event sent through the widgets, see `Inspector::traces`
+#[derive(Debug, Clone)]
+pub struct EventTrace {
+    pub kind: EventKind,
+    pub target: usize,
+    pub target_type: &'static str,
+    /// Widget whose handler took the event
+    pub handler: Option<(usize, &'static str)>,
+    pub response: EventResponse,
+}
+
+/// Widget as listed by the inspector, in the order of the hierarchy
+#[derive(Debug, Clone)]
+pub struct InspectedWidget {
+    pub id: usize,
+    pub type_name: &'static str,
+    pub depth: usize,
+    pub pos: WidgetPosition,
+    pub size: Vec2px,
+    pub shown: bool,
+}
+
+/// Overlay of `GuiContext` outlining the widgets and listing their tree, the latest event
+/// responses and the times of the profiler
+///
+/// `shortcut` toggles it, the profiler runs while it is shown.
+pub struct Inspector {
+    enabled: bool,
+    /// Whether the profiler ran before the inspector turned it on
+    was_profiling: bool,
+    traces: VecDeque<EventTrace>,
+    pub shortcut: Option<Shortcut>,
+    /// Traces kept, oldest dropped first
+    pub trace_limit: usize,
+    pub font: String,
+    pub font_size: f32,
+}
+
+impl Default for Inspector {
+    fn default() -> Self {
+        Inspector {
+            enabled: false,
+            was_profiling: false,
+            traces: VecDeque::new(),
+            shortcut: Some(Shortcut::new(GlutinKey::F12)),
+            trace_limit: 8,
+            font: "sans-serif".to_owned(),
+            font_size: 11.0,
+        }
+    }
+}
+
+/// Profiler sections shown by the inspector, with their labels
+pub(super) const INSPECTOR_TIMINGS: [(&str, &str); 4] = [
+    ("Build", "Rebuild_Gui"),
+    ("Layout", "Layout"),
+    ("Render", "Rebuild_Render"),
+    ("Draw", "Draw"),
+];
+const PANEL_WIDTH: f32 = 340.0;
+const OVERLAY_DEPTH: f32 = 0.95;
+
+impl Inspector {
+    pub fn new() -> Inspector {
+        Default::default()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+    /// Returns whether the profiler should run
+    pub(super) fn set_enabled(&mut self, enabled: bool, profiling: bool) -> bool {
+        if enabled == self.enabled {
+            return profiling;
+        }
+        self.enabled = enabled;
+        if enabled {
+            self.was_profiling = profiling;
+            self.traces.clear();
+            true
+        } else {
+            self.was_profiling
+        }
+    }
+
+    pub fn traces(&self) -> impl Iterator<Item = &EventTrace> {
+        self.traces.iter()
+    }
+    pub(super) fn record(&mut self, trace: EventTrace) {
+        // moves nobody cares about would push everything else out
+        if trace.kind == EventKind::CursorMove && trace.response == EventResponse::Pass {
+            return;
+        }
+        self.traces.push_back(trace);
+        while self.traces.len() > self.trace_limit {
+            self.traces.pop_front();
+        }
+    }
+
+    pub(super) fn build(
+        &self,
+        builder: &mut DrawBuilder,
+        widgets: &[InspectedWidget],
+        hovered: Option<usize>,
+        timings: &[(&str, Option<Duration>)],
+        window_size: Vec2px,
+    ) {
+        builder.owner = None;
+        for widget in widgets.iter().filter(|w| w.shown) {
+            builder.offset = WidgetPosition::new(widget.pos.pos, OVERLAY_DEPTH).to_pixels(1.0);
+            let rect = Rect::from_pos_size(Vec2::origin(), widget.size.as_vec2());
+            if Some(widget.id) == hovered {
+                builder.add_clr_rect(rect, Vec4::new(0.2, 0.6, 1.0, 0.3));
+                add_outline(builder, rect, 2.0, Vec4::new(0.2, 0.6, 1.0, 1.0));
+            } else {
+                add_outline(builder, rect, 1.0, depth_color(widget.depth));
+            }
+        }
+
+        let mut lines = vec![timings
+            .iter()
+            .map(|(label, time)| match time {
+                Some(time) => format!("{} {:.2} ms", label, time.as_secs_f32() * 1000.0),
+                None => format!("{} -", label),
+            })
+            .collect::<Vec<_>>()
+            .join("   ")];
+        if let Some(widget) = hovered.and_then(|id| widgets.iter().find(|w| w.id == id)) {
+            lines.push(format!("> {}", describe(widget)));
+        }
+        lines.push(String::new());
+        for trace in &self.traces {
+            let handler = match trace.handler {
+                Some((id, name)) => format!("#{} {}", id, short_name(name)),
+                None => "-".to_owned(),
+            };
+            lines.push(format!(
+                "{:?} @ #{} {}: {:?} by {}",
+                trace.kind,
+                trace.target,
+                short_name(trace.target_type),
+                trace.response,
+                handler
+            ));
+        }
+        lines.push(String::new());
+        for widget in widgets {
+            lines.push(format!("{}{}", "  ".repeat(widget.depth), describe(widget)));
+        }
+
+        let line_height = (self.font_size * 1.4).ceil();
+        let panel = Vec2px::new(PANEL_WIDTH.min(window_size.x), window_size.y);
+        builder.offset =
+            WidgetPosition::new(Vec2px::new(window_size.x - panel.x, 0.0), OVERLAY_DEPTH)
+                .to_pixels(1.0);
+        builder.add_clr_rect(
+            Rect::from_pos_size(Vec2::origin(), panel.as_vec2()),
+            Vec4::new(0.0, 0.0, 0.0, 0.75),
+        );
+        let fitting = (panel.y / line_height).floor().max(0.0) as usize;
+        for (i, line) in lines.iter().take(fitting).enumerate() {
+            let top = Vec2px::new(window_size.x - panel.x + 6.0, i as f32 * line_height);
+            builder.offset = WidgetPosition::new(top, OVERLAY_DEPTH).to_pixels(1.0);
+            builder.add_text(
+                line,
+                &self.font,
+                Vec2px::new(panel.x - 12.0, line_height),
+                Vec4::WHITE,
+                Align::from(HAlign::Left, VAlign::Center),
+                self.font_size,
+            );
+        }
+    }
+}
+
+fn add_outline(builder: &mut DrawBuilder, rect: Rect, width: f32, clr: Vec4) {
+    let size = rect.size();
+    let edges = [
+        Rect::from_pos_size(Vec2::origin(), Vec2::new(size.x, width)),
+        Rect::from_pos_size(Vec2::new(0.0, size.y - width), Vec2::new(size.x, width)),
+        Rect::from_pos_size(Vec2::origin(), Vec2::new(width, size.y)),
+        Rect::from_pos_size(Vec2::new(size.x - width, 0.0), Vec2::new(width, size.y)),
+    ];
+    for edge in edges.iter() {
+        builder.add_clr_rect(edge.offset(rect.pos()), clr);
+    }
+}
+
+/// Hue cycling with the depth, so nested outlines tell apart
+fn depth_color(depth: usize) -> Vec4 {
+    const COLORS: [(f32, f32, f32); 4] = [
+        (1.0, 0.35, 0.35),
+        (0.35, 1.0, 0.35),
+        (1.0, 0.8, 0.2),
+        (0.8, 0.4, 1.0),
+    ];
+    let (r, g, b) = COLORS[depth % COLORS.len()];
+    Vec4::new(r, g, b, 0.8)
+}
+
+fn describe(widget: &InspectedWidget) -> String {
+    format!(
+        "#{} {} ({:.0}, {:.0}) {:.0}x{:.0} z {:.2}{}",
+        widget.id,
+        short_name(widget.type_name),
+        widget.pos.pos.x,
+        widget.pos.pos.y,
+        widget.size.x,
+        widget.size.y,
+        widget.pos.depth,
+        if widget.shown { "" } else { " hidden" }
+    )
+}
+
+/// Type name without module paths, also inside generic arguments
+fn short_name(type_name: &str) -> String {
+    let mut name = String::with_capacity(type_name.len());
+    let mut segment = String::new();
+    for ch in type_name.chars() {
+        match ch {
+            ':' => segment.clear(),
+            '<' | '>' | ',' | ' ' | '(' | ')' | '&' => {
+                name.push_str(&segment);
+                segment.clear();
+                name.push(ch);
+            }
+            _ => segment.push(ch),
+        }
+    }
+    name.push_str(&segment);
+    name
+}
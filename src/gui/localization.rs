@@ -0,0 +1,413 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+
+thread_local! {
+    static LOCALIZATION_INSTANCE: RefCell<Localization> = RefCell::new(Localization::default());
+}
+
+/// Extension of catalog files, the file stem names the locale (`de.lang`)
+pub const CATALOG_EXTENSION: &str = "lang";
+
+#[derive(Debug)]
+pub enum LocalizationError {
+    IoError(std::io::Error),
+    /// Catalog line that is neither a comment nor `key = value`
+    ParseError {
+        locale: String,
+        line: usize,
+    },
+}
+
+impl From<std::io::Error> for LocalizationError {
+    fn from(e: std::io::Error) -> LocalizationError {
+        LocalizationError::IoError(e)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PluralCategory {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other,
+}
+
+impl PluralCategory {
+    pub fn from_name(name: &str) -> Option<PluralCategory> {
+        match name {
+            "zero" => Some(PluralCategory::Zero),
+            "one" => Some(PluralCategory::One),
+            "two" => Some(PluralCategory::Two),
+            "few" => Some(PluralCategory::Few),
+            "many" => Some(PluralCategory::Many),
+            "other" => Some(PluralCategory::Other),
+            _ => None,
+        }
+    }
+
+    /// Plural form a language uses for `n`, unknown languages follow english
+    pub fn of(locale: &str, n: u64) -> PluralCategory {
+        let language = locale.split(&['-', '_'][..]).next().unwrap_or("");
+        let (n10, n100) = (n % 10, n % 100);
+        match language {
+            "ja" | "ko" | "zh" | "vi" | "th" | "id" => PluralCategory::Other,
+            "fr" | "pt" if n <= 1 => PluralCategory::One,
+            "ru" | "uk" | "be" | "sr" | "hr" | "bs" => {
+                if n10 == 1 && n100 != 11 {
+                    PluralCategory::One
+                } else if (2..=4).contains(&n10) && !(12..=14).contains(&n100) {
+                    PluralCategory::Few
+                } else {
+                    PluralCategory::Many
+                }
+            }
+            "pl" => {
+                if n == 1 {
+                    PluralCategory::One
+                } else if (2..=4).contains(&n10) && !(12..=14).contains(&n100) {
+                    PluralCategory::Few
+                } else {
+                    PluralCategory::Many
+                }
+            }
+            "cs" | "sk" => match n {
+                1 => PluralCategory::One,
+                2..=4 => PluralCategory::Few,
+                _ => PluralCategory::Other,
+            },
+            _ if n == 1 => PluralCategory::One,
+            _ => PluralCategory::Other,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum CatalogEntry {
+    Single(String),
+    Plural(HashMap<PluralCategory, String>),
+}
+
+/// Translated strings of one locale
+///
+/// Catalogs are text files of `key = value` lines, `#` starts a comment. Plural forms are
+/// given as `key[one] = ...`, `key[other] = ...`, values may hold `{name}` placeholders and
+/// `\n` line breaks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Catalog {
+    locale: String,
+    entries: HashMap<String, CatalogEntry>,
+}
+
+impl Catalog {
+    pub fn new(locale: &str) -> Catalog {
+        Catalog {
+            locale: locale.to_owned(),
+            entries: HashMap::new(),
+        }
+    }
+
+    pub fn parse(locale: &str, source: &str) -> Result<Catalog, LocalizationError> {
+        let mut catalog = Catalog::new(locale);
+        for (i, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = LocalizationError::ParseError {
+                locale: locale.to_owned(),
+                line: i + 1,
+            };
+            let (key, value) = match line.find('=') {
+                Some(eq) => (line[..eq].trim(), unescape(line[eq + 1..].trim())),
+                None => return Err(error),
+            };
+
+            if let (Some(open), true) = (key.find('['), key.ends_with(']')) {
+                let category = match PluralCategory::from_name(&key[open + 1..key.len() - 1]) {
+                    Some(category) => category,
+                    None => return Err(error),
+                };
+                catalog.insert_plural(key[..open].trim(), category, &value);
+            } else if key.is_empty() {
+                return Err(error);
+            } else {
+                catalog.insert(key, &value);
+            }
+        }
+        Ok(catalog)
+    }
+
+    /// Load a catalog file, the locale is taken from the file name
+    pub fn load<P: AsRef<Path>>(file: P) -> Result<Catalog, LocalizationError> {
+        let file = file.as_ref();
+        let locale = file
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Catalog::parse(&locale, &fs::read_to_string(file)?)
+    }
+
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    pub fn insert(&mut self, key: &str, value: &str) {
+        self.entries
+            .insert(key.to_owned(), CatalogEntry::Single(value.to_owned()));
+    }
+    pub fn insert_plural(&mut self, key: &str, category: PluralCategory, value: &str) {
+        let entry = self
+            .entries
+            .entry(key.to_owned())
+            .or_insert_with(|| CatalogEntry::Plural(HashMap::new()));
+        match entry {
+            CatalogEntry::Plural(forms) => {
+                forms.insert(category, value.to_owned());
+            }
+            CatalogEntry::Single(_) => {
+                let mut forms = HashMap::new();
+                forms.insert(category, value.to_owned());
+                *entry = CatalogEntry::Plural(forms);
+            }
+        }
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    fn lookup(&self, key: &str, count: Option<u64>) -> Option<&str> {
+        match (self.entries.get(key)?, count) {
+            (CatalogEntry::Single(text), _) => Some(text),
+            (CatalogEntry::Plural(forms), count) => {
+                let category = PluralCategory::of(&self.locale, count.unwrap_or(0));
+                forms
+                    .get(&category)
+                    .or_else(|| forms.get(&PluralCategory::Other))
+                    .map(|s| s.as_str())
+            }
+        }
+    }
+}
+
+fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some(c) => result.push(c),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+/// Replace `{name}` placeholders, `{{` and `}}` produce literal braces
+fn substitute(text: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find(&['{', '}'][..]) {
+        result += &rest[..open];
+        let tail = &rest[open..];
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            result.push(tail.as_bytes()[0] as char);
+            rest = &tail[2..];
+            continue;
+        }
+
+        let name_end = match tail.find('}') {
+            Some(end) if tail.starts_with('{') => end,
+            _ => {
+                result.push(tail.as_bytes()[0] as char);
+                rest = &tail[1..];
+                continue;
+            }
+        };
+        let name = &tail[1..name_end];
+        match args.iter().find(|(arg, _)| *arg == name) {
+            Some((_, value)) => result += &value.to_string(),
+            None => result += &tail[..=name_end],
+        }
+        rest = &tail[name_end + 1..];
+    }
+    result + rest
+}
+
+/// Message catalogs and the active locale used while building guis
+///
+/// Lookups fall back to the source locale and then to the key itself. Switching the locale
+/// makes every `GuiContext` rebuild its widgets.
+#[derive(Default)]
+pub struct Localization {
+    catalogs: HashMap<String, Catalog>,
+    source_locale: String,
+    locale: String,
+    generation: usize,
+    reported: HashSet<(String, String)>,
+}
+
+impl Localization {
+    /// Load every catalog file of a directory, `source_locale` is the language of the keys
+    pub fn load_dir<P: AsRef<Path>>(dir: P, source_locale: &str) -> Result<(), LocalizationError> {
+        let mut catalogs = vec![];
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().map(|e| e == CATALOG_EXTENSION) == Some(true) {
+                catalogs.push(Catalog::load(&path)?);
+            }
+        }
+
+        LOCALIZATION_INSTANCE.with(|localization| {
+            let mut localization = localization.borrow_mut();
+            localization.source_locale = source_locale.to_owned();
+            if localization.locale.is_empty() {
+                localization.locale = source_locale.to_owned();
+            }
+            for catalog in catalogs {
+                localization
+                    .catalogs
+                    .insert(catalog.locale.clone(), catalog);
+            }
+            localization.generation += 1;
+        });
+        Ok(())
+    }
+    pub fn add_catalog(catalog: Catalog) {
+        LOCALIZATION_INSTANCE.with(|localization| {
+            let mut localization = localization.borrow_mut();
+            localization
+                .catalogs
+                .insert(catalog.locale.clone(), catalog);
+            localization.generation += 1;
+        });
+    }
+    pub fn set_source_locale(locale: &str) {
+        LOCALIZATION_INSTANCE.with(|localization| {
+            localization.borrow_mut().source_locale = locale.to_owned();
+        });
+    }
+
+    pub fn set_locale(locale: &str) {
+        LOCALIZATION_INSTANCE.with(|localization| {
+            let mut localization = localization.borrow_mut();
+            if localization.locale != locale {
+                localization.locale = locale.to_owned();
+                localization.generation += 1;
+            }
+        });
+    }
+    pub fn locale() -> String {
+        LOCALIZATION_INSTANCE.with(|localization| localization.borrow().locale.clone())
+    }
+    pub fn available_locales() -> Vec<String> {
+        LOCALIZATION_INSTANCE.with(|localization| {
+            let mut locales: Vec<String> = localization.borrow().catalogs.keys().cloned().collect();
+            locales.sort();
+            locales
+        })
+    }
+    /// Changes whenever the locale or the catalogs change
+    pub fn generation() -> usize {
+        LOCALIZATION_INSTANCE.with(|localization| localization.borrow().generation)
+    }
+
+    pub fn translate(key: &str, args: &[(&str, &dyn Display)]) -> String {
+        Localization::lookup(key, None, args)
+    }
+    /// Translate choosing the plural form for `count`, which is also the `{count}` placeholder
+    pub fn translate_plural(key: &str, count: u64, args: &[(&str, &dyn Display)]) -> String {
+        let mut all_args: Vec<(&str, &dyn Display)> = vec![("count", &count)];
+        all_args.extend_from_slice(args);
+        Localization::lookup(key, Some(count), &all_args)
+    }
+
+    fn lookup(key: &str, count: Option<u64>, args: &[(&str, &dyn Display)]) -> String {
+        LOCALIZATION_INSTANCE.with(|localization| {
+            let mut localization = localization.borrow_mut();
+            let found = localization
+                .catalogs
+                .get(&localization.locale)
+                .and_then(|catalog| catalog.lookup(key, count))
+                .map(|text| text.to_owned());
+
+            let text = match found {
+                Some(text) => text,
+                None => {
+                    localization.report_missing(key);
+                    localization
+                        .catalogs
+                        .get(&localization.source_locale)
+                        .and_then(|catalog| catalog.lookup(key, count))
+                        .unwrap_or(key)
+                        .to_owned()
+                }
+            };
+            substitute(&text, args)
+        })
+    }
+
+    /// Only locales with a catalog of their own can miss translations, the source locale and
+    /// locales without any catalog fall back silently
+    #[cfg(debug_assertions)]
+    fn report_missing(&mut self, key: &str) {
+        if self.locale == self.source_locale || !self.catalogs.contains_key(&self.locale) {
+            return;
+        }
+        let missing = (self.locale.clone(), key.to_owned());
+        if !self.reported.contains(&missing) {
+            eprintln!(
+                "Missing translation of \"{}\" for locale {}",
+                key, self.locale
+            );
+            self.reported.insert(missing);
+        }
+    }
+    #[cfg(not(debug_assertions))]
+    fn report_missing(&mut self, _key: &str) {}
+}
+
+/// Named placeholder value for the translation macros
+pub fn arg<'a, T: Display>(name: &'a str, value: &'a T) -> (&'a str, &'a dyn Display) {
+    (name, value)
+}
+
+/// Translate a catalog key while building a gui: `tr!("save")`, `tr!("hello", name = self.name)`
+#[macro_export]
+macro_rules! tr {
+    ($key:expr) => {
+        $crate::gui::Localization::translate($key, &[])
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)*) => {
+        $crate::gui::Localization::translate(
+            $key,
+            &[$($crate::gui::localization::arg(stringify!($name), &$value)),+],
+        )
+    };
+}
+
+/// Translate a key with plural forms chosen by a count: `trn!("files", self.files.len())`
+#[macro_export]
+macro_rules! trn {
+    ($key:expr, $count:expr) => {
+        $crate::gui::Localization::translate_plural($key, $count as u64, &[])
+    };
+    ($key:expr, $count:expr, $($name:ident = $value:expr),+ $(,)*) => {
+        $crate::gui::Localization::translate_plural(
+            $key,
+            $count as u64,
+            &[$($crate::gui::localization::arg(stringify!($name), &$value)),+],
+        )
+    };
+}
@@ -0,0 +1,702 @@
+use std::f32::consts::PI;
+
+use tools::*;
+
+use super::draw::{DrawFill, ShadedTriangles};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LineJoin {
+    Miter,
+    Round,
+    Bevel,
+}
+
+impl Default for LineJoin {
+    fn default() -> LineJoin {
+        LineJoin::Miter
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LineCap {
+    Butt,
+    Round,
+    Square,
+}
+
+impl Default for LineCap {
+    fn default() -> LineCap {
+        LineCap::Butt
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FillRule {
+    NonZero,
+    EvenOdd,
+}
+
+impl Default for FillRule {
+    fn default() -> FillRule {
+        FillRule::NonZero
+    }
+}
+
+impl FillRule {
+    fn inside(self, winding: i32) -> bool {
+        match self {
+            FillRule::NonZero => winding != 0,
+            FillRule::EvenOdd => winding % 2 != 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrokeStyle {
+    pub width: f32,
+    pub join: LineJoin,
+    pub cap: LineCap,
+    pub miter_limit: f32,
+    pub dashes: Vec<f32>,
+    pub dash_offset: f32,
+}
+
+impl Default for StrokeStyle {
+    fn default() -> StrokeStyle {
+        StrokeStyle {
+            width: 1.0,
+            join: Default::default(),
+            cap: Default::default(),
+            miter_limit: 4.0,
+            dashes: vec![],
+            dash_offset: 0.0,
+        }
+    }
+}
+
+impl StrokeStyle {
+    pub fn new(width: f32) -> StrokeStyle {
+        StrokeStyle {
+            width,
+            ..Default::default()
+        }
+    }
+    pub fn round(width: f32) -> StrokeStyle {
+        StrokeStyle {
+            width,
+            join: LineJoin::Round,
+            cap: LineCap::Round,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct SubPath {
+    pts: Vec<Vec2px>,
+    closed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Path {
+    subpaths: Vec<SubPath>,
+}
+
+fn curve_segments(control_polygon_length: f32) -> usize {
+    usize::min(
+        usize::max(f32::ceil(control_polygon_length / 3.0) as usize, 1),
+        64,
+    )
+}
+
+impl Path {
+    pub fn new() -> Path {
+        Default::default()
+    }
+    pub fn from_points(points: Vec<Vec2px>, closed: bool) -> Path {
+        Path {
+            subpaths: vec![SubPath {
+                pts: points,
+                closed,
+            }],
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.subpaths.iter().all(|sub| sub.pts.is_empty())
+    }
+    pub fn move_to(&mut self, p: Vec2px) -> &mut Path {
+        self.subpaths.push(SubPath {
+            pts: vec![p],
+            closed: false,
+        });
+        self
+    }
+    pub fn line_to(&mut self, p: Vec2px) -> &mut Path {
+        self.open_subpath(p).pts.push(p);
+        self
+    }
+    pub fn quad_to(&mut self, control: Vec2px, p: Vec2px) -> &mut Path {
+        let sub = self.open_subpath(control);
+        let p0 = *sub.pts.last().unwrap();
+        let n = curve_segments((control - p0).length() + (p - control).length());
+        for i in 1..=n {
+            let t = i as f32 / n as f32;
+            let u = 1.0 - t;
+            sub.pts
+                .push(p0 * (u * u) + control * (2.0 * u * t) + p * (t * t));
+        }
+        self
+    }
+    pub fn cubic_to(&mut self, control1: Vec2px, control2: Vec2px, p: Vec2px) -> &mut Path {
+        let sub = self.open_subpath(control1);
+        let p0 = *sub.pts.last().unwrap();
+        let n = curve_segments(
+            (control1 - p0).length() + (control2 - control1).length() + (p - control2).length(),
+        );
+        for i in 1..=n {
+            let t = i as f32 / n as f32;
+            let u = 1.0 - t;
+            sub.pts.push(
+                p0 * (u * u * u)
+                    + control1 * (3.0 * u * u * t)
+                    + control2 * (3.0 * u * t * t)
+                    + p * (t * t * t),
+            );
+        }
+        self
+    }
+    /// Continues the path with a circular arc, angles are in radians
+    pub fn arc(
+        &mut self,
+        center: Vec2px,
+        radius: f32,
+        start_angle: f32,
+        end_angle: f32,
+    ) -> &mut Path {
+        let sweep = end_angle - start_angle;
+        let first = center + Vec2px::pol(radius, start_angle);
+        let continues = match self.subpaths.last() {
+            Some(sub) => !sub.closed,
+            None => false,
+        };
+        if continues {
+            self.line_to(first);
+        } else {
+            self.move_to(first);
+        }
+
+        let n = curve_segments(f32::abs(sweep) * radius);
+        let sub = self.subpaths.last_mut().unwrap();
+        for i in 1..=n {
+            let a = start_angle + sweep * i as f32 / n as f32;
+            sub.pts.push(center + Vec2px::pol(radius, a));
+        }
+        self
+    }
+    pub fn close(&mut self) -> &mut Path {
+        if let Some(sub) = self.subpaths.last_mut() {
+            sub.closed = true;
+        }
+        self
+    }
+
+    /// The subpath that drawing continues, starting a new one where the last was closed
+    fn open_subpath(&mut self, p: Vec2px) -> &mut SubPath {
+        let start = match self.subpaths.last() {
+            Some(sub) if !sub.closed => None,
+            Some(sub) => Some(sub.pts[0]),
+            None => Some(p),
+        };
+        if let Some(start) = start {
+            self.move_to(start);
+        }
+        self.subpaths.last_mut().unwrap()
+    }
+
+    fn polylines(&self, gui_scale: f32) -> Vec<(Vec<Vec2>, bool)> {
+        self.subpaths
+            .iter()
+            .map(|sub| {
+                let mut pts =
+                    dedup_points(sub.pts.iter().map(|p| p.to_pixels(gui_scale)).collect());
+                if sub.closed && pts.len() > 1 && close_points(pts[0], *pts.last().unwrap()) {
+                    pts.pop();
+                }
+                (pts, sub.closed)
+            })
+            .collect()
+    }
+}
+
+fn close_points(a: Vec2, b: Vec2) -> bool {
+    (a - b).length_squared() < 1e-8
+}
+
+fn dedup_points(mut pts: Vec<Vec2>) -> Vec<Vec2> {
+    pts.dedup_by(|a, b| close_points(*a, *b));
+    pts
+}
+
+fn dash_polyline(pts: &[Vec2], closed: bool, pattern: &[f32], offset: f32) -> Vec<Vec<Vec2>> {
+    let mut pattern: Vec<f32> = pattern.iter().map(|d| f32::max(*d, 0.0)).collect();
+    if pattern.len() % 2 == 1 {
+        let repeated = pattern.clone();
+        pattern.extend(repeated);
+    }
+    let period: f32 = pattern.iter().sum();
+
+    let mut pts = pts.to_vec();
+    if closed && pts.len() > 1 {
+        pts.push(pts[0]);
+    }
+
+    let mut idx = 0;
+    let mut remaining = pattern[0];
+    let mut phase = offset % period;
+    if phase < 0.0 {
+        phase += period;
+    }
+    while phase > 0.0 {
+        if phase >= remaining {
+            phase -= remaining;
+            idx = (idx + 1) % pattern.len();
+            remaining = pattern[idx];
+        } else {
+            remaining -= phase;
+            phase = 0.0;
+        }
+    }
+
+    let mut dashes = vec![];
+    let mut current = if idx % 2 == 0 { vec![pts[0]] } else { vec![] };
+    for w in pts.windows(2) {
+        let (a, b) = (w[0], w[1]);
+        let len = (b - a).length();
+        let mut t = 0.0;
+        while len - t > remaining {
+            t += remaining;
+            let p = a + (b - a) * (t / len);
+            if idx % 2 == 0 {
+                current.push(p);
+                dashes.push(std::mem::take(&mut current));
+            } else {
+                current = vec![p];
+            }
+            idx = (idx + 1) % pattern.len();
+            remaining = pattern[idx];
+        }
+        remaining -= len - t;
+        if idx % 2 == 0 {
+            current.push(b);
+        }
+    }
+    if idx % 2 == 0 && !current.is_empty() {
+        dashes.push(current);
+    }
+    dashes
+}
+
+/// Convex part of a stroke, every point also tells whether the edge to the next one lies on the
+/// outline and gets faded out
+type StrokePiece = Vec<(Vec2, bool)>;
+
+/// Fan of the piece with its outline edges faded out
+fn add_piece(tris: &mut ShadedTriangles, piece: &[(Vec2, bool)], clr: Vec4) {
+    let n = piece.len();
+    if n < 3 {
+        return;
+    }
+    let center = piece.iter().fold(Vec2::origin(), |s, &(p, _)| s + p) / n as f32;
+    for i in 0..n {
+        let ((a, outline), b) = (piece[i], piece[(i + 1) % n].0);
+        if close_points(a, b) {
+            continue;
+        }
+        tris.triangle((center, clr), (a, clr), (b, clr));
+        if outline {
+            let nrm = (b - a).perp().sgn();
+            let nrm = if nrm.dot(a - center) < 0.0 { -nrm } else { nrm };
+            tris.fringe(a, b, nrm, clr);
+        }
+    }
+}
+
+/// Cuts off the part of the piece beyond the line through `p` along `dir`, seen from `keep`
+fn clip_piece(piece: StrokePiece, p: Vec2, dir: Vec2, keep: Vec2) -> StrokePiece {
+    let nrm = dir.perp();
+    let nrm = if nrm.dot(keep - p) < 0.0 { -nrm } else { nrm };
+    let side = |q: Vec2| nrm.dot(q - p);
+    let n = piece.len();
+    let mut clipped = Vec::with_capacity(n + 1);
+    for i in 0..n {
+        let ((a, outline), b) = (piece[i], piece[(i + 1) % n].0);
+        let (sa, sb) = (side(a), side(b));
+        if sa >= 0.0 {
+            clipped.push((a, outline));
+        }
+        if (sa >= 0.0) != (sb >= 0.0) {
+            let x = a + (b - a) * (sa / (sa - sb));
+            // the edge leaving the kept side runs along the cut
+            clipped.push((x, sa < 0.0 && outline));
+        }
+    }
+    clipped
+}
+
+/// Piece from `p` along the arc of radius `hw` starting in direction `a0`
+fn arc_piece(p: Vec2, a0: f32, sweep: f32, hw: f32) -> StrokePiece {
+    let k = usize::max(f32::ceil(f32::abs(sweep) * hw / 3.0) as usize, 1);
+    let mut piece = vec![(p, false)];
+    for i in 0..=k {
+        let q = p + Vec2::pol(hw, a0 + sweep * i as f32 / k as f32);
+        piece.push((q, i < k));
+    }
+    piece
+}
+
+/// Line through a point of a polyline separating the segments that meet there
+fn joint_cut(d0: Vec2, d1: Vec2) -> Vec2 {
+    let m = d0.perp() + d1.perp();
+    if m.length() > 1e-6 {
+        m
+    } else {
+        d0.perp()
+    }
+}
+
+/// The piece filling the outer side of a turn, the segments are cut at `joint_cut`
+fn join_piece(p: Vec2, d0: Vec2, d1: Vec2, hw: f32, style: &StrokeStyle) -> Option<StrokePiece> {
+    let cross = d0.x * d1.y - d0.y * d1.x;
+    if f32::abs(cross) < 1e-6 && d0.dot(d1) > 0.0 {
+        return None;
+    }
+
+    // normals on the outer side of the turn
+    let s = if cross > 0.0 { -1.0 } else { 1.0 };
+    let n0 = d0.perp() * s;
+    let n1 = d1.perp() * s;
+    let (a, b) = (p + n0 * hw, p + n1 * hw);
+    let bevel = vec![(p, false), (a, true), (b, false)];
+
+    Some(match style.join {
+        LineJoin::Bevel => bevel,
+        LineJoin::Round => {
+            let a0 = n0.y.atan2(n0.x);
+            let mut sweep = n1.y.atan2(n1.x) - a0;
+            while sweep > PI {
+                sweep -= 2.0 * PI;
+            }
+            while sweep < -PI {
+                sweep += 2.0 * PI;
+            }
+            arc_piece(p, a0, sweep, hw)
+        }
+        LineJoin::Miter => {
+            let m = n0 + n1;
+            let cos_half = if m.length() > 1e-6 {
+                m.sgn().dot(n0)
+            } else {
+                0.0
+            };
+            if cos_half <= 0.0 || 1.0 / cos_half > style.miter_limit {
+                bevel
+            } else {
+                let tip = p + m.sgn() * (hw / cos_half);
+                vec![(p, false), (a, true), (tip, true), (b, false)]
+            }
+        }
+    })
+}
+
+/// Adds the pieces of the stroke of a polyline
+///
+/// Segments are cut where they meet and joins and caps only cover what lies beyond, so the
+/// pieces don't overlap and translucent strokes blend once. Only parts of a path crossing
+/// each other, or segments shorter than the stroke is wide, still overlap.
+fn stroke_polyline(
+    tris: &mut ShadedTriangles,
+    pts: Vec<Vec2>,
+    closed: bool,
+    hw: f32,
+    style: &StrokeStyle,
+    clr: Vec4,
+) {
+    let mut pts = dedup_points(pts);
+    let n = pts.len();
+    if n == 0 {
+        return;
+    }
+    if n == 1 {
+        let p = pts[0];
+        match style.cap {
+            LineCap::Round => {
+                let mut disc = arc_piece(p, 0.0, 2.0 * PI, hw);
+                disc.remove(0);
+                add_piece(tris, &disc, clr);
+            }
+            LineCap::Square => {
+                let square: Vec<(Vec2, bool)> = [(-hw, -hw), (hw, -hw), (hw, hw), (-hw, hw)]
+                    .iter()
+                    .map(|&(x, y)| (p + Vec2::new(x, y), true))
+                    .collect();
+                add_piece(tris, &square, clr);
+            }
+            LineCap::Butt => {}
+        }
+        return;
+    }
+
+    if !closed && style.cap == LineCap::Square {
+        let d0 = (pts[0] - pts[1]).sgn();
+        let d1 = (pts[n - 1] - pts[n - 2]).sgn();
+        pts[0] += d0 * hw;
+        pts[n - 1] += d1 * hw;
+    }
+
+    let dirs: Vec<Vec2> = (0..n).map(|i| (pts[(i + 1) % n] - pts[i]).sgn()).collect();
+    let segments = if closed { n } else { n - 1 };
+    let flat_ends = style.cap != LineCap::Round;
+    for i in 0..segments {
+        let j = (i + 1) % n;
+        let (a, b) = (pts[i], pts[j]);
+        let open_start = !closed && i == 0;
+        let open_end = !closed && i == segments - 1;
+        let nrm = dirs[i].perp() * hw;
+        let mut piece = vec![
+            (a - nrm, true),
+            (b - nrm, open_end && flat_ends),
+            (b + nrm, true),
+            (a + nrm, open_start && flat_ends),
+        ];
+        if !open_start {
+            piece = clip_piece(piece, a, joint_cut(dirs[(i + n - 1) % n], dirs[i]), b);
+        }
+        if !open_end {
+            piece = clip_piece(piece, b, joint_cut(dirs[i], dirs[j]), a);
+        }
+        add_piece(tris, &piece, clr);
+    }
+
+    let joints = if closed { 0..n } else { 1..n - 1 };
+    for i in joints {
+        let d0 = dirs[(i + n - 1) % n];
+        if let Some(piece) = join_piece(pts[i], d0, dirs[i], hw, style) {
+            add_piece(tris, &piece, clr);
+        }
+    }
+
+    if !closed && style.cap == LineCap::Round {
+        let ends = [(pts[0], -dirs[0]), (pts[n - 1], dirs[n - 2])];
+        for &(p, d) in ends.iter() {
+            let n = d.perp();
+            add_piece(tris, &arc_piece(p, n.y.atan2(n.x), -PI, hw), clr);
+        }
+    }
+}
+
+pub(super) fn stroke_triangles(
+    path: &Path,
+    style: &StrokeStyle,
+    clr: Vec4,
+    gui_scale: f32,
+) -> ShadedTriangles {
+    let mut tris = ShadedTriangles::default();
+    let hw = style.width * gui_scale / 2.0;
+    if hw <= 0.0 || clr.w == 0.0 {
+        return tris;
+    }
+
+    let dashed = style.dashes.iter().any(|d| *d > 0.0);
+    let pattern: Vec<f32> = style.dashes.iter().map(|d| d * gui_scale).collect();
+    for (pts, closed) in path.polylines(gui_scale) {
+        if dashed && !pts.is_empty() {
+            for dash in dash_polyline(&pts, closed, &pattern, style.dash_offset * gui_scale) {
+                stroke_polyline(&mut tris, dash, false, hw, style, clr);
+            }
+        } else {
+            stroke_polyline(&mut tris, pts, closed, hw, style, clr);
+        }
+    }
+    tris
+}
+
+struct FillEdge {
+    a: Vec2,
+    b: Vec2,
+    winding: i32,
+}
+
+impl FillEdge {
+    fn x_at(&self, y: f32) -> f32 {
+        self.a.x + (self.b.x - self.a.x) * (y - self.a.y) / (self.b.y - self.a.y)
+    }
+    fn intersection_y(&self, other: &FillEdge) -> Option<f32> {
+        let r = self.b - self.a;
+        let s = other.b - other.a;
+        let denom = r.x * s.y - r.y * s.x;
+        if f32::abs(denom) < 1e-9 {
+            return None;
+        }
+        let q = other.a - self.a;
+        let t = (q.x * s.y - q.y * s.x) / denom;
+        let u = (q.x * r.y - q.y * r.x) / denom;
+        if t > 0.0 && t < 1.0 && u > 0.0 && u < 1.0 {
+            Some(self.a.y + r.y * t)
+        } else {
+            None
+        }
+    }
+}
+
+/// Parts of the sorted spans `a` not covered by the sorted spans `b`
+fn span_difference(a: &[(f32, f32)], b: &[(f32, f32)]) -> Vec<(f32, f32)> {
+    let mut out = vec![];
+    for &(l, r) in a {
+        let mut l = l;
+        for &(bl, br) in b {
+            if br <= l {
+                continue;
+            }
+            if bl >= r {
+                break;
+            }
+            if bl > l {
+                out.push((l, bl));
+            }
+            l = f32::max(l, br);
+        }
+        if l < r {
+            out.push((l, r));
+        }
+    }
+    out.retain(|&(l, r)| r - l > 1e-3);
+    out
+}
+
+/// Fades out the horizontal parts of the outline at `y`, where the spans above and below differ
+fn add_horizontal_fringes<F>(
+    tris: &mut ShadedTriangles,
+    above: &[(f32, f32)],
+    below: &[(f32, f32)],
+    y: f32,
+    color_at: &F,
+) where
+    F: Fn(Vec2) -> Vec4,
+{
+    let sides = [
+        (span_difference(below, above), Vec2::new(0.0, -1.0)),
+        (span_difference(above, below), Vec2::new(0.0, 1.0)),
+    ];
+    for (spans, nrm) in sides.iter() {
+        for &(l, r) in spans {
+            let (a, b) = (Vec2::new(l, y), Vec2::new(r, y));
+            tris.fringe(a, b, *nrm, color_at((a + b) / 2.0));
+        }
+    }
+}
+
+/// Triangulates the path by cutting it into horizontal slabs at every vertex and crossing,
+/// inside a slab the edges never cross so the filled spans are plain trapezoids
+pub(super) fn fill_triangles(
+    path: &Path,
+    rule: FillRule,
+    fill: &DrawFill,
+    gui_scale: f32,
+) -> ShadedTriangles {
+    let mut edges = vec![];
+    for (pts, _) in path.polylines(gui_scale) {
+        let n = pts.len();
+        if n < 3 {
+            continue;
+        }
+        for i in 0..n {
+            let (p, q) = (pts[i], pts[(i + 1) % n]);
+            if p.y < q.y {
+                edges.push(FillEdge {
+                    a: p,
+                    b: q,
+                    winding: 1,
+                });
+            } else if p.y > q.y {
+                edges.push(FillEdge {
+                    a: q,
+                    b: p,
+                    winding: -1,
+                });
+            }
+        }
+    }
+    edges.sort_by(|e, f| e.a.y.partial_cmp(&f.a.y).unwrap());
+
+    let mut ys: Vec<f32> = edges.iter().map(|e| e.a.y).collect();
+    ys.extend(edges.iter().map(|e| e.b.y));
+    // sorted by their tops, the edges an edge can cross start above its bottom
+    for i in 0..edges.len() {
+        for j in i + 1..edges.len() {
+            if edges[j].a.y >= edges[i].b.y {
+                break;
+            }
+            if let Some(y) = edges[i].intersection_y(&edges[j]) {
+                ys.push(y);
+            }
+        }
+    }
+    ys.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    ys.dedup_by(|a, b| f32::abs(*a - *b) < 1e-4);
+
+    let mut tris = ShadedTriangles::default();
+    let color_at = |p: Vec2| fill.color_at(p, gui_scale);
+    let clr = |p: Vec2| (p, color_at(p));
+    let mut next = 0;
+    let mut active: Vec<&FillEdge> = vec![];
+    // spans of the slab above at its bottom
+    let mut above: Vec<(f32, f32)> = vec![];
+    for w in ys.windows(2) {
+        let (y0, y1) = (w[0], w[1]);
+        let ym = (y0 + y1) / 2.0;
+        while next < edges.len() && edges[next].a.y <= ym {
+            active.push(&edges[next]);
+            next += 1;
+        }
+        active.retain(|e| e.b.y >= ym);
+        let mut crossing: Vec<(&FillEdge, f32)> = active.iter().map(|&e| (e, e.x_at(ym))).collect();
+        crossing.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+
+        let mut tops = vec![];
+        let mut bottoms = vec![];
+        let mut winding = 0;
+        let mut span_start = None;
+        for &(edge, _) in &crossing {
+            let was_inside = rule.inside(winding);
+            winding += edge.winding;
+            let inside = rule.inside(winding);
+
+            if !was_inside && inside {
+                span_start = Some(edge);
+            } else if was_inside && !inside {
+                if let Some(left) = span_start.take() {
+                    let l0 = Vec2::new(left.x_at(y0), y0);
+                    let l1 = Vec2::new(left.x_at(y1), y1);
+                    let r0 = Vec2::new(edge.x_at(y0), y0);
+                    let r1 = Vec2::new(edge.x_at(y1), y1);
+                    tris.quad(clr(l0), clr(r0), clr(r1), clr(l1));
+                    tops.push((l0.x, r0.x));
+                    bottoms.push((l1.x, r1.x));
+
+                    let nl = (l1 - l0).perp().sgn();
+                    let nr = (r1 - r0).perp().sgn();
+                    let nl = if nl.x > 0.0 { -nl } else { nl };
+                    let nr = if nr.x < 0.0 { -nr } else { nr };
+                    tris.fringe(l0, l1, nl, color_at((l0 + l1) / 2.0));
+                    tris.fringe(r0, r1, nr, color_at((r0 + r1) / 2.0));
+                }
+            }
+        }
+        add_horizontal_fringes(&mut tris, &above, &tops, y0, &color_at);
+        above = bottoms;
+    }
+    if let Some(&y) = ys.last() {
+        add_horizontal_fringes(&mut tris, &above, &[], y, &color_at);
+    }
+    tris
+}
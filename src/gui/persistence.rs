@@ -0,0 +1,168 @@
+#![cfg(feature = "serializable")]
+extern crate serde;
+extern crate serde_json;
+
+use self::serde::de::DeserializeOwned;
+use self::serde::Serialize;
+use self::serde_json::Value;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tools::serde_tools::SerdeError;
+
+/// Where and how often `GuiContext::new_persistent` stores the gui data as JSON
+///
+/// Loading tolerates other versions of the data: fields unknown to it are ignored, fields
+/// missing from the file or no longer fitting their type keep the value given to the context.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GuiPersistence {
+    pub path: PathBuf,
+    /// Saves this often while the data differs from the file, None to save on detach only
+    pub autosave: Option<Duration>,
+    /// Written along the data, to tell files of older layouts apart
+    pub version: u32,
+}
+
+impl GuiPersistence {
+    pub fn new<P: AsRef<Path>>(path: P) -> GuiPersistence {
+        GuiPersistence {
+            path: path.as_ref().to_owned(),
+            autosave: Some(Duration::from_secs(30)),
+            version: 0,
+        }
+    }
+    pub fn autosave(mut self, interval: Option<Duration>) -> GuiPersistence {
+        self.autosave = interval;
+        self
+    }
+    pub fn version(mut self, version: u32) -> GuiPersistence {
+        self.version = version;
+        self
+    }
+
+    pub fn save<D: Serialize>(&self, data: &D) -> Result<(), SerdeError> {
+        let document = serde_json::json!({
+            "version": self.version,
+            "data": data,
+        });
+        let mut file = File::create(&self.path)?;
+        write!(file, "{}", serde_json::to_string_pretty(&document)?)?;
+        Ok(())
+    }
+    /// `initial` with every field stored in the file that still fits
+    pub fn load<D>(&self, initial: &D) -> Result<D, SerdeError>
+    where
+        D: Serialize + DeserializeOwned,
+    {
+        let document: Value = serde_json::from_str(&std::fs::read_to_string(&self.path)?)?;
+        let stored = document.get("data").cloned().unwrap_or(Value::Null);
+        Ok(merged(initial, stored)?)
+    }
+    /// Version the file was written with
+    pub fn stored_version(&self) -> Option<u32> {
+        let document: Value =
+            serde_json::from_str(&std::fs::read_to_string(&self.path).ok()?).ok()?;
+        document.get("version")?.as_u64().map(|v| v as u32)
+    }
+}
+
+/// Takes the stored fields one by one, each is kept only if the data still deserializes
+fn merged<D>(initial: &D, stored: Value) -> Result<D, serde_json::Error>
+where
+    D: Serialize + DeserializeOwned,
+{
+    let mut data = serde_json::to_value(initial)?;
+    let fields = match stored {
+        Value::Object(fields) => fields,
+        stored => return serde_json::from_value(stored).or_else(|_| serde_json::from_value(data)),
+    };
+    for (key, value) in fields {
+        let candidates = match data.get(&key) {
+            Some(field) => {
+                let mut field = field.clone();
+                merge(&mut field, value.clone());
+                vec![field, value]
+            }
+            None => vec![value],
+        };
+        for candidate in candidates {
+            let mut attempt = data.clone();
+            attempt[key.as_str()] = candidate;
+            if serde_json::from_value::<D>(attempt.clone()).is_ok() {
+                data = attempt;
+                break;
+            }
+        }
+    }
+    serde_json::from_value(data)
+}
+
+/// Stored objects fill into the initial ones, so nested fields missing in the file survive
+fn merge(base: &mut Value, stored: Value) {
+    match (base, stored) {
+        (Value::Object(base), Value::Object(stored)) => {
+            for (key, value) in stored {
+                match base.get_mut(&key) {
+                    Some(field) => merge(field, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, stored) => *base = stored,
+    }
+}
+
+/// Saving side of the persistence held by the context, the data type is erased to a function
+pub(super) struct AutoSave<D> {
+    persistence: GuiPersistence,
+    save: fn(&GuiPersistence, &D) -> Result<(), SerdeError>,
+    saved: Option<D>,
+    last_save: Instant,
+}
+
+impl<D> AutoSave<D>
+where
+    D: Clone + PartialEq,
+{
+    /// `saved` is the data found in the file, if any
+    pub(super) fn new(persistence: GuiPersistence, saved: Option<D>) -> AutoSave<D>
+    where
+        D: Serialize,
+    {
+        AutoSave {
+            persistence,
+            save: GuiPersistence::save::<D>,
+            saved,
+            last_save: Instant::now(),
+        }
+    }
+
+    pub(super) fn persistence(&self) -> &GuiPersistence {
+        &self.persistence
+    }
+
+    pub(super) fn update(&mut self, data: &D) {
+        match self.persistence.autosave {
+            Some(interval) if self.last_save.elapsed() >= interval => self.save(data),
+            _ => {}
+        }
+    }
+    /// Writes the file unless it already holds `data`
+    pub(super) fn save(&mut self, data: &D) {
+        self.last_save = Instant::now();
+        if self.saved.as_ref() == Some(data) {
+            return;
+        }
+        match (self.save)(&self.persistence, data) {
+            Ok(()) => self.saved = Some(data.clone()),
+            Err(e) => eprintln!(
+                "Could not save gui data to {}: {:?}",
+                self.persistence.path.display(),
+                e
+            ),
+        }
+    }
+}
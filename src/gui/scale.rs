@@ -0,0 +1,27 @@
+use mecs::Message;
+
+/// How a gui maps units to pixels
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GuiScale {
+    /// Follow the scale factor of the monitor showing the window
+    Window,
+    /// The monitor's scale factor multiplied, for accessibility zoom
+    Zoom(f32),
+    /// Pixels per unit regardless of the monitor
+    Fixed(f32),
+}
+
+impl GuiScale {
+    pub fn resolve(self, window_scale: f32) -> f32 {
+        match self {
+            GuiScale::Window => window_scale,
+            GuiScale::Zoom(zoom) => window_scale * zoom,
+            GuiScale::Fixed(scale) => scale,
+        }
+    }
+}
+
+/// Changes the scale of the gui contexts receiving it, usually broadcast from a callback
+#[derive(Debug)]
+pub struct SetGuiScale(pub GuiScale);
+impl Message for SetGuiScale {}
@@ -0,0 +1,76 @@
+use std::fmt;
+
+use mecs::{GlutinKey, StaticWorld};
+
+/// Key combination like Ctrl+S
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Shortcut {
+    pub key: GlutinKey,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl Shortcut {
+    pub fn new(key: GlutinKey) -> Shortcut {
+        Shortcut {
+            key,
+            ctrl: false,
+            shift: false,
+            alt: false,
+        }
+    }
+    pub fn ctrl(key: GlutinKey) -> Shortcut {
+        Shortcut {
+            ctrl: true,
+            ..Shortcut::new(key)
+        }
+    }
+    pub fn shift(mut self) -> Shortcut {
+        self.shift = true;
+        self
+    }
+    pub fn alt(mut self) -> Shortcut {
+        self.alt = true;
+        self
+    }
+
+    /// `key` with the modifiers currently held
+    pub fn pressed(key: GlutinKey, world: &StaticWorld) -> Shortcut {
+        Shortcut {
+            key,
+            ctrl: world.is_ctrl_pressed(),
+            shift: world.is_shift_pressed(),
+            alt: world.is_alt_pressed(),
+        }
+    }
+
+    /// Whether pressing `key` with the modifiers currently held triggers the shortcut
+    pub fn matches(&self, key: GlutinKey, world: &StaticWorld) -> bool {
+        self.key == key
+            && self.ctrl == world.is_ctrl_pressed()
+            && self.shift == world.is_shift_pressed()
+            && self.alt == world.is_alt_pressed()
+    }
+}
+
+impl fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        let key = format!("{:?}", self.key);
+        match key.as_str() {
+            k if k.len() == 4 && k.starts_with("Key") => write!(f, "{}", &k[3..]),
+            "Return" => write!(f, "Enter"),
+            "Back" => write!(f, "Backspace"),
+            k => write!(f, "{}", k),
+        }
+    }
+}
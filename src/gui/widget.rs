@@ -1,18 +1,45 @@
 extern crate downcast_rs;
 
+use std::ops::{Add, Mul, Sub};
+
+use mecs::{GlutinButton, GlutinKey};
 use tools::*;
 
 use super::CallbackExecutor;
+use super::DragPayload;
 use super::DrawBuilder;
+use super::WidgetEvent;
 
 use self::downcast_rs::impl_downcast;
 use self::downcast_rs::Downcast;
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+/// Units per em of the default font size, the size of `FontSize::Em(1.0)` and the em of widgets
+/// whose parents set no other
+pub const DEFAULT_EM: f32 = 20.0;
+
+/// Length along one axis of a widget
+///
+/// Dimensions combine with `+`, `-` and `*`, like `Relative(0.5) - Units(20.0)`, and are bounded
+/// by `min`, `max` and `clamp`.
+#[derive(Debug, Clone, PartialEq)]
 pub enum GuiDimension {
     Default,
     Relative(f32),
     Units(f32),
+    /// Multiples of the font size the widget gets in `WidgetConstraints::em`
+    Em(f32),
+    /// Fraction of the window width
+    ViewportWidth(f32),
+    /// Fraction of the window height
+    ViewportHeight(f32),
+    /// Multiple of the size along the other axis, e.g. a height following the width
+    Aspect(f32),
+    Min(Box<GuiDimension>, Box<GuiDimension>),
+    Max(Box<GuiDimension>, Box<GuiDimension>),
+    /// The first dimension kept between the other two
+    Clamp(Box<GuiDimension>, Box<GuiDimension>, Box<GuiDimension>),
+    Sum(Box<GuiDimension>, Box<GuiDimension>),
+    Scale(f32, Box<GuiDimension>),
 }
 
 impl Default for GuiDimension {
@@ -21,49 +48,208 @@ impl Default for GuiDimension {
     }
 }
 
+/// What a `GuiDimension` gets resolved against
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DimensionContext {
+    /// Size of the container along the axis, what `Relative` multiplies
+    pub container: f32,
+    /// Size along the other axis, what `Aspect` multiplies
+    pub cross: f32,
+    pub viewport: Vec2px,
+    /// Units per em, what `Em` multiplies
+    pub em: f32,
+}
+
+impl DimensionContext {
+    /// Along the x axis of `container`, or the y axis if `vertical`, with the viewport and em
+    /// of `constraints`
+    pub fn along(
+        container: Vec2px,
+        vertical: bool,
+        constraints: &WidgetConstraints,
+    ) -> DimensionContext {
+        let (container, cross) = if vertical {
+            (container.y, container.x)
+        } else {
+            (container.x, container.y)
+        };
+        DimensionContext {
+            container,
+            cross,
+            viewport: constraints.viewport,
+            em: constraints.em(),
+        }
+    }
+}
+
 impl GuiDimension {
-    pub fn to_units(self, container_dimension: f32) -> f32 {
+    /// Units of a dimension that depends on neither the window nor the other axis, `Em` counts
+    /// as `DEFAULT_EM`
+    pub fn to_units(&self, container_dimension: f32) -> f32 {
+        self.resolve(&DimensionContext {
+            container: container_dimension,
+            cross: container_dimension,
+            viewport: Vec2px::zero(),
+            em: DEFAULT_EM,
+        })
+    }
+    pub fn resolve(&self, ctx: &DimensionContext) -> f32 {
+        match self {
+            GuiDimension::Default => ctx.container,
+            GuiDimension::Relative(r) => ctx.container * r,
+            GuiDimension::Units(s) => *s,
+            GuiDimension::Em(e) => e * ctx.em,
+            GuiDimension::ViewportWidth(r) => ctx.viewport.x * r,
+            GuiDimension::ViewportHeight(r) => ctx.viewport.y * r,
+            GuiDimension::Aspect(r) => ctx.cross * r,
+            GuiDimension::Min(a, b) => f32::min(a.resolve(ctx), b.resolve(ctx)),
+            GuiDimension::Max(a, b) => f32::max(a.resolve(ctx), b.resolve(ctx)),
+            GuiDimension::Clamp(d, mn, mx) => {
+                f32::max(f32::min(d.resolve(ctx), mx.resolve(ctx)), mn.resolve(ctx))
+            }
+            GuiDimension::Sum(a, b) => a.resolve(ctx) + b.resolve(ctx),
+            GuiDimension::Scale(f, d) => f * d.resolve(ctx),
+        }
+    }
+    /// Whether the units grow in proportion to the container, which lets layouts split space
+    /// without searching
+    pub fn is_linear(&self) -> bool {
         match self {
-            GuiDimension::Units(s) => s,
-            GuiDimension::Relative(r) => container_dimension * r,
-            _ => container_dimension,
+            GuiDimension::Min(..) | GuiDimension::Max(..) | GuiDimension::Clamp(..) => false,
+            GuiDimension::Sum(a, b) => a.is_linear() && b.is_linear(),
+            GuiDimension::Scale(_, d) => d.is_linear(),
+            _ => true,
         }
     }
-    pub fn relative(self) -> f32 {
+    /// Share of the container in a linear dimension
+    pub fn relative(&self) -> f32 {
         match self {
-            GuiDimension::Units(_) => 0.0,
-            GuiDimension::Relative(r) => r,
+            GuiDimension::Relative(r) => *r,
             GuiDimension::Default => 1.0,
+            GuiDimension::Sum(a, b) => a.relative() + b.relative(),
+            GuiDimension::Scale(f, d) => f * d.relative(),
+            _ => 0.0,
         }
     }
-    pub fn absolute(self) -> f32 {
+    /// Units of a linear dimension besides its share of the container, `Em` counts as
+    /// `DEFAULT_EM`
+    pub fn absolute(&self) -> f32 {
         match self {
-            GuiDimension::Units(s) => s,
+            GuiDimension::Units(s) => *s,
+            GuiDimension::Em(e) => e * DEFAULT_EM,
+            GuiDimension::Sum(a, b) => a.absolute() + b.absolute(),
+            GuiDimension::Scale(f, d) => f * d.absolute(),
             _ => 0.0,
         }
     }
+    fn follows_cross(&self) -> bool {
+        match self {
+            GuiDimension::Aspect(_) => true,
+            GuiDimension::Min(a, b) | GuiDimension::Max(a, b) | GuiDimension::Sum(a, b) => {
+                a.follows_cross() || b.follows_cross()
+            }
+            GuiDimension::Clamp(d, mn, mx) => {
+                d.follows_cross() || mn.follows_cross() || mx.follows_cross()
+            }
+            GuiDimension::Scale(_, d) => d.follows_cross(),
+            _ => false,
+        }
+    }
     pub fn relative_array(array: Vec<f32>) -> Vec<GuiDimension> {
         array.iter().map(|f| GuiDimension::Relative(*f)).collect()
     }
+
+    pub fn min(self, other: GuiDimension) -> GuiDimension {
+        GuiDimension::Min(Box::new(self), Box::new(other))
+    }
+    pub fn max(self, other: GuiDimension) -> GuiDimension {
+        GuiDimension::Max(Box::new(self), Box::new(other))
+    }
+    pub fn clamp(self, min: GuiDimension, max: GuiDimension) -> GuiDimension {
+        GuiDimension::Clamp(Box::new(self), Box::new(min), Box::new(max))
+    }
 }
 
-#[derive(Copy, Clone)]
-pub struct WidgetSize {
-    pub x: GuiDimension,
-    pub y: GuiDimension,
+impl Add for GuiDimension {
+    type Output = GuiDimension;
+    fn add(self, other: GuiDimension) -> GuiDimension {
+        GuiDimension::Sum(Box::new(self), Box::new(other))
+    }
 }
 
-pub type GuiPoint = WidgetSize;
+impl Sub for GuiDimension {
+    type Output = GuiDimension;
+    fn sub(self, other: GuiDimension) -> GuiDimension {
+        self + other * -1.0
+    }
+}
 
-impl Default for WidgetSize {
-    fn default() -> WidgetSize {
-        WidgetSize {
-            x: Default::default(),
-            y: Default::default(),
+impl Mul<f32> for GuiDimension {
+    type Output = GuiDimension;
+    fn mul(self, factor: f32) -> GuiDimension {
+        GuiDimension::Scale(factor, Box::new(self))
+    }
+}
+
+/// Finds the size of a relative share so that `dims` add up to `total`
+///
+/// Linear dimensions are solved directly, bounded ones by bisection. None if the sum does not
+/// depend on the share, bounds that never reach `total` get a share large enough to saturate
+/// them. `cross` and the viewport and em of `constraints` resolve the parts not depending on
+/// the share.
+pub fn solve_shares(
+    dims: &[GuiDimension],
+    total: f32,
+    cross: f32,
+    constraints: &WidgetConstraints,
+) -> Option<f32> {
+    let sum = |share: f32| -> f32 {
+        let ctx = DimensionContext {
+            container: share,
+            cross,
+            viewport: constraints.viewport,
+            em: constraints.em(),
+        };
+        dims.iter().map(|d| d.resolve(&ctx)).sum()
+    };
+    let fixed = sum(0.0);
+    if dims.iter().all(|d| d.is_linear()) {
+        let per_share = sum(1.0) - fixed;
+        return if per_share == 0.0 {
+            None
+        } else {
+            Some((total - fixed) / per_share)
+        };
+    }
+
+    let mut high = total.abs().max(1.0);
+    // a share growing without the sum growing means the bounds hold everything
+    if sum(high * 1024.0) == fixed {
+        return None;
+    }
+    while sum(high) < total && high < 1e9 {
+        high *= 2.0;
+    }
+    let mut low = 0.0;
+    for _ in 0..40 {
+        let mid = (low + high) / 2.0;
+        if sum(mid) < total {
+            low = mid;
+        } else {
+            high = mid;
         }
     }
+    Some(high)
+}
+
+#[derive(Clone, Default)]
+pub struct WidgetSize {
+    pub x: GuiDimension,
+    pub y: GuiDimension,
 }
 
+pub type GuiPoint = WidgetSize;
+
 impl From<Vec2px> for WidgetSize {
     fn from(s: Vec2px) -> Self {
         WidgetSize {
@@ -75,10 +261,29 @@ impl From<Vec2px> for WidgetSize {
 
 impl WidgetSize {
     pub fn to_units(&self, container_size: Vec2px) -> Vec2px {
-        Vec2px::new(
-            self.x.to_units(container_size.x),
-            self.y.to_units(container_size.y),
-        )
+        self.resolve_in(container_size, &Default::default())
+    }
+    /// Size within the space the constraints give
+    pub fn resolve(&self, constraints: &WidgetConstraints) -> Vec2px {
+        self.resolve_in(constraints.max_size, constraints)
+    }
+    /// An `Aspect` axis follows the resolved other one, or the container if both are
+    pub fn resolve_in(&self, container_size: Vec2px, constraints: &WidgetConstraints) -> Vec2px {
+        let ctx_x = DimensionContext::along(container_size, false, constraints);
+        let ctx_y = DimensionContext::along(container_size, true, constraints);
+        match (self.x.follows_cross(), self.y.follows_cross()) {
+            (false, true) => {
+                let x = self.x.resolve(&ctx_x);
+                let y = self.y.resolve(&DimensionContext { cross: x, ..ctx_y });
+                Vec2px::new(x, y)
+            }
+            (true, false) => {
+                let y = self.y.resolve(&ctx_y);
+                let x = self.x.resolve(&DimensionContext { cross: y, ..ctx_x });
+                Vec2px::new(x, y)
+            }
+            _ => Vec2px::new(self.x.resolve(&ctx_x), self.y.resolve(&ctx_y)),
+        }
     }
     pub fn from_units(units: Vec2) -> Self {
         Self {
@@ -103,9 +308,22 @@ impl WidgetSize {
 #[derive(Copy, Clone, Default, Debug)]
 pub struct WidgetConstraints {
     pub max_size: Vec2px,
+    /// Logical size of the window, set by the layout
+    pub viewport: Vec2px,
+    /// Units per em, inherited from the parent by the layout unless its `child_constraint`
+    /// sets one
+    pub em: Option<f32>,
+}
+
+impl WidgetConstraints {
+    /// Units per em, `DEFAULT_EM` when nothing set one
+    pub fn em(&self) -> f32 {
+        self.em.unwrap_or(DEFAULT_EM)
+    }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq)]
+/// Ordered from weakest to strongest, `max` of two responses asks for both effects
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum EventResponse {
     Pass,
     Handled,
@@ -143,9 +361,46 @@ pub trait Widget: Downcast {
     fn place_child(&mut self, _child_size: Vec2px, _child_descent: f32) -> WidgetPosition {
         Vec2px::zero().into()
     }
+    /// Depths replacing those given by `place_child` once every child is placed, so a child can
+    /// be stacked above siblings that come later; `descents[i]` is the depth the subtree of
+    /// child `i` spans
+    fn child_depths(&self, _descents: &[f32]) -> Option<Vec<f32>> {
+        None
+    }
     fn child_constraint(&self) -> Option<WidgetConstraints> {
         None
     }
+    /// Whether what the descendants draw is cut to the rect of this widget
+    fn clips_children(&self) -> bool {
+        false
+    }
+    /// Hidden children are neither laid out, drawn nor reached by events
+    fn is_child_shown(&self, _child_index: usize) -> bool {
+        true
+    }
+    /// Local area drawn above everything else, like an open dropdown, that gets the cursor first
+    fn popup_area(&self) -> Option<Rect> {
+        None
+    }
+    /// Widgets that change over time keep frames coming when rendering on demand
+    fn is_animating(&self) -> bool {
+        false
+    }
+    /// Runs after each layout, for callbacks reporting what only the layout tells, like sizes
+    fn on_layout(&mut self, _executor: &mut CallbackExecutor) {}
+
+    /// Sees pointer and key events on their way to the target and back, before the typed handlers
+    fn on_event(
+        &mut self,
+        _event: &mut WidgetEvent,
+        _executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        EventResponse::Pass
+    }
+    /// An ancestor grabbed the pointer during a press of this widget, no release will follow
+    fn on_pointer_lost(&mut self, _executor: &mut CallbackExecutor) -> EventResponse {
+        EventResponse::Pass
+    }
 
     fn on_press(
         &mut self,
@@ -157,6 +412,15 @@ pub trait Widget: Downcast {
     fn on_release(&mut self, _executor: &mut CallbackExecutor) -> EventResponse {
         EventResponse::Pass
     }
+    /// Press of any mouse button other than the left one
+    fn on_button_press(
+        &mut self,
+        _button: GlutinButton,
+        _local_cursor_pos: Vec2px,
+        _executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        EventResponse::Pass
+    }
     fn on_cursor_enter(&mut self, _executor: &mut CallbackExecutor) -> EventResponse {
         EventResponse::Pass
     }
@@ -170,8 +434,75 @@ pub trait Widget: Downcast {
     ) -> EventResponse {
         EventResponse::Pass
     }
+    fn on_scroll(
+        &mut self,
+        _local_cursor_pos: Vec2px,
+        _delta: Vec2,
+        _executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        EventResponse::Pass
+    }
+    fn on_key_press(&mut self, _key: GlutinKey, _executor: &mut CallbackExecutor) -> EventResponse {
+        EventResponse::Pass
+    }
+    fn on_char(&mut self, _ch: char, _executor: &mut CallbackExecutor) -> EventResponse {
+        EventResponse::Pass
+    }
+    fn on_focus_lost(&mut self, _executor: &mut CallbackExecutor) -> EventResponse {
+        EventResponse::Pass
+    }
+    /// Touch gesture around the widget, bubbling until handled, positions are local
+    fn on_gesture(
+        &mut self,
+        _gesture: &Gesture,
+        _executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        EventResponse::Pass
+    }
+    /// Key press no focused widget took, offered to every shown widget so shortcuts work anywhere
+    fn on_accelerator(
+        &mut self,
+        _key: GlutinKey,
+        _executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        EventResponse::Pass
+    }
+    /// Dragged payload reached the widget, returning true makes it the drop target
+    fn on_drag_enter(&mut self, _payload: &DragPayload, _executor: &mut CallbackExecutor) -> bool {
+        false
+    }
+    fn on_drag_over(
+        &mut self,
+        _local_cursor_pos: Vec2px,
+        _payload: &DragPayload,
+        _executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        EventResponse::Pass
+    }
+    fn on_drag_leave(&mut self, _executor: &mut CallbackExecutor) -> EventResponse {
+        EventResponse::Pass
+    }
+    /// Payload released over the accepting widget, anything but Pass counts as taken
+    fn on_drop(
+        &mut self,
+        _local_cursor_pos: Vec2px,
+        _payload: &DragPayload,
+        _executor: &mut CallbackExecutor,
+    ) -> EventResponse {
+        EventResponse::Pass
+    }
+    /// A drag this widget started is over, `dropped` tells whether a target took the payload
+    fn on_drag_end(&mut self, _dropped: bool, _executor: &mut CallbackExecutor) -> EventResponse {
+        EventResponse::Pass
+    }
+    /// Takes over interaction state from the widget this one replaces when the gui is rebuilt
+    fn adopt_state(&mut self, _previous: &dyn Widget) {}
     fn on_draw_build(&self, _builder: &mut DrawBuilder) {}
     fn size(&self) -> Vec2px;
+    /// Shown by the inspector
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
 }
 
 impl_downcast!(Widget);
@@ -7,6 +7,7 @@ pub struct WidgetLayoutBuilder {
     pub widget_graph: Vec<Vec<usize>>,
     pub constraints: Vec<WidgetConstraints>,
     pub positions: Vec<WidgetPosition>,
+    pub shown: Vec<bool>,
     pub max_descent: Vec<f32>,
     root_descent: f32,
     next_child_constraints: Vec<WidgetConstraints>,
@@ -25,6 +26,7 @@ impl WidgetLayoutBuilder {
             widget_graph,
             constraints: vec![],
             positions: vec![],
+            shown: vec![],
             next_child_constraints: vec![],
             root_descent: 0.015,
             win_size: Vec2px::zero(),
@@ -35,10 +37,16 @@ impl WidgetLayoutBuilder {
     fn set_widget_count(&mut self, n: usize) {
         self.constraints.resize(n, Default::default());
         self.positions.resize(n, Default::default());
+        self.shown.resize(n, true);
         self.max_descent.resize(n, Default::default());
         self.next_child_constraints.resize(n, Default::default());
     }
     fn pop(&mut self, id: usize, parent: Option<usize>) {
+        if !self.shown[id] {
+            self.positions[id] = WidgetPosition::new(Vec2px::origin(), 0.0);
+            return;
+        }
+        self.restack_children(id);
         self.positions[id] = match parent {
             Some(parid) => {
                 let s = self.widgets[id].size();
@@ -57,7 +65,31 @@ impl WidgetLayoutBuilder {
         };
         // println!("Widget id {}, size: {:?} has been put to {:?}", id, self.widgets[id].size(), self.positions[id]);
     }
+    fn restack_children(&mut self, id: usize) {
+        let children = self.widget_graph[id].clone();
+        let descents: Vec<f32> = children.iter().map(|&c| self.max_descent[c]).collect();
+        if let Some(depths) = self.widgets[id].child_depths(&descents) {
+            let mut max_descent = 0.0;
+            for (&c, depth) in children.iter().zip(depths) {
+                if self.shown[c] {
+                    self.positions[c].depth = depth;
+                    max_descent = f32::max(self.max_descent[c] + depth, max_descent);
+                }
+            }
+            self.max_descent[id] = max_descent;
+        }
+    }
     fn push(&mut self, id: usize, parent: Option<usize>) {
+        if let Some(parid) = parent {
+            let index = self.widget_graph[parid]
+                .iter()
+                .position(|&c| c == id)
+                .unwrap();
+            self.shown[id] = self.shown[parid] && self.widgets[parid].is_child_shown(index);
+            if !self.shown[id] {
+                return;
+            }
+        }
         match parent {
             Some(parid) => {
                 let c = self.widgets[parid].child_constraint();
@@ -72,9 +104,17 @@ impl WidgetLayoutBuilder {
             None => {
                 self.constraints[id] = WidgetConstraints {
                     max_size: self.win_size,
+                    ..Default::default()
                 };
             }
         }
+        self.constraints[id].viewport = self.win_size;
+        if self.constraints[id].em.is_none() {
+            self.constraints[id].em = Some(match parent {
+                Some(parid) => self.constraints[parid].em(),
+                None => DEFAULT_EM,
+            });
+        }
 
         self.widgets[id].constraint(self.constraints[id]);
         self.next_child_constraints[id] = match self.widgets[id].child_constraint() {
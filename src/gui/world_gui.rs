@@ -0,0 +1,298 @@
+use std::time::Duration;
+
+use graphics::*;
+use gui::{GuiBuilder, GuiContext};
+use mecs::*;
+use tools::*;
+
+type OcclusionTest = dyn Fn(Vec3, Vec3, &StaticWorld) -> bool;
+
+/// A gui rendered into a texture shown on a quad in the 3d scene
+///
+/// The quad spans the unit square of its local xy plane, `transform` places it in the world.
+/// Mouse input is routed to the gui by casting the cursor ray of the camera onto the quad.
+/// The ray is not tested against the rest of the scene, so a quad hidden behind other geometry
+/// still takes the clicks unless an occlusion test is given with `set_occlusion_test`.
+pub struct WorldGui<D>
+where
+    D: GuiBuilder + 'static,
+{
+    context: GuiContext<D>,
+    texture: RgbaTexture,
+    framebuffer: Framebuffer,
+    camera: Entity,
+    quad: Entity,
+    transform: Mat4,
+    window_size: Vec2,
+    cursor_inside: bool,
+    pressed: bool,
+    occlusion_test: Option<Box<OcclusionTest>>,
+    pub background: Vec4,
+}
+
+impl<D> System for WorldGui<D>
+where
+    D: GuiBuilder + 'static,
+{
+    fn receive(&mut self, msg: &Box<dyn Message>, world: &mut StaticWorld) {
+        self.context.receive(msg, world);
+    }
+
+    fn update(&mut self, delta_time: Duration, world: &mut StaticWorld) {
+        self.context.update(delta_time, world);
+    }
+
+    fn render(&mut self, world: &mut StaticWorld) {
+        self.framebuffer.bind();
+        unsafe {
+            let clr = self.background;
+            gl::ClearColor(clr.x, clr.y, clr.z, clr.w);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+        }
+        self.context.render(world);
+        Framebuffer::bind_def_framebuffer(Some(self.window_size));
+    }
+
+    fn needs_redraw(&self, world: &StaticWorld) -> bool {
+        self.context.needs_redraw(world)
+    }
+
+    fn window_event(&mut self, event: &GlutinWindowEvent, world: &mut StaticWorld) -> bool {
+        match event {
+            GlutinWindowEvent::Resized(size) => {
+                self.window_size = size.into();
+                false
+            }
+            GlutinWindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                self.window_size = (**new_inner_size).into();
+                false
+            }
+            GlutinWindowEvent::CursorMoved { position, .. } => {
+                self.cursor_moved(position.into(), world)
+            }
+            GlutinWindowEvent::CursorLeft { .. } => self.cursor_left(world),
+            GlutinWindowEvent::MouseInput { state, .. } => {
+                let pressed = *state == glutin::event::ElementState::Pressed;
+                let releasing = self.pressed && !pressed;
+                if !self.cursor_inside && !releasing {
+                    return false;
+                }
+                self.pressed = pressed;
+                self.context.window_event(event, world)
+            }
+            GlutinWindowEvent::MouseWheel { .. } => {
+                self.cursor_inside && self.context.window_event(event, world)
+            }
+            GlutinWindowEvent::KeyboardInput { .. } | GlutinWindowEvent::ReceivedCharacter(_) => {
+                self.context.window_event(event, world)
+            }
+            _ => false,
+        }
+    }
+
+    fn detach(&mut self, world: &mut StaticWorld) {
+        self.context.detach(world);
+        world.delete_entity(self.quad);
+    }
+}
+
+impl<D> WorldGui<D>
+where
+    D: GuiBuilder + 'static,
+{
+    /// Gui of `size` pixels seen through the camera held by `camera`, see `DrawSystem::camera_entity`
+    pub fn new(
+        world: &mut World,
+        camera: Entity,
+        size: (usize, usize),
+        transform: Mat4,
+        gui_builder: D,
+    ) -> WorldGui<D> {
+        let window_info = world.window_info().unwrap();
+        let target = WindowInfo {
+            size: Vec2::new(size.0 as f32, size.1 as f32),
+            ..window_info
+        };
+
+        let texture = RgbaTexture::new(size.0, size.1);
+        let mut framebuffer = Framebuffer::new();
+        framebuffer.attach_texture(FrameBufferAttachment::Color(0), &texture);
+        framebuffer.set_draw_targets(vec![FrameBufferAttachment::Color(0)]);
+
+        let world = world.as_static_mut();
+        let quad = world.entity();
+        world.add_component(
+            quad,
+            DrawComponent {
+                render_seq: quad_render_seq(&texture),
+                model_matrix: transform,
+            },
+        );
+
+        WorldGui {
+            context: GuiContext::new(target, false, gui_builder, world),
+            texture,
+            framebuffer,
+            camera,
+            quad,
+            transform,
+            window_size: window_info.size,
+            cursor_inside: false,
+            pressed: false,
+            occlusion_test: None,
+            background: Vec4::new(0.0, 0.0, 0.0, 0.8),
+        }
+    }
+
+    pub fn context(&self) -> &GuiContext<D> {
+        &self.context
+    }
+    pub fn context_mut(&mut self) -> &mut GuiContext<D> {
+        &mut self.context
+    }
+    pub fn texture(&self) -> &RgbaTexture {
+        &self.texture
+    }
+    pub fn quad(&self) -> Entity {
+        self.quad
+    }
+
+    pub fn transform(&self) -> Mat4 {
+        self.transform
+    }
+    pub fn set_transform(&mut self, transform: Mat4, world: &mut StaticWorld) {
+        self.transform = transform;
+        if let Some(draw) = world.component_mut::<DrawComponent>(self.quad) {
+            draw.model_matrix = transform;
+        }
+    }
+
+    /// Decides whether the scene hides the quad from the cursor
+    ///
+    /// `test` gets the world space points where the cursor ray starts and where it hits the quad
+    /// and returns true if something lies between them, e.g. by comparing against a depth read.
+    /// The cursor then counts as outside the gui and the input goes on to other systems.
+    pub fn set_occlusion_test<F>(&mut self, test: F)
+    where
+        F: Fn(Vec3, Vec3, &StaticWorld) -> bool + 'static,
+    {
+        self.occlusion_test = Some(Box::new(test));
+    }
+    pub fn clear_occlusion_test(&mut self) {
+        self.occlusion_test = None;
+    }
+
+    /// Point where the cursor ray crosses the plane of the quad, in gui pixels
+    ///
+    /// The point may lie outside the gui, None if the plane is not in front of the camera.
+    /// Geometry in front of the quad is not considered, see `set_occlusion_test`.
+    pub fn cursor_to_gui(&self, cursor: Vec2, world: &StaticWorld) -> Option<Vec2> {
+        self.cursor_ray(cursor, world)
+            .map(|(_, hit)| self.local_to_gui(hit))
+    }
+
+    /// Start of the cursor ray and its hit on the plane of the quad, in local coordinates
+    fn cursor_ray(&self, cursor: Vec2, world: &StaticWorld) -> Option<(Vec3, Vec3)> {
+        let camera = &world.component::<DataComponent<Camera>>(self.camera)?.data;
+        let ndc = Vec2::new(
+            cursor.x / self.window_size.x * 2.0 - 1.0,
+            1.0 - cursor.y / self.window_size.y * 2.0,
+        );
+
+        let to_local = self.transform.inverse()
+            * camera.inv_view()
+            * camera.inv_proj(self.window_size.aspect());
+        let unproject = |z: f32| {
+            let p = to_local * Vec4::new(ndc.x, ndc.y, z, 1.0);
+            p.xyz() / p.w
+        };
+        let near = unproject(-1.0);
+        let far = unproject(1.0);
+
+        if near.z == far.z {
+            return None;
+        }
+        let t = near.z / (near.z - far.z);
+        if t < 0.0 {
+            return None;
+        }
+
+        Some((near, near + (far - near) * t))
+    }
+    fn local_to_gui(&self, p: Vec3) -> Vec2 {
+        let size = self.texture.size();
+        Vec2::new(p.x * size.x, (1.0 - p.y) * size.y)
+    }
+    fn occluded(&self, near: Vec3, hit: Vec3, world: &StaticWorld) -> bool {
+        let test = match self.occlusion_test {
+            Some(ref test) => test,
+            None => return false,
+        };
+        let to_world = |p: Vec3| (self.transform * Vec4::from_vec3(p, 1.0)).xyz();
+        test(to_world(near), to_world(hit), world)
+    }
+
+    fn cursor_moved(&mut self, cursor: Vec2, world: &mut StaticWorld) -> bool {
+        let size = self.texture.size();
+        let ray = self.cursor_ray(cursor, world);
+        let p = ray.map(|(_, hit)| self.local_to_gui(hit));
+        let inside = match (ray, p) {
+            (Some((near, hit)), Some(p)) => {
+                p.x >= 0.0
+                    && p.y >= 0.0
+                    && p.x <= size.x
+                    && p.y <= size.y
+                    && !self.occluded(near, hit, world)
+            }
+            _ => false,
+        };
+
+        // a press on the gui keeps feeding it the cursor until released
+        match p {
+            Some(p) if inside || self.pressed => {
+                self.cursor_inside = true;
+                let p = Vec2px::from_pixels(p, self.context.gui_scale());
+                self.context.cursor_moved(p, world)
+            }
+            _ => self.cursor_left(world),
+        }
+    }
+
+    fn cursor_left(&mut self, world: &mut StaticWorld) -> bool {
+        if !self.cursor_inside {
+            return false;
+        }
+        self.cursor_inside = false;
+        self.context.cursor_left(world)
+    }
+}
+
+/// Unit square in the xy plane textured with the gui
+fn quad_render_seq(texture: &RgbaTexture) -> RenderSequence {
+    let pts = Rect::unit().corners_3d();
+    let tpt = Rect::unit().corners();
+    let clr = vec![Vec4::WHITE; pts.len()];
+
+    let pbuf = Buffer::from_vec(&pts);
+    let cbuf = Buffer::from_vec(&clr);
+    let tbuf = Buffer::from_vec(&tpt);
+    let mut vao = VertexArray::new();
+    vao.attrib_buffer(0, &pbuf);
+    vao.attrib_buffer(1, &cbuf);
+    vao.attrib_buffer(2, &tbuf);
+
+    let mut render_seq = RenderSequence::new();
+    render_seq.add_buffer(pbuf.into_base_type());
+    render_seq.add_buffer(cbuf.into_base_type());
+    render_seq.add_buffer(tbuf.into_base_type());
+
+    let mut cmd = RenderCommand::new_uniforms(
+        vao,
+        DrawMode::TriangleFan,
+        DrawShaderSelector::Textured,
+        vec![Uniform::from("tex", texture)],
+    );
+    cmd.transparent = true;
+    render_seq.add_command(cmd);
+    render_seq
+}
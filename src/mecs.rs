@@ -16,6 +16,8 @@ pub use self::glutin_util::GlutinElementState;
 pub use self::glutin_util::GlutinEvent;
 pub use self::glutin_util::GlutinKey;
 pub use self::glutin_util::GlutinScrollDelta;
+pub use self::glutin_util::GlutinTouch;
+pub use self::glutin_util::GlutinTouchPhase;
 pub use self::glutin_util::GlutinWindowEvent;
 pub use self::glutin_win::GlutinWindowData;
 pub use self::message::AnnotatedMessage;
@@ -25,11 +27,18 @@ pub use self::message_channel::MessageChannel;
 pub use self::message_loop_data::MessageLoopData;
 pub use self::render_pipeline::RenderPipeline;
 pub use self::render_target::WindowInfo;
+pub use self::shortcuts::ShortcutConflict;
+pub use self::shortcuts::ShortcutId;
+pub use self::shortcuts::ShortcutRegistry;
+pub use self::shortcuts::ShortcutScope;
 pub use self::static_world::StaticWorld;
 pub use self::system::System;
 pub use self::system::SystemId;
 pub use self::system_set::SystemSet;
 pub use self::systems::DrawSystem;
+#[cfg(feature = "serializable")]
+pub use self::window_state::WindowState;
+pub use self::world::RenderMode;
 pub use self::world::World;
 
 pub mod component;
@@ -43,8 +52,10 @@ pub mod message_channel;
 pub mod message_loop_data;
 pub mod render_pipeline;
 pub mod render_target;
+pub mod shortcuts;
 pub mod static_world;
 pub mod system;
 pub mod system_set;
 pub mod systems;
+pub mod window_state;
 pub mod world;
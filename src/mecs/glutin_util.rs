@@ -7,6 +7,8 @@ pub type GlutinWindowEvent<'a> = glutin::event::WindowEvent<'a>;
 pub type GlutinDeviceEvent = glutin::event::DeviceEvent;
 pub type GlutinScrollDelta = glutin::event::MouseScrollDelta;
 pub type GlutinElementState = glutin::event::ElementState;
+pub type GlutinTouch = glutin::event::Touch;
+pub type GlutinTouchPhase = glutin::event::TouchPhase;
 
 pub enum GlutinEvent<'a> {
     WindowEvent(GlutinWindowEvent<'a>),
@@ -1,5 +1,7 @@
 use super::glutin_util::*;
 use super::render_target::*;
+#[cfg(feature = "serializable")]
+use super::window_state::WindowState;
 use std::time::Duration;
 use tools::*;
 
@@ -61,4 +63,20 @@ impl GlutinWindowData {
     pub fn event_loop_proxy(&self) -> GlutinEventLoopProxy {
         self.event_loop.create_proxy()
     }
+    #[cfg(feature = "serializable")]
+    pub fn window_state(&self) -> WindowState {
+        let win = self.gl_window.window();
+        WindowState {
+            size: win.inner_size().into(),
+            position: win.outer_position().ok().map(Into::into),
+        }
+    }
+    #[cfg(feature = "serializable")]
+    pub fn set_window_state(&self, state: &WindowState) {
+        let win = self.gl_window.window();
+        win.set_inner_size(glutin::dpi::PhysicalSize::new(state.size.x, state.size.y));
+        if let Some(pos) = state.position {
+            win.set_outer_position(glutin::dpi::PhysicalPosition::new(pos.x, pos.y));
+        }
+    }
 }
@@ -41,7 +41,7 @@ impl From<SystemId> for MessageTarget {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone)]
 pub struct Exit {}
 impl Message for Exit {}
 
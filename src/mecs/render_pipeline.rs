@@ -3,7 +3,13 @@ use tools::Vec3;
 
 pub trait RenderPipeline {
     fn render(&mut self, world: &mut StaticWorld, all_systems: &mut SystemSet);
-    fn event(&mut self, world: &mut StaticWorld, all_systems: &mut SystemSet, event: &GlutinEvent);
+    /// Hands the event to the systems, true if one of them handled it
+    fn event(
+        &mut self,
+        world: &mut StaticWorld,
+        all_systems: &mut SystemSet,
+        event: &GlutinEvent,
+    ) -> bool;
 }
 
 pub struct DefaultPipeline {
@@ -32,12 +38,17 @@ impl RenderPipeline for DefaultPipeline {
         }
     }
 
-    fn event(&mut self, world: &mut StaticWorld, all_systems: &mut SystemSet, event: &GlutinEvent) {
+    fn event(
+        &mut self,
+        world: &mut StaticWorld,
+        all_systems: &mut SystemSet,
+        event: &GlutinEvent,
+    ) -> bool {
         // Everything first
         for (id, sys) in all_systems.all_systems_mut() {
             if !id.is_type::<DrawSystem>() {
                 if sys.event(event, world) {
-                    return;
+                    return true;
                 }
             }
         }
@@ -46,9 +57,10 @@ impl RenderPipeline for DefaultPipeline {
         for (id, sys) in all_systems.all_systems_mut() {
             if id.is_type::<DrawSystem>() {
                 if sys.event(event, world) {
-                    return;
+                    return true;
                 }
             }
         }
+        false
     }
 }
@@ -0,0 +1,123 @@
+use std::error::Error;
+use std::fmt;
+
+use gui::Shortcut;
+use mecs::{AnnotatedMessage, Message, MessageTarget};
+
+/// Where a shortcut is active
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ShortcutScope {
+    /// Before any system sees the key, even while a text field has the focus
+    Global,
+    /// When no system took the key
+    Window,
+    /// When the focused widget lies inside a `ShortcutArea` of this name, before the window
+    Focus(String),
+}
+
+impl ShortcutScope {
+    /// Whether a key may trigger both scopes, a focused area shadows the window without conflict
+    fn overlaps(&self, other: &ShortcutScope) -> bool {
+        match (self, other) {
+            (ShortcutScope::Global, _) | (_, ShortcutScope::Global) => true,
+            (ShortcutScope::Window, ShortcutScope::Window) => true,
+            (ShortcutScope::Focus(a), ShortcutScope::Focus(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ShortcutId(usize);
+
+/// The key chord is already bound in an overlapping scope
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShortcutConflict {
+    pub shortcut: Shortcut,
+    pub scope: ShortcutScope,
+    pub existing: ShortcutId,
+    pub existing_scope: ShortcutScope,
+}
+
+impl fmt::Display for ShortcutConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Shortcut {} in {:?} conflicts with the binding in {:?}",
+            self.shortcut, self.scope, self.existing_scope
+        )
+    }
+}
+
+impl Error for ShortcutConflict {}
+
+struct Binding {
+    id: ShortcutId,
+    shortcut: Shortcut,
+    scope: ShortcutScope,
+    message: Box<dyn Fn() -> AnnotatedMessage>,
+}
+
+/// Key chords bound to messages, queued into the world when pressed in their scope
+#[derive(Default)]
+pub struct ShortcutRegistry {
+    bindings: Vec<Binding>,
+    next_id: usize,
+}
+
+impl ShortcutRegistry {
+    pub fn register<T, M>(
+        &mut self,
+        shortcut: Shortcut,
+        scope: ShortcutScope,
+        target: T,
+        message: M,
+    ) -> Result<ShortcutId, ShortcutConflict>
+    where
+        T: Into<MessageTarget>,
+        M: Message + Clone,
+    {
+        if let Some(existing) = self
+            .bindings
+            .iter()
+            .find(|b| b.shortcut == shortcut && b.scope.overlaps(&scope))
+        {
+            return Err(ShortcutConflict {
+                shortcut,
+                scope,
+                existing: existing.id,
+                existing_scope: existing.scope.clone(),
+            });
+        }
+
+        let id = ShortcutId(self.next_id);
+        self.next_id += 1;
+        let target = target.into();
+        self.bindings.push(Binding {
+            id,
+            shortcut,
+            scope,
+            message: Box::new(move || (target, message.clone()).into()),
+        });
+        Ok(id)
+    }
+    pub fn unregister(&mut self, id: ShortcutId) -> bool {
+        let count = self.bindings.len();
+        self.bindings.retain(|b| b.id != id);
+        self.bindings.len() != count
+    }
+
+    pub fn bindings(&self) -> Vec<(ShortcutId, Shortcut, &ShortcutScope)> {
+        self.bindings
+            .iter()
+            .map(|b| (b.id, b.shortcut, &b.scope))
+            .collect()
+    }
+    /// Message of the binding for `shortcut` in `scope`
+    pub fn message(&self, shortcut: Shortcut, scope: &ShortcutScope) -> Option<AnnotatedMessage> {
+        self.bindings
+            .iter()
+            .find(|b| b.shortcut == shortcut && b.scope == *scope)
+            .map(|b| (b.message)())
+    }
+}
@@ -1,6 +1,8 @@
 use super::bimap::BiMap;
 use super::component::*;
 use super::message::*;
+use super::shortcuts::*;
+use gui::Shortcut;
 use mecs::entity::Entity;
 use mecs::{GlutinElementState, GlutinKey, System};
 use std::any::TypeId;
@@ -13,6 +15,7 @@ pub struct StaticWorld {
     entity_names: BiMap<String, Entity>,
     next_entity_id: usize,
     pub(super) queued_messages: Vec<AnnotatedMessage>,
+    shortcuts: ShortcutRegistry,
 }
 
 impl StaticWorld {
@@ -203,6 +206,37 @@ impl StaticWorld {
         self.send_annotated((MessageTarget::Broadcast, msg).into());
     }
 
+    /// Binds `shortcut` to send `message` to `target` whenever it is pressed in `scope`
+    pub fn register_shortcut<T, M>(
+        &mut self,
+        shortcut: Shortcut,
+        scope: ShortcutScope,
+        target: T,
+        message: M,
+    ) -> Result<ShortcutId, ShortcutConflict>
+    where
+        T: Into<MessageTarget>,
+        M: Message + Clone,
+    {
+        self.shortcuts.register(shortcut, scope, target, message)
+    }
+    pub fn unregister_shortcut(&mut self, id: ShortcutId) -> bool {
+        self.shortcuts.unregister(id)
+    }
+    pub fn shortcuts(&self) -> &ShortcutRegistry {
+        &self.shortcuts
+    }
+    /// Queues the message bound to `key` and the held modifiers in `scope`, if any
+    pub fn trigger_shortcut(&mut self, key: GlutinKey, scope: &ShortcutScope) -> bool {
+        match self.shortcuts.message(Shortcut::pressed(key, self), scope) {
+            Some(msg) => {
+                self.send_annotated(msg);
+                true
+            }
+            None => false,
+        }
+    }
+
     pub fn is_key_pressed(&self, key: GlutinKey) -> bool {
         if let Some(GlutinElementState::Pressed) = self.key_states.get(&key) {
             true
@@ -210,4 +244,13 @@ impl StaticWorld {
             false
         }
     }
+    pub fn is_shift_pressed(&self) -> bool {
+        self.is_key_pressed(GlutinKey::LShift) || self.is_key_pressed(GlutinKey::RShift)
+    }
+    pub fn is_ctrl_pressed(&self) -> bool {
+        self.is_key_pressed(GlutinKey::LControl) || self.is_key_pressed(GlutinKey::RControl)
+    }
+    pub fn is_alt_pressed(&self) -> bool {
+        self.is_key_pressed(GlutinKey::LAlt) || self.is_key_pressed(GlutinKey::RAlt)
+    }
 }
@@ -38,6 +38,13 @@ pub trait System: Downcast {
     fn receive(&mut self, _msg: &Box<dyn Message>, _world: &mut StaticWorld) {}
     fn update(&mut self, _delta_time: Duration, _world: &mut StaticWorld) {}
     fn render(&mut self, _world: &mut StaticWorld) {}
+    /// Whether the next frame would differ, consulted when rendering on demand
+    ///
+    /// Systems drawing something that changes over time have to say so here, otherwise the
+    /// loop sleeps until input arrives.
+    fn needs_redraw(&self, _world: &StaticWorld) -> bool {
+        false
+    }
     fn window_event(&mut self, _event: &GlutinWindowEvent, _world: &mut StaticWorld) -> bool {
         false
     }
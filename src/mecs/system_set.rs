@@ -76,6 +76,11 @@ impl SystemSet {
             systems.remove(&id);
         }
     }
+    pub fn all_systems(&self) -> impl Iterator<Item = (&SystemId, &Box<dyn System>)> {
+        self.systems_of_type
+            .values()
+            .flat_map(|systems| systems.iter())
+    }
     pub fn all_systems_mut<'a>(
         &'a mut self,
     ) -> impl Iterator<Item = (&SystemId, &'a mut Box<dyn System>)> {
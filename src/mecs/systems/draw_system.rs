@@ -4,11 +4,23 @@ use mecs::{
     StaticWorld, System, World,
 };
 use std::time::Duration;
-use tools::{Camera, CameraController, Vec2, Vec4};
+use tools::{Camera, CameraController, Mat4, Vec2, Vec4};
 
 pub struct DrawSystem {
     pub camera_entity: Entity,
     pub resources: DrawResources,
+    drawn: Option<DrawnScene>,
+}
+
+/// What the last frame showed, a new one is needed once it differs
+///
+/// Only the camera and the placement of the entities are compared, a system replacing the render
+/// sequence of a `DrawComponent` has to report that through its own `System::needs_redraw`.
+#[derive(PartialEq)]
+struct DrawnScene {
+    view: Mat4,
+    proj: Mat4,
+    entities: Vec<(Entity, Mat4)>,
 }
 
 impl System for DrawSystem {
@@ -53,6 +65,7 @@ impl System for DrawSystem {
             }
         });
 
+        self.drawn = Some(self.scene(world));
         for (e, _, _) in entities {
             let c = world.component::<DrawComponent>(e).unwrap();
             self.resources.model_matrix = c.model_matrix;
@@ -65,9 +78,24 @@ impl System for DrawSystem {
         }
     }
 
+    fn needs_redraw(&self, world: &StaticWorld) -> bool {
+        self.drawn.as_ref() != Some(&self.scene(world))
+    }
+
     fn window_event(&mut self, event: &GlutinWindowEvent, world: &mut StaticWorld) -> bool {
-        if let GlutinWindowEvent::Resized(s) = event {
-            self.resources.window_info.size = Vec2::new(s.width as f32, s.height as f32);
+        match event {
+            GlutinWindowEvent::Resized(s) => {
+                self.resources.window_info.size = Vec2::new(s.width as f32, s.height as f32);
+            }
+            GlutinWindowEvent::ScaleFactorChanged {
+                scale_factor,
+                new_inner_size,
+            } => {
+                self.resources.window_info.size =
+                    Vec2::new(new_inner_size.width as f32, new_inner_size.height as f32);
+                self.resources.window_info.gui_scale = *scale_factor as f32;
+            }
+            _ => {}
         }
 
         let camera = world
@@ -102,6 +130,20 @@ impl DrawSystem {
         DrawSystem {
             camera_entity,
             resources: draw_res,
+            drawn: None,
+        }
+    }
+
+    fn scene(&self, world: &StaticWorld) -> DrawnScene {
+        let camera = self.camera(world);
+        DrawnScene {
+            view: camera.view(),
+            proj: camera.proj(self.resources.window_info.size.aspect()),
+            entities: world
+                .entities_with_component::<DrawComponent>()
+                .into_iter()
+                .map(|(e, c)| (e, c.model_matrix))
+                .collect(),
         }
     }
 
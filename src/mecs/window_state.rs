@@ -0,0 +1,14 @@
+#![cfg(feature = "serializable")]
+extern crate serde;
+
+use self::serde::{Deserialize, Serialize};
+use tools::*;
+
+/// Size and position of a window in physical pixels, see `World::persist_window`
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WindowState {
+    pub size: Vec2,
+    /// None where the platform does not tell window positions
+    #[serde(default)]
+    pub position: Option<Vec2>,
+}
@@ -2,6 +2,8 @@ extern crate gl;
 extern crate glutin;
 
 use std::mem::swap;
+#[cfg(feature = "serializable")]
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::*;
 use std::sync::Arc;
@@ -23,28 +25,70 @@ use super::system::*;
 
 use self::glutin::event::*;
 use self::glutin::platform::desktop::EventLoopExtDesktop;
-use gui::{GuiBuilder, GuiContext};
+use gui::{GuiBuilder, GuiContext, Shortcut};
 use mecs::render_pipeline::DefaultPipeline;
 use mecs::SystemSet;
-use mecs::{Component, Entity, RenderPipeline};
+use mecs::{Component, Entity, RenderPipeline, ShortcutConflict, ShortcutId, ShortcutScope};
+#[cfg(feature = "serializable")]
+use mecs::WindowState;
+#[cfg(feature = "serializable")]
+use tools::serde_tools::SerdeJsonQuick;
+#[cfg(feature = "serializable")]
+extern crate serde;
+#[cfg(feature = "serializable")]
+use self::serde::de::DeserializeOwned;
+#[cfg(feature = "serializable")]
+use self::serde::Serialize;
+#[cfg(feature = "serializable")]
+use gui::GuiPersistence;
+
+/// When the windowed message loop draws frames
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RenderMode {
+    /// Every loop iteration, for games and other ever-changing scenes
+    Continuous,
+    /// Only when a system says its frame is outdated or the window got resized
+    OnDemand,
+}
 
 pub struct World {
     static_world: StaticWorld,
     systems: SystemSet,
     loop_data: MessageLoopData,
     render_pipeline: Box<dyn RenderPipeline>,
+    render_mode: RenderMode,
+    redraw_pending: bool,
+    quit_shortcut: Option<ShortcutId>,
+    /// File the window state is saved to on drop, see `persist_window`
+    #[cfg(feature = "serializable")]
+    window_persistence: Option<(PathBuf, WindowState)>,
     running: bool,
 }
 
 impl Default for World {
     fn default() -> Self {
+        let mut static_world = StaticWorld::default();
+        let quit_shortcut = static_world
+            .register_shortcut(
+                Shortcut::new(GlutinKey::Escape),
+                ShortcutScope::Window,
+                MessageTarget::Root,
+                message::EXIT,
+            )
+            .ok();
+
         World {
-            static_world: Default::default(),
+            static_world,
             systems: Default::default(),
             loop_data: Default::default(),
             render_pipeline: Box::new(DefaultPipeline {
                 bgcolor: Vec3::new(0.3, 0.3, 0.3),
             }),
+            render_mode: RenderMode::Continuous,
+            redraw_pending: true,
+            quit_shortcut,
+            #[cfg(feature = "serializable")]
+            window_persistence: None,
             running: Default::default(),
         }
     }
@@ -124,6 +168,64 @@ impl World {
 
         Ok(w)
     }
+    pub fn set_render_mode(&mut self, render_mode: RenderMode) {
+        self.render_mode = render_mode;
+        self.redraw_pending = true;
+    }
+    pub fn render_mode(&self) -> RenderMode {
+        self.render_mode
+    }
+    /// Window shortcut closing the world, Escape by default, None to have no such shortcut
+    pub fn set_quit_shortcut(
+        &mut self,
+        shortcut: Option<Shortcut>,
+    ) -> Result<(), ShortcutConflict> {
+        if let Some(id) = self.quit_shortcut.take() {
+            self.static_world.unregister_shortcut(id);
+        }
+        if let Some(shortcut) = shortcut {
+            self.quit_shortcut = Some(self.static_world.register_shortcut(
+                shortcut,
+                ShortcutScope::Window,
+                MessageTarget::Root,
+                message::EXIT,
+            )?);
+        }
+        Ok(())
+    }
+    /// Restores the window size and position stored at `path`, they are saved back there when
+    /// the world is dropped
+    #[cfg(feature = "serializable")]
+    pub fn persist_window<P: AsRef<Path>>(&mut self, path: P) {
+        if let MessageLoopData::GlutinWindowed(win) = &self.loop_data {
+            let state = match WindowState::load_json(&path) {
+                Ok(state) => {
+                    win.set_window_state(&state);
+                    state
+                }
+                Err(_) => win.window_state(),
+            };
+            self.window_persistence = Some((path.as_ref().to_owned(), state));
+        }
+    }
+    #[cfg(feature = "serializable")]
+    fn track_window_state(&mut self, event: &glutin::event::WindowEvent) {
+        if let Some((_, state)) = self.window_persistence.as_mut() {
+            match event {
+                glutin::event::WindowEvent::Resized(size) => state.size = (*size).into(),
+                glutin::event::WindowEvent::Moved(pos) => state.position = Some((*pos).into()),
+                _ => (),
+            }
+        }
+    }
+    fn needs_redraw(&self) -> bool {
+        self.render_mode == RenderMode::Continuous
+            || self.redraw_pending
+            || self
+                .systems
+                .all_systems()
+                .any(|(_id, sys)| sys.needs_redraw(&self.static_world))
+    }
     pub fn window_info(&self) -> Option<WindowInfo> {
         match &self.loop_data {
             MessageLoopData::GlutinWindowed(win) => Some(win.render_target()),
@@ -362,11 +464,15 @@ impl World {
                 match event {
                     Event::WindowEvent { event, .. } => {
                         if self.glutin_window_event(&event) {
-                            self.render_pipeline.event(
+                            let event = GlutinEvent::WindowEvent(event);
+                            let handled = self.render_pipeline.event(
                                 &mut self.static_world,
                                 &mut self.systems,
-                                &GlutinEvent::WindowEvent(event),
+                                &event,
                             );
+                            if !handled {
+                                self.window_shortcut(&event);
+                            }
                         }
 
                         self.deliver_all_messages();
@@ -384,13 +490,17 @@ impl World {
                         self.render_pipeline
                             .render(&mut self.static_world, &mut self.systems);
                         win.swap_buffers().unwrap();
+                        self.redraw_pending = false;
                     }
                     Event::UserEvent(msg) => {
                         self.static_world.send_annotated(msg);
                     }
                     Event::MainEventsCleared => {
                         self.deliver_all_messages();
-                        win.window().request_redraw();
+                        // on demand the loop sleeps until the next input, message or update tick
+                        if self.needs_redraw() {
+                            win.window().request_redraw();
+                        }
                     }
                     _ => (),
                 }
@@ -402,16 +512,14 @@ impl World {
     }
 
     fn glutin_window_event(&mut self, event: &glutin::event::WindowEvent) -> bool {
+        #[cfg(feature = "serializable")]
+        self.track_window_state(event);
         match event {
             glutin::event::WindowEvent::CloseRequested => {
                 self.running = false;
             }
             glutin::event::WindowEvent::KeyboardInput { input, .. } => {
                 if let Some(key) = input.virtual_keycode {
-                    if key == glutin::event::VirtualKeyCode::Escape {
-                        self.running = false;
-                    }
-
                     if input.state == GlutinElementState::Pressed
                         && self.static_world.is_key_pressed(key)
                     {
@@ -419,16 +527,45 @@ impl World {
                     }
 
                     self.static_world.key_states.insert(key, input.state);
+
+                    if input.state == GlutinElementState::Pressed
+                        && self.static_world.trigger_shortcut(key, &ShortcutScope::Global)
+                    {
+                        return false;
+                    }
+                }
+            }
+            glutin::event::WindowEvent::Resized(size) => {
+                self.redraw_pending = true;
+                unsafe {
+                    gl::Viewport(0, 0, size.width as i32, size.height as i32);
+                }
+            }
+            glutin::event::WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                self.redraw_pending = true;
+                unsafe {
+                    gl::Viewport(
+                        0,
+                        0,
+                        new_inner_size.width as i32,
+                        new_inner_size.height as i32,
+                    );
                 }
             }
-            glutin::event::WindowEvent::Resized(size) => unsafe {
-                gl::Viewport(0, 0, size.width as i32, size.height as i32);
-            },
             _ => (),
         }
         true
     }
 
+    /// Key press no system took
+    fn window_shortcut(&mut self, event: &GlutinEvent) {
+        if let GlutinEvent::WindowEvent(WindowEvent::KeyboardInput { input, .. }) = event {
+            if let (GlutinElementState::Pressed, Some(key)) = (input.state, input.virtual_keycode) {
+                self.static_world.trigger_shortcut(key, &ShortcutScope::Window);
+            }
+        }
+    }
+
     pub fn add_gui<T>(&mut self, gui_builder: T)
     where
         T: GuiBuilder + 'static,
@@ -440,6 +577,22 @@ impl World {
             &mut self.static_world,
         );
 
+        self.add_system(gui_context);
+    }
+    /// Gui whose data is restored from and saved to a file, see `GuiContext::new_persistent`
+    #[cfg(feature = "serializable")]
+    pub fn add_persistent_gui<T>(&mut self, gui_builder: T, persistence: GuiPersistence)
+    where
+        T: GuiBuilder + Serialize + DeserializeOwned + 'static,
+    {
+        let gui_context = GuiContext::new_persistent(
+            self.window_info().unwrap(),
+            false,
+            gui_builder,
+            &mut self.static_world,
+            persistence,
+        );
+
         self.add_system(gui_context);
     }
 }
@@ -449,6 +602,14 @@ impl Drop for World {
         for (_id, sys) in self.systems.all_systems_mut() {
             sys.detach(&mut self.static_world);
         }
+        #[cfg(feature = "serializable")]
+        {
+            if let Some((path, state)) = self.window_persistence.as_ref() {
+                if let Err(e) = state.save_json(path) {
+                    eprintln!("Could not save window state to {}: {:?}", path.display(), e);
+                }
+            }
+        }
     }
 }
 
@@ -23,6 +23,9 @@ pub use self::font_loader_error::FontLoaderError;
 pub use self::framebuffer::FrameBufferAttachment;
 pub use self::framebuffer::FrameBufferStatus;
 pub use self::framebuffer::Framebuffer;
+pub use self::gesture::Gesture;
+pub use self::gesture::GesturePhase;
+pub use self::gesture::GestureRecognizer;
 pub use self::gltraits::GlNum;
 pub use self::gltraits::GlUniform;
 pub use self::gpuclock::GPUClock;
@@ -60,6 +63,7 @@ pub mod font_error;
 pub mod font_loader;
 pub mod font_loader_error;
 pub mod framebuffer;
+pub mod gesture;
 pub mod gltraits;
 pub mod gpuclock;
 pub mod indices;
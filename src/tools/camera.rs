@@ -1,9 +1,10 @@
 use graphics::{DrawResources, RenderSequence};
 use mecs::{GlutinDeviceEvent, GlutinWindowEvent};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tools::camera_parameters::Projection;
 use tools::{
-    CameraController, CameraParameters, FrameBufferAttachment, Framebuffer, Mat4, Texture,
+    CameraController, CameraParameters, FrameBufferAttachment, Framebuffer, GestureRecognizer,
+    Mat4, Texture,
 };
 
 #[derive(Debug)]
@@ -11,6 +12,7 @@ pub struct Camera {
     fbo: Option<Framebuffer>,
     pub params: CameraParameters,
     controller: Box<dyn CameraController>,
+    gestures: GestureRecognizer,
 }
 
 fn aspect(size: (usize, usize)) -> f32 {
@@ -39,9 +41,19 @@ impl Camera {
         self.controller.downcast_mut()
     }
     pub fn update(&mut self, delta: Duration) {
+        for gesture in self.gestures.update(Instant::now()) {
+            self.controller.on_gesture(&mut self.params, &gesture);
+        }
         self.controller.update(&mut self.params, delta);
     }
     pub fn on_window_event(&mut self, event: &GlutinWindowEvent) -> bool {
+        if let GlutinWindowEvent::Touch(touch) = event {
+            let mut handled = false;
+            for gesture in self.gestures.touch_event(touch, Instant::now()) {
+                handled |= self.controller.on_gesture(&mut self.params, &gesture);
+            }
+            return handled;
+        }
         self.controller.on_window_event(&mut self.params, event)
     }
     pub fn on_device_event(&mut self, event: &GlutinDeviceEvent) -> bool {
@@ -66,6 +78,7 @@ impl Camera {
             fbo: None,
             params,
             controller: Box::new(controller),
+            gestures: GestureRecognizer::new(),
         }
     }
 
@@ -6,6 +6,7 @@ use mecs::{GlutinDeviceEvent, GlutinWindowEvent};
 use std::fmt::Debug;
 use std::time::Duration;
 use tools::camera_parameters::CameraParameters;
+use tools::Gesture;
 
 pub trait CameraController: Downcast + Debug {
     fn on_window_event(&mut self, _cam: &mut CameraParameters, _event: &GlutinWindowEvent) -> bool {
@@ -14,6 +15,10 @@ pub trait CameraController: Downcast + Debug {
     fn on_device_event(&mut self, _cam: &mut CameraParameters, _event: &GlutinDeviceEvent) -> bool {
         false
     }
+    /// Gestures recognized from the touches the camera got as window events
+    fn on_gesture(&mut self, _cam: &mut CameraParameters, _gesture: &Gesture) -> bool {
+        false
+    }
     fn update(&mut self, _cam: &mut CameraParameters, _delta: Duration) {}
     fn init(&self, _cam: &mut CameraParameters) {}
 }
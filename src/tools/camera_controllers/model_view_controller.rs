@@ -4,7 +4,7 @@ use mecs::{
 };
 use std::collections::HashMap;
 use std::f32::consts::PI;
-use tools::{CameraController, CameraParameters, CameraSpatialParams, Vec2};
+use tools::{CameraController, CameraParameters, CameraSpatialParams, Gesture, Vec2};
 
 #[derive(Debug)]
 pub struct ModelViewController {
@@ -103,24 +103,7 @@ impl CameraController for ModelViewController {
 
                     if self.rotation_direction != None || !self.is_key_pressed(GlutinKey::LControl)
                     {
-                        let pitch = self.real_spatial.pitch();
-                        let delta_y = if !self.enable_upside_down
-                            && -delta.y * 0.01 + pitch < -PI / 2.0 * 0.99
-                        {
-                            PI / 2.0 * 0.99 + pitch
-                        } else if !self.enable_upside_down
-                            && -delta.y * 0.01 + pitch > PI / 2.0 * 0.99
-                        {
-                            -PI / 2.0 * 0.99 + pitch
-                        } else {
-                            delta.y * 0.01
-                        };
-
-                        let d = self.real_spatial.target_to_pos();
-                        let d = d.rotate(self.real_spatial.u(), delta.x * 0.01);
-                        let d = d.rotate(self.real_spatial.r(), delta_y);
-
-                        self.real_spatial.set_pos(self.real_spatial.target + d);
+                        self.orbit(delta);
                     }
 
                     if self.disable_roll {
@@ -154,6 +137,32 @@ impl CameraController for ModelViewController {
             _ => false,
         }
     }
+    fn on_gesture(&mut self, cam: &mut CameraParameters, gesture: &Gesture) -> bool {
+        match *gesture {
+            Gesture::Pan { mut delta, .. } => {
+                if self.disable_roll {
+                    delta.x *= self.real_spatial.cos_pitch();
+                }
+                self.orbit(delta);
+                if self.disable_roll {
+                    self.real_spatial.cancel_roll(self.enable_upside_down);
+                }
+            }
+            Gesture::Pinch { scale, .. } if scale > 0.0 => {
+                let d = self.real_spatial.target_to_pos();
+                self.real_spatial
+                    .set_pos(self.real_spatial.target + d / scale);
+            }
+            Gesture::Rotate { angle, .. } => {
+                let d = self.real_spatial.target_to_pos();
+                let d = d.rotate(self.real_spatial.u(), -angle);
+                self.real_spatial.set_pos(self.real_spatial.target + d);
+            }
+            _ => return false,
+        }
+        cam.spatial = self.real_spatial;
+        true
+    }
     fn init(&self, cam: &mut CameraParameters) {
         cam.spatial = self.real_spatial;
     }
@@ -173,6 +182,23 @@ impl CameraController for ModelViewController {
 }
 
 impl ModelViewController {
+    /// Rotates the camera around its target, `delta` in pixels
+    fn orbit(&mut self, delta: Vec2) {
+        let pitch = self.real_spatial.pitch();
+        let delta_y = if !self.enable_upside_down && -delta.y * 0.01 + pitch < -PI / 2.0 * 0.99 {
+            PI / 2.0 * 0.99 + pitch
+        } else if !self.enable_upside_down && -delta.y * 0.01 + pitch > PI / 2.0 * 0.99 {
+            -PI / 2.0 * 0.99 + pitch
+        } else {
+            delta.y * 0.01
+        };
+
+        let d = self.real_spatial.target_to_pos();
+        let d = d.rotate(self.real_spatial.u(), delta.x * 0.01);
+        let d = d.rotate(self.real_spatial.r(), delta_y);
+
+        self.real_spatial.set_pos(self.real_spatial.target + d);
+    }
     pub fn spatial_mut(&mut self) -> &mut CameraSpatialParams {
         &mut self.real_spatial
     }
@@ -0,0 +1,606 @@
+use mecs::{GlutinTouch, GlutinTouchPhase};
+use std::f32::consts::PI;
+use std::time::{Duration, Instant};
+use tools::Vec2;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GesturePhase {
+    Began,
+    Changed,
+    Ended,
+}
+
+/// Gesture recognized from touches, positions in window pixels
+///
+/// Deltas of continuous gestures are relative to the previous event of the same gesture.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Gesture {
+    Tap {
+        pos: Vec2,
+    },
+    /// Follows the `Tap` of a second tap close in time and place to the first
+    DoubleTap {
+        pos: Vec2,
+    },
+    LongPress {
+        pos: Vec2,
+    },
+    Pan {
+        phase: GesturePhase,
+        pos: Vec2,
+        delta: Vec2,
+    },
+    /// `scale` multiplies the distance of the two fingers
+    Pinch {
+        phase: GesturePhase,
+        center: Vec2,
+        scale: f32,
+    },
+    /// Clockwise `angle` in radians the two fingers turned by
+    Rotate {
+        phase: GesturePhase,
+        center: Vec2,
+        angle: f32,
+    },
+}
+
+impl Gesture {
+    /// Position of the finger, or the center of two fingers
+    pub fn pos(&self) -> Vec2 {
+        match *self {
+            Gesture::Tap { pos }
+            | Gesture::DoubleTap { pos }
+            | Gesture::LongPress { pos }
+            | Gesture::Pan { pos, .. } => pos,
+            Gesture::Pinch { center, .. } | Gesture::Rotate { center, .. } => center,
+        }
+    }
+    /// Maps positions to `pos * scale + offset` and scales the deltas
+    pub fn transformed(self, offset: Vec2, scale: f32) -> Gesture {
+        let map = |p: Vec2| p * scale + offset;
+        match self {
+            Gesture::Tap { pos } => Gesture::Tap { pos: map(pos) },
+            Gesture::DoubleTap { pos } => Gesture::DoubleTap { pos: map(pos) },
+            Gesture::LongPress { pos } => Gesture::LongPress { pos: map(pos) },
+            Gesture::Pan { phase, pos, delta } => Gesture::Pan {
+                phase,
+                pos: map(pos),
+                delta: delta * scale,
+            },
+            Gesture::Pinch {
+                phase,
+                center,
+                scale: s,
+            } => Gesture::Pinch {
+                phase,
+                center: map(center),
+                scale: s,
+            },
+            Gesture::Rotate {
+                phase,
+                center,
+                angle,
+            } => Gesture::Rotate {
+                phase,
+                center: map(center),
+                angle,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct TrackedTouch {
+    id: u64,
+    pos: Vec2,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum RecognizerState {
+    Idle,
+    /// One finger down that has not moved beyond the slop yet
+    Pressed {
+        start: Vec2,
+        time: Instant,
+    },
+    Panning,
+    TwoFingers {
+        distance: f32,
+        angle: f32,
+    },
+    /// Gesture finished or long press fired, waiting for every finger to lift
+    Done,
+}
+
+/// Turns raw touches into taps, long presses, pans, pinches and rotations
+///
+/// Time is passed in, so synthetic touches replay deterministically. `update` has to be called
+/// regularly for long presses to fire while the finger rests.
+#[derive(Debug, Clone)]
+pub struct GestureRecognizer {
+    touches: Vec<TrackedTouch>,
+    state: RecognizerState,
+    /// Position and time of the last tap not yet part of a double tap
+    last_tap: Option<(Vec2, Instant)>,
+    /// Distance in pixels a finger may move and still tap
+    pub slop: f32,
+    pub long_press: Duration,
+    /// Longest time between two taps of a double tap
+    pub double_tap: Duration,
+}
+
+impl Default for GestureRecognizer {
+    fn default() -> Self {
+        GestureRecognizer {
+            touches: Vec::new(),
+            state: RecognizerState::Idle,
+            last_tap: None,
+            slop: 10.0,
+            long_press: Duration::from_millis(500),
+            double_tap: Duration::from_millis(300),
+        }
+    }
+}
+
+impl GestureRecognizer {
+    pub fn new() -> GestureRecognizer {
+        Default::default()
+    }
+
+    pub fn touch_event(&mut self, touch: &GlutinTouch, now: Instant) -> Vec<Gesture> {
+        self.touch(touch.id, touch.phase, (&touch.location).into(), now)
+    }
+
+    pub fn touch(
+        &mut self,
+        id: u64,
+        phase: GlutinTouchPhase,
+        pos: Vec2,
+        now: Instant,
+    ) -> Vec<Gesture> {
+        let mut gestures = self.update(now);
+        match phase {
+            GlutinTouchPhase::Started => self.started(id, pos, now, &mut gestures),
+            GlutinTouchPhase::Moved => self.moved(id, pos, &mut gestures),
+            GlutinTouchPhase::Ended => self.ended(id, pos, Some(now), &mut gestures),
+            GlutinTouchPhase::Cancelled => self.ended(id, pos, None, &mut gestures),
+        }
+        gestures
+    }
+
+    /// Fires the long press once the finger rested long enough
+    pub fn update(&mut self, now: Instant) -> Vec<Gesture> {
+        match self.state {
+            RecognizerState::Pressed { start, time } if now - time >= self.long_press => {
+                self.state = RecognizerState::Done;
+                vec![Gesture::LongPress { pos: start }]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Fingers currently down
+    pub fn touch_count(&self) -> usize {
+        self.touches.len()
+    }
+
+    fn started(&mut self, id: u64, pos: Vec2, now: Instant, gestures: &mut Vec<Gesture>) {
+        self.touches.retain(|t| t.id != id);
+        self.touches.push(TrackedTouch { id, pos });
+        match self.touches.len() {
+            1 => {
+                self.state = RecognizerState::Pressed {
+                    start: pos,
+                    time: now,
+                }
+            }
+            2 => {
+                if self.state == RecognizerState::Panning {
+                    gestures.push(Gesture::Pan {
+                        phase: GesturePhase::Ended,
+                        pos: self.touches[0].pos,
+                        delta: Vec2::zero(),
+                    });
+                }
+                let (center, distance, angle) = self.two_fingers();
+                self.state = RecognizerState::TwoFingers { distance, angle };
+                gestures.push(Gesture::Pinch {
+                    phase: GesturePhase::Began,
+                    center,
+                    scale: 1.0,
+                });
+                gestures.push(Gesture::Rotate {
+                    phase: GesturePhase::Began,
+                    center,
+                    angle: 0.0,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    fn moved(&mut self, id: u64, pos: Vec2, gestures: &mut Vec<Gesture>) {
+        let previous = match self.touches.iter_mut().find(|t| t.id == id) {
+            Some(touch) => std::mem::replace(&mut touch.pos, pos),
+            None => return,
+        };
+        match self.state {
+            RecognizerState::Pressed { start, .. } if (pos - start).length() > self.slop => {
+                self.state = RecognizerState::Panning;
+                gestures.push(Gesture::Pan {
+                    phase: GesturePhase::Began,
+                    pos,
+                    delta: pos - start,
+                });
+            }
+            RecognizerState::Panning => gestures.push(Gesture::Pan {
+                phase: GesturePhase::Changed,
+                pos,
+                delta: pos - previous,
+            }),
+            RecognizerState::TwoFingers { distance, angle } if self.is_two_finger_touch(id) => {
+                let (center, new_distance, new_angle) = self.two_fingers();
+                self.state = RecognizerState::TwoFingers {
+                    distance: new_distance,
+                    angle: new_angle,
+                };
+                if distance > 0.0 {
+                    gestures.push(Gesture::Pinch {
+                        phase: GesturePhase::Changed,
+                        center,
+                        scale: new_distance / distance,
+                    });
+                }
+                gestures.push(Gesture::Rotate {
+                    phase: GesturePhase::Changed,
+                    center,
+                    angle: wrap_angle(new_angle - angle),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    /// `lifted` is the time the finger lifted, `None` if the touch got cancelled
+    fn ended(&mut self, id: u64, pos: Vec2, lifted: Option<Instant>, gestures: &mut Vec<Gesture>) {
+        if !self.touches.iter().any(|t| t.id == id) {
+            return;
+        }
+        if let RecognizerState::TwoFingers { .. } = self.state {
+            if self.is_two_finger_touch(id) {
+                let (center, _, _) = self.two_fingers();
+                gestures.push(Gesture::Pinch {
+                    phase: GesturePhase::Ended,
+                    center,
+                    scale: 1.0,
+                });
+                gestures.push(Gesture::Rotate {
+                    phase: GesturePhase::Ended,
+                    center,
+                    angle: 0.0,
+                });
+                self.state = RecognizerState::Done;
+            }
+        }
+        self.touches.retain(|t| t.id != id);
+
+        match (self.state, lifted) {
+            (RecognizerState::Pressed { .. }, Some(now)) => self.tapped(pos, now, gestures),
+            (RecognizerState::Panning, _) => gestures.push(Gesture::Pan {
+                phase: GesturePhase::Ended,
+                pos,
+                delta: Vec2::zero(),
+            }),
+            _ => {}
+        }
+        self.state = if self.touches.is_empty() {
+            RecognizerState::Idle
+        } else {
+            RecognizerState::Done
+        };
+    }
+
+    fn tapped(&mut self, pos: Vec2, now: Instant, gestures: &mut Vec<Gesture>) {
+        gestures.push(Gesture::Tap { pos });
+        match self.last_tap.take() {
+            Some((first, time))
+                if now - time <= self.double_tap && (pos - first).length() <= self.slop =>
+            {
+                gestures.push(Gesture::DoubleTap { pos })
+            }
+            _ => self.last_tap = Some((pos, now)),
+        }
+    }
+
+    fn is_two_finger_touch(&self, id: u64) -> bool {
+        self.touches.iter().take(2).any(|t| t.id == id)
+    }
+    /// Center, distance and angle of the first two fingers
+    fn two_fingers(&self) -> (Vec2, f32, f32) {
+        let a = self.touches[0].pos;
+        let b = self.touches[1].pos;
+        let d = b - a;
+        ((a + b) * 0.5, d.length(), d.y.atan2(d.x))
+    }
+}
+
+fn wrap_angle(angle: f32) -> f32 {
+    if angle > PI {
+        angle - 2.0 * PI
+    } else if angle < -PI {
+        angle + 2.0 * PI
+    } else {
+        angle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glutin::dpi::PhysicalPosition;
+    use glutin::event::DeviceId;
+
+    /// Replays touches against a recognizer, with time advanced by hand
+    struct Replay {
+        recognizer: GestureRecognizer,
+        start: Instant,
+        now: Duration,
+    }
+
+    impl Replay {
+        fn new() -> Replay {
+            Replay {
+                recognizer: GestureRecognizer::new(),
+                start: Instant::now(),
+                now: Duration::from_millis(0),
+            }
+        }
+        fn wait(&mut self, ms: u64) -> Vec<Gesture> {
+            self.now += Duration::from_millis(ms);
+            self.recognizer.update(self.start + self.now)
+        }
+        fn touch(&mut self, id: u64, phase: GlutinTouchPhase, x: f32, y: f32) -> Vec<Gesture> {
+            let touch = GlutinTouch {
+                device_id: unsafe { DeviceId::dummy() },
+                phase,
+                location: PhysicalPosition::new(x as f64, y as f64),
+                force: None,
+                id,
+            };
+            self.recognizer.touch_event(&touch, self.start + self.now)
+        }
+        fn tap(&mut self, id: u64, x: f32, y: f32) -> Vec<Gesture> {
+            self.touch(id, GlutinTouchPhase::Started, x, y);
+            self.wait(50);
+            self.touch(id, GlutinTouchPhase::Ended, x, y)
+        }
+    }
+
+    fn approx(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn tap() {
+        let mut replay = Replay::new();
+        assert!(replay
+            .touch(1, GlutinTouchPhase::Started, 10.0, 10.0)
+            .is_empty());
+        replay.wait(100);
+        let gestures = replay.touch(1, GlutinTouchPhase::Moved, 13.0, 12.0);
+        assert!(gestures.is_empty());
+        let gestures = replay.touch(1, GlutinTouchPhase::Ended, 13.0, 12.0);
+        assert_eq!(
+            gestures,
+            vec![Gesture::Tap {
+                pos: Vec2::new(13.0, 12.0)
+            }]
+        );
+        assert_eq!(replay.recognizer.touch_count(), 0);
+    }
+
+    #[test]
+    fn cancelled_touch_does_not_tap() {
+        let mut replay = Replay::new();
+        replay.touch(1, GlutinTouchPhase::Started, 10.0, 10.0);
+        assert!(replay
+            .touch(1, GlutinTouchPhase::Cancelled, 10.0, 10.0)
+            .is_empty());
+    }
+
+    #[test]
+    fn double_tap() {
+        let mut replay = Replay::new();
+        let pos = Vec2::new(40.0, 40.0);
+        assert_eq!(replay.tap(1, 40.0, 40.0), vec![Gesture::Tap { pos }]);
+        replay.wait(100);
+        assert_eq!(
+            replay.tap(2, 42.0, 41.0),
+            vec![
+                Gesture::Tap {
+                    pos: Vec2::new(42.0, 41.0)
+                },
+                Gesture::DoubleTap {
+                    pos: Vec2::new(42.0, 41.0)
+                },
+            ]
+        );
+        // a third tap starts over
+        replay.wait(100);
+        assert_eq!(replay.tap(3, 40.0, 40.0), vec![Gesture::Tap { pos }]);
+    }
+
+    #[test]
+    fn slow_or_distant_taps_stay_single() {
+        let mut replay = Replay::new();
+        replay.tap(1, 40.0, 40.0);
+        replay.wait(400);
+        assert_eq!(replay.tap(2, 40.0, 40.0).len(), 1);
+        replay.wait(50);
+        assert_eq!(replay.tap(3, 100.0, 40.0).len(), 1);
+    }
+
+    #[test]
+    fn long_press() {
+        let mut replay = Replay::new();
+        replay.touch(1, GlutinTouchPhase::Started, 20.0, 30.0);
+        assert!(replay.wait(400).is_empty());
+        assert_eq!(
+            replay.wait(100),
+            vec![Gesture::LongPress {
+                pos: Vec2::new(20.0, 30.0)
+            }]
+        );
+        assert!(replay.wait(1000).is_empty());
+        // lifting after a long press is no tap
+        assert!(replay
+            .touch(1, GlutinTouchPhase::Ended, 20.0, 30.0)
+            .is_empty());
+    }
+
+    #[test]
+    fn pan() {
+        let mut replay = Replay::new();
+        replay.touch(1, GlutinTouchPhase::Started, 0.0, 0.0);
+        assert!(replay
+            .touch(1, GlutinTouchPhase::Moved, 5.0, 0.0)
+            .is_empty());
+        assert_eq!(
+            replay.touch(1, GlutinTouchPhase::Moved, 20.0, 0.0),
+            vec![Gesture::Pan {
+                phase: GesturePhase::Began,
+                pos: Vec2::new(20.0, 0.0),
+                delta: Vec2::new(20.0, 0.0),
+            }]
+        );
+        assert_eq!(
+            replay.touch(1, GlutinTouchPhase::Moved, 20.0, 15.0),
+            vec![Gesture::Pan {
+                phase: GesturePhase::Changed,
+                pos: Vec2::new(20.0, 15.0),
+                delta: Vec2::new(0.0, 15.0),
+            }]
+        );
+        // panning past the long press time fires no long press
+        assert!(replay.wait(1000).is_empty());
+        assert_eq!(
+            replay.touch(1, GlutinTouchPhase::Ended, 20.0, 15.0),
+            vec![Gesture::Pan {
+                phase: GesturePhase::Ended,
+                pos: Vec2::new(20.0, 15.0),
+                delta: Vec2::zero(),
+            }]
+        );
+    }
+
+    #[test]
+    fn pinch() {
+        let mut replay = Replay::new();
+        replay.touch(1, GlutinTouchPhase::Started, 0.0, 0.0);
+        let began = replay.touch(2, GlutinTouchPhase::Started, 100.0, 0.0);
+        assert_eq!(
+            began[0],
+            Gesture::Pinch {
+                phase: GesturePhase::Began,
+                center: Vec2::new(50.0, 0.0),
+                scale: 1.0,
+            }
+        );
+        let changed = replay.touch(2, GlutinTouchPhase::Moved, 200.0, 0.0);
+        match changed[0] {
+            Gesture::Pinch {
+                phase: GesturePhase::Changed,
+                center,
+                scale,
+            } => {
+                assert_eq!(center, Vec2::new(100.0, 0.0));
+                assert!(approx(scale, 2.0));
+            }
+            g => panic!("expected a pinch, got {:?}", g),
+        }
+        // scales are relative to the previous event
+        let changed = replay.touch(1, GlutinTouchPhase::Moved, 100.0, 0.0);
+        match changed[0] {
+            Gesture::Pinch { scale, .. } => assert!(approx(scale, 0.5)),
+            g => panic!("expected a pinch, got {:?}", g),
+        }
+        let ended = replay.touch(1, GlutinTouchPhase::Ended, 100.0, 0.0);
+        assert!(ended.iter().any(|g| match g {
+            Gesture::Pinch { phase, .. } => *phase == GesturePhase::Ended,
+            _ => false,
+        }));
+        // the finger left behind neither pans nor taps
+        assert!(replay
+            .touch(2, GlutinTouchPhase::Moved, 300.0, 0.0)
+            .is_empty());
+        assert!(replay
+            .touch(2, GlutinTouchPhase::Ended, 300.0, 0.0)
+            .is_empty());
+    }
+
+    #[test]
+    fn rotate() {
+        let mut replay = Replay::new();
+        let angles = |gestures: Vec<Gesture>| -> Vec<f32> {
+            gestures
+                .iter()
+                .filter_map(|g| match *g {
+                    Gesture::Rotate { angle, .. } => Some(angle),
+                    _ => None,
+                })
+                .collect()
+        };
+        replay.touch(1, GlutinTouchPhase::Started, -50.0, 0.0);
+        let began = replay.touch(2, GlutinTouchPhase::Started, 50.0, 0.0);
+        assert_eq!(
+            began[1],
+            Gesture::Rotate {
+                phase: GesturePhase::Began,
+                center: Vec2::zero(),
+                angle: 0.0,
+            }
+        );
+        // y points down, so positive angles turn clockwise on screen
+        let turned = angles(replay.touch(1, GlutinTouchPhase::Moved, 0.0, -50.0));
+        assert_eq!(turned.len(), 1);
+        assert!(approx(turned[0], PI / 4.0));
+        let changed = replay.touch(2, GlutinTouchPhase::Moved, 0.0, 50.0);
+        match changed[1] {
+            Gesture::Rotate {
+                phase: GesturePhase::Changed,
+                center,
+                angle,
+            } => {
+                assert_eq!(center, Vec2::zero());
+                assert!(approx(angle, PI / 4.0));
+            }
+            g => panic!("expected a rotation, got {:?}", g),
+        }
+    }
+
+    #[test]
+    fn rotation_wraps_around_half_turns() {
+        let mut replay = Replay::new();
+        replay.touch(1, GlutinTouchPhase::Started, 50.0, 0.0);
+        replay.touch(2, GlutinTouchPhase::Started, -50.0, 0.0);
+        let changed = replay.touch(2, GlutinTouchPhase::Moved, -50.0, -1.0);
+        match changed[1] {
+            Gesture::Rotate { angle, .. } => assert!(angle.abs() < 0.02),
+            g => panic!("expected a rotation, got {:?}", g),
+        }
+    }
+
+    #[test]
+    fn third_finger_joins_no_gesture() {
+        let mut replay = Replay::new();
+        replay.touch(1, GlutinTouchPhase::Started, 0.0, 0.0);
+        replay.touch(2, GlutinTouchPhase::Started, 100.0, 0.0);
+        assert!(replay
+            .touch(3, GlutinTouchPhase::Started, 50.0, 50.0)
+            .is_empty());
+        assert!(replay
+            .touch(3, GlutinTouchPhase::Moved, 50.0, 90.0)
+            .is_empty());
+        assert_eq!(replay.recognizer.touch_count(), 3);
+    }
+}
@@ -1,11 +1,14 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::time::Duration;
 use std::time::Instant;
 use std::io::Write;
 use super::gpuclock::*;
 
+/// Samples kept per name, older ones get dropped while profiling runs on
+const MAX_SAMPLES: usize = 1000;
+
 pub struct Profiler {
-    times: HashMap<String, Vec<Duration>>,
+    times: HashMap<String, VecDeque<Duration>>,
     clock: Instant,
     gpuclock: GPUClock,
     enabled: bool,
@@ -40,14 +43,22 @@ impl Profiler {
     
     pub fn end(&mut self) {
         if !self.enabled {return;}
-        let v = self.times.entry(self.name.clone()).or_insert(vec![]);
-        v.push(self.clock.elapsed());
+        let t = self.clock.elapsed();
+        self.record(self.name.clone(), t);
     }
     
     pub fn end_gpu(&mut self) {
         if !self.enabled {return;}
-        let v = self.times.entry(self.name_gpu.clone()).or_insert(vec![]);
-        v.push(self.gpuclock.time());
+        let t = self.gpuclock.time();
+        self.record(self.name_gpu.clone(), t);
+    }
+    
+    fn record(&mut self, name: String, t: Duration) {
+        let v = self.times.entry(name).or_default();
+        if v.len() == MAX_SAMPLES {
+            v.pop_front();
+        }
+        v.push_back(t);
     }
     
     pub fn set_enabled(&mut self, enabled: bool) {
@@ -59,6 +70,10 @@ impl Profiler {
     pub fn enabled(&self) -> bool {
         self.enabled
     }
+    /// Latest time measured for `name`
+    pub fn last(&self, name: &str) -> Option<Duration> {
+        self.times.get(name).and_then(|v| v.back()).copied()
+    }
     pub fn print(&self, file: &mut std::fs::File) -> std::io::Result<()> {
         for entry in &self.times {
             write!(file,"{}: [",entry.0)?;
@@ -88,6 +88,15 @@ impl Rect {
             bottom: self.bottom + o.y,
         }
     }
+    /// Overlap of the two rects, empty ones come out with a negative size
+    pub fn intersection(self, other: Rect) -> Rect {
+        Rect {
+            left: f32::max(self.left, other.left),
+            top: f32::max(self.top, other.top),
+            right: f32::min(self.right, other.right),
+            bottom: f32::min(self.bottom, other.bottom),
+        }
+    }
     pub fn scale(self, s: Vec2) -> Rect {
         Rect {
             left: self.left * s.x,
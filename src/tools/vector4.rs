@@ -91,6 +91,95 @@ impl Vec4 {
         self.x * 0.299 + self.y * 0.587 + self.z * 0.144
     }
 
+    /// hue, saturation and value in [0,1], hue wraps around
+    pub fn from_hsv(h: f32, s: f32, v: f32, a: f32) -> Vec4 {
+        let h = (h - h.floor()) * 6.0;
+        let f = h - h.floor();
+        let p = v * (1.0 - s);
+        let q = v * (1.0 - s * f);
+        let t = v * (1.0 - s * (1.0 - f));
+        match h as u32 {
+            0 => Vec4::new(v, t, p, a),
+            1 => Vec4::new(q, v, p, a),
+            2 => Vec4::new(p, v, t, a),
+            3 => Vec4::new(p, q, v, a),
+            4 => Vec4::new(t, p, v, a),
+            _ => Vec4::new(v, p, q, a),
+        }
+    }
+    /// (hue, saturation, value, alpha) of the color, hue is 0 for greys
+    pub fn to_hsv(self) -> Vec4 {
+        let mx = self.x.max(self.y).max(self.z);
+        let mn = self.x.min(self.y).min(self.z);
+        let d = mx - mn;
+        let h = if d <= 0.0 {
+            0.0
+        } else if mx == self.x {
+            ((self.y - self.z) / d + 6.0) % 6.0
+        } else if mx == self.y {
+            (self.z - self.x) / d + 2.0
+        } else {
+            (self.x - self.y) / d + 4.0
+        };
+        let s = if mx <= 0.0 { 0.0 } else { d / mx };
+        Vec4::new(h / 6.0, s, mx, self.w)
+    }
+
+    pub fn srgb_to_linear(self) -> Vec4 {
+        let f = |c: f32| {
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        Vec4::new(f(self.x), f(self.y), f(self.z), self.w)
+    }
+    pub fn linear_to_srgb(self) -> Vec4 {
+        let f = |c: f32| {
+            if c <= 0.003_130_8 {
+                c * 12.92
+            } else {
+                1.055 * c.powf(1.0 / 2.4) - 0.055
+            }
+        };
+        Vec4::new(f(self.x), f(self.y), f(self.z), self.w)
+    }
+
+    /// parses #RGB, #RGBA, #RRGGBB and #RRGGBBAA, the # is optional
+    pub fn from_hex(hex: &str) -> Option<Vec4> {
+        let hex = hex.trim().trim_start_matches('#');
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let digits: Option<Vec<u8>> = match hex.len() {
+            3 | 4 => hex
+                .chars()
+                .map(|c| c.to_digit(16).map(|d| d as u8 * 17))
+                .collect(),
+            6 | 8 => (0..hex.len() / 2)
+                .map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok())
+                .collect(),
+            _ => None,
+        };
+        digits.map(|d| Vec4::from_bytes(d[0], d[1], d[2], *d.get(3).unwrap_or(&255)))
+    }
+    /// #RRGGBB for opaque colors, #RRGGBBAA otherwise
+    pub fn to_hex(self) -> String {
+        let b = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+        if b(self.w) == 255 {
+            format!("#{:02X}{:02X}{:02X}", b(self.x), b(self.y), b(self.z))
+        } else {
+            format!(
+                "#{:02X}{:02X}{:02X}{:02X}",
+                b(self.x),
+                b(self.y),
+                b(self.z),
+                b(self.w)
+            )
+        }
+    }
+
     pub const WHITE: Vec4 = Vec4 {
         x: 1.0,
         y: 1.0,